@@ -17,19 +17,23 @@ fn main() {
         .header("opus/include/opus.h")
         // Add the opus include directory
         .clang_arg("-Iopus/include")
-        // Only generate bindings for opus encoder functions
+        // Only generate bindings for opus encoder and decoder functions
         .allowlist_function("opus_encoder_.*")
         .allowlist_function("opus_encode.*")
+        .allowlist_function("opus_decoder_.*")
+        .allowlist_function("opus_decode.*")
         .allowlist_function("opus_strerror")
         .allowlist_function("opus_get_version_string")
         // Include the relevant types
         .allowlist_type("OpusEncoder")
+        .allowlist_type("OpusDecoder")
         // Include relevant constants
         .allowlist_var("OPUS_.*")
         // Include CTL requests
         .allowlist_var("OPUS_GET_LOOKAHEAD_REQUEST")
-        // Make OpusEncoder opaque since we only use it as a pointer
+        // Make OpusEncoder/OpusDecoder opaque since we only use them as pointers
         .opaque_type("OpusEncoder")
+        .opaque_type("OpusDecoder")
         // Tell cargo to invalidate the built crate whenever any of the included header files changed
         .parse_callbacks(Box::new(bindgen::CargoCallbacks::new()))
         // Finish the builder and generate the bindings
@@ -0,0 +1,204 @@
+//! Per-application profiles
+//!
+//! A profile applies its own rewrite prompt, rewrite setting and output mode
+//! while dictating into a given application, e.g. code-style rewriting in an
+//! editor and prose everywhere else. The focused application is captured
+//! when recording starts. On Linux it is read from the X11
+//! `_NET_ACTIVE_WINDOW` with `xprop`; other platforms always use the global
+//! settings for now.
+
+use serde::{Deserialize, Serialize};
+
+/// Settings used while dictating into one application
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct AppProfile {
+    /// Window class or process name, matched ignoring case
+    pub app: String,
+    pub prompt_id: String,
+    pub rewrite_enabled: bool,
+    pub output_mode: String,
+}
+
+/// The application that had focus when a recording started
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct FocusedApp {
+    /// X11 `WM_CLASS` names, instance first, e.g. ["code", "Code"]
+    pub classes: Vec<String>,
+    /// Executable name of the window's process, if known
+    pub process: Option<String>,
+}
+
+impl FocusedApp {
+    fn names(&self) -> impl Iterator<Item = &str> {
+        self.classes.iter().map(String::as_str).chain(self.process.as_deref())
+    }
+
+    /// Short name to show in logs and settings
+    pub fn name(&self) -> &str {
+        self.classes.last().map(String::as_str).or(self.process.as_deref()).unwrap_or("")
+    }
+}
+
+impl AppProfile {
+    pub fn matches(&self, app: &FocusedApp) -> bool {
+        let wanted = self.app.trim();
+        app.names().any(|name| name.eq_ignore_ascii_case(wanted))
+    }
+}
+
+/// The first profile for `app`
+pub fn find<'a>(profiles: &'a [AppProfile], app: &FocusedApp) -> Option<&'a AppProfile> {
+    profiles.iter().find(|profile| profile.matches(app))
+}
+
+/// Check profiles before they are saved; prompt ids and output modes are
+/// checked by the caller, which knows the valid ones
+pub fn validate(profiles: &[AppProfile]) -> Result<(), String> {
+    for (i, profile) in profiles.iter().enumerate() {
+        let app = profile.app.trim();
+        if app.is_empty() {
+            return Err("Every app profile needs an application name".to_string());
+        }
+        if profiles[..i].iter().any(|other| other.app.trim().eq_ignore_ascii_case(app)) {
+            return Err(format!("There is more than one profile for '{}'", app));
+        }
+    }
+    Ok(())
+}
+
+/// Id of the active window in `xprop -root _NET_ACTIVE_WINDOW` output
+fn parse_active_window(output: &str) -> Option<&str> {
+    let id = output.split("window id #").nth(1)?.split_whitespace().next()?;
+    // 0x0 when the desktop or nothing has focus
+    (u64::from_str_radix(id.trim_start_matches("0x"), 16).ok()? != 0).then_some(id)
+}
+
+/// `WM_CLASS` names and `_NET_WM_PID` from `xprop -id <window>` output
+fn parse_window_properties(output: &str) -> (Vec<String>, Option<u32>) {
+    let mut classes = Vec::new();
+    let mut pid = None;
+    for line in output.lines() {
+        let Some((name, value)) = line.split_once(" = ") else { continue };
+        if name.starts_with("WM_CLASS") {
+            classes = value
+                .split(',')
+                .map(|class| class.trim().trim_matches('"').to_string())
+                .filter(|class| !class.is_empty())
+                .collect();
+        } else if name.starts_with("_NET_WM_PID") {
+            pid = value.trim().parse().ok();
+        }
+    }
+    (classes, pid)
+}
+
+#[cfg(target_os = "linux")]
+fn xprop(args: &[&str]) -> Option<String> {
+    let output = std::process::Command::new("xprop").args(args).output().ok()?;
+    output.status.success().then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// The focused application, or `None` if it cannot be determined
+#[cfg(target_os = "linux")]
+pub fn focused_app() -> Option<FocusedApp> {
+    let root = xprop(&["-root", "_NET_ACTIVE_WINDOW"])?;
+    let window = parse_active_window(&root)?;
+    let (classes, pid) = parse_window_properties(&xprop(&["-id", window, "WM_CLASS", "_NET_WM_PID"])?);
+    let process = pid
+        .and_then(|pid| std::fs::read_to_string(format!("/proc/{}/comm", pid)).ok())
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty());
+    if classes.is_empty() && process.is_none() {
+        return None;
+    }
+    Some(FocusedApp { classes, process })
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn focused_app() -> Option<FocusedApp> {
+    None
+}
+
+/// Id of the focused window, or `None` if it cannot be determined
+#[cfg(target_os = "linux")]
+pub fn focused_window() -> Option<String> {
+    parse_active_window(&xprop(&["-root", "_NET_ACTIVE_WINDOW"])?).map(str::to_string)
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn focused_window() -> Option<String> {
+    None
+}
+
+/// `focused_app` on a blocking thread, since it waits for `xprop`
+pub async fn focused_app_async() -> Option<FocusedApp> {
+    tokio::task::spawn_blocking(focused_app).await.unwrap_or_else(|e| {
+        eprintln!("Failed to look up the focused app: {}", e);
+        None
+    })
+}
+
+/// `focused_window` on a blocking thread
+pub async fn focused_window_async() -> Option<String> {
+    tokio::task::spawn_blocking(focused_window).await.unwrap_or_else(|e| {
+        eprintln!("Failed to look up the focused window: {}", e);
+        None
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile(app: &str) -> AppProfile {
+        AppProfile {
+            app: app.to_string(),
+            prompt_id: "default".to_string(),
+            rewrite_enabled: true,
+            output_mode: "type".to_string(),
+        }
+    }
+
+    fn editor() -> FocusedApp {
+        FocusedApp {
+            classes: vec!["code".to_string(), "Code".to_string()],
+            process: Some("electron".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_parse_xprop_output() {
+        assert_eq!(parse_active_window("_NET_ACTIVE_WINDOW(WINDOW): window id # 0x3a00007\n"), Some("0x3a00007"));
+        assert_eq!(parse_active_window("_NET_ACTIVE_WINDOW(WINDOW): window id # 0x0\n"), None);
+        assert_eq!(parse_active_window("_NET_ACTIVE_WINDOW:  not found.\n"), None);
+
+        let (classes, pid) = parse_window_properties(
+            "WM_CLASS(STRING) = \"code\", \"Code\"\n_NET_WM_PID(CARDINAL) = 4242\n",
+        );
+        assert_eq!(classes, vec!["code", "Code"]);
+        assert_eq!(pid, Some(4242));
+
+        let (classes, pid) = parse_window_properties("WM_CLASS:  not found.\n_NET_WM_PID:  not found.\n");
+        assert!(classes.is_empty());
+        assert_eq!(pid, None);
+    }
+
+    #[test]
+    fn test_find_profile() {
+        let profiles = vec![profile("firefox"), profile(" CODE ")];
+        assert_eq!(find(&profiles, &editor()), Some(&profiles[1]));
+        assert_eq!(find(&profiles[..1], &editor()), None);
+
+        // The process name matches too
+        let by_process = vec![profile("Electron")];
+        assert!(find(&by_process, &editor()).is_some());
+        assert_eq!(editor().name(), "Code");
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(validate(&[profile("code"), profile("firefox")]).is_ok());
+        assert!(validate(&[profile("  ")]).is_err());
+        assert!(validate(&[profile("code"), profile("Code")]).unwrap_err().contains("more than one"));
+    }
+}
@@ -23,6 +23,8 @@ const DONE: &[u8] = include_bytes!("../sounds/done.wav");
 
 pub enum AudioPlaybackCommand {
     PlaySound(Vec<f32>),
+    /// Drop anything still queued and play these samples instead
+    Replace(Vec<f32>),
     Stop,
 }
 
@@ -78,6 +80,16 @@ impl AudioOutputManager {
             }
         };
 
+        self.send_command(AudioPlaybackCommand::PlaySound(samples));
+    }
+
+    /// Play 48 kHz mono samples, e.g. a saved recording, interrupting any
+    /// earlier playback
+    pub fn play_samples(&mut self, samples: Vec<f32>) {
+        self.send_command(AudioPlaybackCommand::Replace(samples));
+    }
+
+    fn send_command(&mut self, command: AudioPlaybackCommand) {
         if self.should_refresh_stream() {
             if let Err(e) = self.refresh_stream() {
                 eprintln!("Failed to refresh audio output stream: {}", e);
@@ -86,7 +98,7 @@ impl AudioOutputManager {
         }
 
         if let Some(ref stream) = self.active_stream {
-            if let Err(e) = stream.sender.send(AudioPlaybackCommand::PlaySound(samples)) {
+            if let Err(e) = stream.sender.send(command) {
                 eprintln!("Failed to send playback command: {}", e);
                 self.active_stream = None;
             } else {
@@ -236,6 +248,11 @@ fn create_output_stream(
                     let mut buf = playback_buffer_clone.lock().unwrap();
                     buf.extend_from_slice(&samples);
                 }
+                Ok(AudioPlaybackCommand::Replace(samples)) => {
+                    let mut buf = playback_buffer_clone.lock().unwrap();
+                    buf.clear();
+                    buf.extend_from_slice(&samples);
+                }
                 Ok(AudioPlaybackCommand::Stop) => break,
                 Err(_) => break,
             }
@@ -0,0 +1,180 @@
+//! Splitting long recordings into separately transcribed chunks
+//!
+//! Recordings over the duration or upload size limit are decoded back to PCM,
+//! cut at pauses by the silence segmenter and re-encoded as standalone WebM
+//! files. Splitting is deterministic, so a retry produces the same chunks and
+//! can reuse transcripts of chunks that already succeeded.
+
+use crate::opus::{OpusError, OpusFrameDecoder};
+use crate::segmenter::{SegmentConfig, SilenceSegmenter, DEFAULT_SILENCE_RMS};
+use crate::webm::{self, WebmWriter};
+
+/// Sample rate of recorded audio (Opus native rate)
+const SAMPLE_RATE: u32 = 48000;
+
+/// Samples in each packet written by `WebmWriter` (20ms frames)
+const SAMPLES_PER_PACKET: usize = 960;
+
+/// Bitrate for re-encoded chunks, same as recordings
+const BITRATE: i32 = 64000;
+
+/// Chunks are only cut at a pause once they are this long
+const CHUNK_MIN_SECS: f32 = 300.0;
+
+/// Chunks are cut here even without a pause; shorter recordings are sent whole
+const CHUNK_MAX_SECS: f32 = 600.0;
+
+/// Recordings larger than this are chunked regardless of length (OpenAI accepts 25 MB)
+const MAX_UPLOAD_BYTES: usize = 24 * 1024 * 1024;
+
+/// A standalone piece of a longer recording
+#[derive(Debug, Clone)]
+pub struct AudioChunk {
+    /// Offset of the chunk within the recording, in seconds
+    pub start_secs: f64,
+    /// Audio data (WebM format)
+    pub audio_data: Vec<u8>,
+}
+
+/// Split a recording that exceeds the length or upload size limit
+///
+/// Returns `None` if the recording can be transcribed in one request.
+/// Chunks that contain only silence are left out.
+pub fn split_long_recording(audio_data: &[u8]) -> Result<Option<Vec<AudioChunk>>, OpusError> {
+    let config = SegmentConfig::new(SAMPLE_RATE, CHUNK_MIN_SECS, CHUNK_MAX_SECS, DEFAULT_SILENCE_RMS);
+    split_recording(audio_data, config, MAX_UPLOAD_BYTES)
+}
+
+fn split_recording(
+    audio_data: &[u8],
+    config: SegmentConfig,
+    max_bytes: usize,
+) -> Result<Option<Vec<AudioChunk>>, OpusError> {
+    let track = webm::read_opus_track(audio_data)?;
+    let total_samples = track.packets.len() * SAMPLES_PER_PACKET;
+    if total_samples <= config.max_samples && audio_data.len() <= max_bytes {
+        return Ok(None);
+    }
+
+    println!(
+        "Splitting {:.1}s recording ({} bytes) into chunks",
+        total_samples as f32 / SAMPLE_RATE as f32,
+        audio_data.len()
+    );
+
+    let mut decoder = OpusFrameDecoder::new()?;
+    let mut segmenter = SilenceSegmenter::new(config);
+    let mut writer = WebmWriter::new(BITRATE)?;
+    let mut chunks = Vec::new();
+    let mut preskip = track.preskip as usize;
+    // Decoded samples before the current packet, and before the current chunk
+    let mut position = 0;
+    let mut chunk_start = 0;
+
+    for packet in &track.packets {
+        let decoded = decoder.decode_f32(packet)?;
+
+        // Drop the encoder lookahead at the start of the recording
+        let skip = preskip.min(decoded.len());
+        preskip -= skip;
+        let pcm = &decoded[skip..];
+
+        let mut start = 0;
+        for cut in segmenter.push(pcm) {
+            writer.add_samples_f32(&pcm[start..cut.offset])?;
+            let finished = std::mem::replace(&mut writer, WebmWriter::new(BITRATE)?);
+            if cut.voiced {
+                chunks.push(AudioChunk {
+                    start_secs: chunk_start as f64 / SAMPLE_RATE as f64,
+                    audio_data: finished.finalize()?,
+                });
+            }
+            chunk_start = position + cut.offset;
+            start = cut.offset;
+        }
+        writer.add_samples_f32(&pcm[start..])?;
+        position += pcm.len();
+    }
+
+    if segmenter.segment_len() > 0 && segmenter.segment_voiced() {
+        chunks.push(AudioChunk {
+            start_secs: chunk_start as f64 / SAMPLE_RATE as f64,
+            audio_data: writer.finalize()?,
+        });
+    }
+
+    println!("Recording split into {} chunks", chunks.len());
+    Ok(Some(chunks))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn speech(secs: f32) -> Vec<f32> {
+        let len = (SAMPLE_RATE as f32 * secs) as usize;
+        (0..len).map(|i| (i as f32 * 0.03).sin() * 0.4).collect()
+    }
+
+    fn pause(secs: f32) -> Vec<f32> {
+        // Low-level noise rather than digital silence, like a real microphone
+        let len = (SAMPLE_RATE as f32 * secs) as usize;
+        (0..len).map(|i| (i as f32 * 1.7).sin() * 0.001).collect()
+    }
+
+    fn encode(parts: &[Vec<f32>]) -> Vec<u8> {
+        let mut writer = WebmWriter::new(BITRATE).unwrap();
+        for part in parts {
+            writer.add_samples_f32(part).unwrap();
+        }
+        writer.finalize().unwrap()
+    }
+
+    fn test_config() -> SegmentConfig {
+        SegmentConfig::new(SAMPLE_RATE, 1.5, 3.0, DEFAULT_SILENCE_RMS)
+    }
+
+    #[test]
+    fn test_short_recording_not_split() {
+        let audio = encode(&[speech(1.0), pause(0.5), speech(1.0)]);
+        assert!(split_recording(&audio, test_config(), usize::MAX).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_split_at_pauses() {
+        let audio = encode(&[
+            speech(2.0),
+            pause(1.0),
+            speech(2.0),
+            pause(1.0),
+            speech(1.0),
+        ]);
+        let chunks = split_recording(&audio, test_config(), usize::MAX).unwrap().unwrap();
+        assert_eq!(chunks.len(), 3);
+
+        // Every chunk is a standalone recording
+        for chunk in &chunks {
+            let track = webm::read_opus_track(&chunk.audio_data).unwrap();
+            assert!(!track.packets.is_empty());
+        }
+
+        // Chunks start after each pause that ended the previous one
+        assert_eq!(chunks[0].start_secs, 0.0);
+        assert!(chunks[1].start_secs > 2.0 && chunks[1].start_secs < 3.0);
+        assert!(chunks[2].start_secs > 5.0 && chunks[2].start_secs < 6.0);
+    }
+
+    #[test]
+    fn test_silent_chunks_dropped() {
+        let audio = encode(&[speech(2.0), pause(5.0), speech(2.0)]);
+        let chunks = split_recording(&audio, test_config(), usize::MAX).unwrap().unwrap();
+        assert_eq!(chunks.len(), 2);
+    }
+
+    #[test]
+    fn test_split_by_size() {
+        let audio = encode(&[speech(2.0), pause(1.0), speech(1.0)]);
+        let chunks = split_recording(&audio, test_config(), audio.len() - 1).unwrap().unwrap();
+        assert_eq!(chunks.len(), 2);
+    }
+}
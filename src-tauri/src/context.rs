@@ -0,0 +1,120 @@
+//! Conversation context from recent dictation
+//!
+//! Sentences dictated over several recordings lose continuity when each one
+//! is transcribed on its own. With context on, the last few history entries
+//! are sent along: as the transcription prompt, which models read as the
+//! text preceding the audio, and as a block of earlier text in the rewrite
+//! request. Entries older than the expiry window are left out, so context
+//! does not carry over into an unrelated task.
+
+/// Upper bound for the number of context entries
+pub const MAX_ENTRIES: u32 = 10;
+
+/// Upper bound for the expiry window
+pub const MAX_WINDOW_MINUTES: u32 = 120;
+
+/// Context characters kept in the transcription prompt; Whisper only reads
+/// the last 224 tokens of a prompt
+const MAX_PROMPT_CHARS: usize = 800;
+
+/// Check context settings before they are saved; 0 entries turns context off
+pub fn validate_settings(entries: u32, window_minutes: u32) -> Result<(), String> {
+    if entries > MAX_ENTRIES {
+        return Err(format!("Context entries must be at most {}, got {}", MAX_ENTRIES, entries));
+    }
+    if window_minutes == 0 || window_minutes > MAX_WINDOW_MINUTES {
+        return Err(format!(
+            "Context window must be between 1 and {} minutes, got {}",
+            MAX_WINDOW_MINUTES, window_minutes
+        ));
+    }
+    Ok(())
+}
+
+/// Texts of the last `entries` history entries at most `window_minutes` old,
+/// oldest first
+///
+/// `history` yields `(timestamp, text)` in the order entries were added, with
+/// timestamps in seconds.
+pub fn recent<'a>(
+    history: impl DoubleEndedIterator<Item = (u64, &'a str)>,
+    now: u64,
+    entries: u32,
+    window_minutes: u32,
+) -> Vec<String> {
+    let since = now.saturating_sub(u64::from(window_minutes) * 60);
+    let mut texts: Vec<String> = history
+        .rev()
+        .take_while(|(timestamp, _)| *timestamp >= since)
+        .map(|(_, text)| text.trim())
+        .filter(|text| !text.is_empty())
+        .take(entries as usize)
+        .map(str::to_string)
+        .collect();
+    texts.reverse();
+    texts
+}
+
+/// Transcription prompt: the vocabulary prompt, then the end of the context
+///
+/// The context goes last since it is read as the text right before the
+/// recording.
+pub fn transcription_prompt(prompt: Option<&str>, context: &[String]) -> Option<String> {
+    let context = context.join(" ");
+    let mut tail = context.as_str();
+    if let Some((start, _)) = context.char_indices().rev().nth(MAX_PROMPT_CHARS - 1) {
+        // Start at a word so the prompt does not open with half of one
+        tail = &context[start..];
+        if let Some(space) = tail.find(' ') {
+            tail = &tail[space + 1..];
+        }
+    }
+    match (prompt, tail.is_empty()) {
+        (None, true) => None,
+        (Some(prompt), true) => Some(prompt.to_string()),
+        (None, false) => Some(tail.to_string()),
+        (Some(prompt), false) => Some(format!("{}\n\n{}", prompt, tail)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_settings() {
+        assert!(validate_settings(0, 5).is_ok());
+        assert!(validate_settings(MAX_ENTRIES + 1, 5).is_err());
+        assert!(validate_settings(3, 0).is_err());
+        assert!(validate_settings(3, MAX_WINDOW_MINUTES + 1).is_err());
+    }
+
+    #[test]
+    fn test_recent() {
+        let history = [(100, "old task"), (900, "First part."), (950, "  "), (1000, "second part")];
+        let recent = |entries, window| recent(history.iter().copied(), 1060, entries, window);
+        assert_eq!(recent(3, 5), vec!["First part.", "second part"]);
+        assert_eq!(recent(1, 5), vec!["second part"]);
+        assert!(recent(0, 5).is_empty());
+        // Anything older than the window ends the context
+        assert_eq!(recent(5, 1), vec!["second part"]);
+        assert_eq!(recent(5, 60).len(), 3);
+    }
+
+    #[test]
+    fn test_transcription_prompt() {
+        let context = vec!["We met Anya.".to_string(), "She said".to_string()];
+        assert_eq!(transcription_prompt(None, &[]), None);
+        assert_eq!(transcription_prompt(Some("MuseVoice"), &[]).as_deref(), Some("MuseVoice"));
+        assert_eq!(transcription_prompt(None, &context).as_deref(), Some("We met Anya. She said"));
+        assert_eq!(
+            transcription_prompt(Some("MuseVoice"), &context).as_deref(),
+            Some("MuseVoice\n\nWe met Anya. She said")
+        );
+
+        // Long context keeps its end, from a word boundary
+        let long = vec!["word ".repeat(MAX_PROMPT_CHARS) + "last"];
+        let prompt = transcription_prompt(None, &long).unwrap();
+        assert!(prompt.len() < MAX_PROMPT_CHARS && prompt.starts_with("word ") && prompt.ends_with("last"));
+    }
+}
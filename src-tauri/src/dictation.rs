@@ -0,0 +1,441 @@
+//! Spoken dictation commands
+//!
+//! Applies the common formatting commands locally, so plain dictation does
+//! not need the rewrite model: "comma" becomes ",", "new paragraph" starts a
+//! paragraph, "alpha bravo charlie" spells "ABC", "camel case user name"
+//! becomes "userName" and "no space hello there" becomes "hellothere".
+//!
+//! Commands are matched on words with case and punctuation ignored, since
+//! transcription models often write "Comma," or "New line." for them. Words
+//! that are also ordinary nouns, "period", "full stop" and "colon", only count
+//! at the end of a clause, so "the trial period ended" is left alone. Each
+//! rule can be turned on separately; all are off by default.
+
+use serde::{Deserialize, Serialize};
+
+/// Which commands are applied
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct DictationRules {
+    /// "comma"
+    pub comma: bool,
+    /// "period" and "full stop"
+    pub period: bool,
+    /// "question mark"
+    pub question_mark: bool,
+    /// "exclamation mark" and "exclamation point"
+    pub exclamation_mark: bool,
+    /// "colon"
+    pub colon: bool,
+    /// "semicolon"
+    pub semicolon: bool,
+    /// "new line"
+    pub new_line: bool,
+    /// "new paragraph"
+    pub new_paragraph: bool,
+    /// Runs of two or more NATO alphabet words, spelled in capitals
+    pub spelling: bool,
+    /// "camel case ..."
+    pub camel_case: bool,
+    /// "title case ..." and "pascal case ..."
+    pub title_case: bool,
+    /// "no space ..."
+    pub no_space: bool,
+}
+
+impl DictationRules {
+    pub fn any(&self) -> bool {
+        self.comma
+            || self.period
+            || self.question_mark
+            || self.exclamation_mark
+            || self.colon
+            || self.semicolon
+            || self.new_line
+            || self.new_paragraph
+            || self.spelling
+            || self.camel_case
+            || self.title_case
+            || self.no_space
+    }
+
+    fn enables(&self, rule: Rule) -> bool {
+        match rule {
+            Rule::Comma => self.comma,
+            Rule::Period => self.period,
+            Rule::QuestionMark => self.question_mark,
+            Rule::ExclamationMark => self.exclamation_mark,
+            Rule::Colon => self.colon,
+            Rule::Semicolon => self.semicolon,
+            Rule::NewLine => self.new_line,
+            Rule::NewParagraph => self.new_paragraph,
+            Rule::CamelCase => self.camel_case,
+            Rule::TitleCase => self.title_case,
+            Rule::NoSpace => self.no_space,
+        }
+    }
+}
+
+/// The setting that turns a spoken phrase on
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Rule {
+    Comma,
+    Period,
+    QuestionMark,
+    ExclamationMark,
+    Colon,
+    Semicolon,
+    NewLine,
+    NewParagraph,
+    CamelCase,
+    TitleCase,
+    NoSpace,
+}
+
+impl Rule {
+    /// Whether the phrase is also an ordinary word, and so only a command at
+    /// the end of a clause
+    fn needs_clause_end(self) -> bool {
+        matches!(self, Rule::Period | Rule::Colon)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Join {
+    Camel,
+    Title,
+    AsSpoken,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Command {
+    Symbol(&'static str),
+    Break(&'static str),
+    Join(Join),
+}
+
+/// Spoken phrases, as normalized words, what they do and the rule that
+/// turns them on
+const PHRASES: &[(&[&str], Command, Rule)] = &[
+    (&["comma"], Command::Symbol(","), Rule::Comma),
+    (&["period"], Command::Symbol("."), Rule::Period),
+    (&["full", "stop"], Command::Symbol("."), Rule::Period),
+    (&["question", "mark"], Command::Symbol("?"), Rule::QuestionMark),
+    (&["exclamation", "mark"], Command::Symbol("!"), Rule::ExclamationMark),
+    (&["exclamation", "point"], Command::Symbol("!"), Rule::ExclamationMark),
+    (&["colon"], Command::Symbol(":"), Rule::Colon),
+    (&["semicolon"], Command::Symbol(";"), Rule::Semicolon),
+    (&["semi", "colon"], Command::Symbol(";"), Rule::Semicolon),
+    (&["new", "line"], Command::Break("\n"), Rule::NewLine),
+    (&["newline"], Command::Break("\n"), Rule::NewLine),
+    (&["new", "paragraph"], Command::Break("\n\n"), Rule::NewParagraph),
+    (&["camel", "case"], Command::Join(Join::Camel), Rule::CamelCase),
+    (&["camelcase"], Command::Join(Join::Camel), Rule::CamelCase),
+    (&["title", "case"], Command::Join(Join::Title), Rule::TitleCase),
+    (&["pascal", "case"], Command::Join(Join::Title), Rule::TitleCase),
+    (&["no", "space"], Command::Join(Join::AsSpoken), Rule::NoSpace),
+];
+
+/// NATO alphabet words with their common spellings
+const NATO: &[(&str, char)] = &[
+    ("alpha", 'A'),
+    ("alfa", 'A'),
+    ("bravo", 'B'),
+    ("charlie", 'C'),
+    ("delta", 'D'),
+    ("echo", 'E'),
+    ("foxtrot", 'F'),
+    ("golf", 'G'),
+    ("hotel", 'H'),
+    ("india", 'I'),
+    ("juliet", 'J'),
+    ("juliett", 'J'),
+    ("kilo", 'K'),
+    ("lima", 'L'),
+    ("mike", 'M'),
+    ("november", 'N'),
+    ("oscar", 'O'),
+    ("papa", 'P'),
+    ("quebec", 'Q'),
+    ("romeo", 'R'),
+    ("sierra", 'S'),
+    ("tango", 'T'),
+    ("uniform", 'U'),
+    ("victor", 'V'),
+    ("whiskey", 'W'),
+    ("whisky", 'W'),
+    ("xray", 'X'),
+    ("yankee", 'Y'),
+    ("zulu", 'Z'),
+];
+
+/// Shortest run of NATO words that is spelled; single words such as "hotel"
+/// are far more often meant literally
+const MIN_SPELLING_RUN: usize = 2;
+
+/// Punctuation a command symbol replaces at the end of the previous word
+const TRAILING_PUNCTUATION: &[char] = &['.', ',', '?', '!', ':', ';'];
+
+struct Word<'a> {
+    raw: &'a str,
+    /// Lowercase letters and digits only, used for matching
+    key: String,
+}
+
+impl<'a> Word<'a> {
+    fn new(raw: &'a str) -> Self {
+        let key = raw
+            .chars()
+            .filter(|c| c.is_alphanumeric())
+            .flat_map(char::to_lowercase)
+            .collect();
+        Word { raw, key }
+    }
+
+    /// The word without surrounding punctuation
+    fn core(&self) -> &'a str {
+        self.raw.trim_matches(|c: char| !c.is_alphanumeric())
+    }
+
+    /// Punctuation after the word, e.g. the "." of "there."
+    fn trailing(&self) -> &'a str {
+        &self.raw[self.raw.trim_end_matches(|c: char| !c.is_alphanumeric()).len()..]
+    }
+}
+
+/// The enabled command whose phrase starts at `words[0]`, and its length
+fn match_command(words: &[Word], rules: &DictationRules) -> Option<(Command, usize)> {
+    PHRASES
+        .iter()
+        .filter(|(phrase, _, rule)| {
+            rules.enables(*rule)
+                && phrase.len() <= words.len()
+                && phrase.iter().zip(words).all(|(spoken, word)| *spoken == word.key)
+                && (!rule.needs_clause_end() || ends_clause(&words[phrase.len()..], &words[phrase.len() - 1], rules))
+        })
+        .max_by_key(|(phrase, _, _)| phrase.len())
+        .map(|(phrase, command, _)| (*command, phrase.len()))
+}
+
+/// Whether a phrase ending in `last` and followed by `rest` ends a clause:
+/// it ends the line, the transcription model put punctuation after it, or
+/// another command follows
+fn ends_clause(rest: &[Word], last: &Word, rules: &DictationRules) -> bool {
+    rest.is_empty() || !last.trailing().is_empty() || match_command(rest, rules).is_some()
+}
+
+fn nato_letter(word: &Word) -> Option<char> {
+    NATO.iter().find(|(name, _)| *name == word.key).map(|(_, letter)| *letter)
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars.flat_map(char::to_lowercase)).collect(),
+        None => String::new(),
+    }
+}
+
+fn join_words(words: &[&str], join: Join) -> String {
+    words
+        .iter()
+        .enumerate()
+        .map(|(i, word)| match join {
+            Join::Camel if i == 0 => word.to_lowercase(),
+            Join::Camel | Join::Title => capitalize(word),
+            Join::AsSpoken => word.to_string(),
+        })
+        .collect()
+}
+
+/// Text being built, tracking how the next word attaches
+#[derive(Default)]
+struct Output {
+    text: String,
+    capitalize_next: bool,
+}
+
+impl Output {
+    fn word(&mut self, word: &str) {
+        if !self.text.is_empty() && !self.text.ends_with('\n') {
+            self.text.push(' ');
+        }
+        if std::mem::take(&mut self.capitalize_next) {
+            let mut chars = word.chars();
+            if let Some(first) = chars.next() {
+                self.text.extend(first.to_uppercase());
+                self.text.push_str(chars.as_str());
+                return;
+            }
+        }
+        self.text.push_str(word);
+    }
+
+    fn symbol(&mut self, symbol: &str) {
+        // Replaces punctuation the transcription model guessed for the pause
+        let kept = self.text.trim_end().trim_end_matches(TRAILING_PUNCTUATION).len();
+        self.text.truncate(kept);
+        self.text.push_str(symbol);
+        self.capitalize_next = matches!(symbol, "." | "?" | "!");
+    }
+
+    fn line_break(&mut self, separator: &str) {
+        let kept = self.text.trim_end_matches(' ').len();
+        self.text.truncate(kept);
+        self.text.push_str(separator);
+        self.capitalize_next = true;
+    }
+}
+
+fn apply_line(line: &str, rules: &DictationRules) -> String {
+    let words: Vec<Word> = line.split_whitespace().map(Word::new).collect();
+    let mut output = Output::default();
+    let mut i = 0;
+
+    while i < words.len() {
+        if let Some((command, length)) = match_command(&words[i..], rules) {
+            i += length;
+            match command {
+                Command::Symbol(symbol) => output.symbol(symbol),
+                Command::Break(separator) => output.line_break(separator),
+                Command::Join(join) => {
+                    // Joins words up to the next command or the end of the clause
+                    let start = i;
+                    while i < words.len() && match_command(&words[i..], rules).is_none() {
+                        i += 1;
+                        if !words[i - 1].trailing().is_empty() {
+                            break;
+                        }
+                    }
+                    let span = &words[start..i];
+                    let cores: Vec<&str> = span.iter().map(Word::core).filter(|c| !c.is_empty()).collect();
+                    if !cores.is_empty() {
+                        let trailing = span.last().map_or("", Word::trailing);
+                        output.word(&format!("{}{}", join_words(&cores, join), trailing));
+                    }
+                }
+            }
+            continue;
+        }
+
+        if rules.spelling {
+            let run = words[i..].iter().map_while(nato_letter).count();
+            if run >= MIN_SPELLING_RUN {
+                let letters: String = words[i..i + run].iter().filter_map(nato_letter).collect();
+                output.word(&format!("{}{}", letters, words[i + run - 1].trailing()));
+                i += run;
+                continue;
+            }
+        }
+
+        output.word(words[i].raw);
+        i += 1;
+    }
+    output.text
+}
+
+/// Apply the enabled dictation commands to a transcript
+///
+/// Lines are processed separately, so existing line breaks such as those
+/// between speaker turns are kept.
+pub fn apply(text: &str, rules: &DictationRules) -> String {
+    if !rules.any() {
+        return text.to_string();
+    }
+    text.split('\n').map(|line| apply_line(line, rules)).collect::<Vec<_>>().join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn all_rules() -> DictationRules {
+        DictationRules {
+            comma: true,
+            period: true,
+            question_mark: true,
+            exclamation_mark: true,
+            colon: true,
+            semicolon: true,
+            new_line: true,
+            new_paragraph: true,
+            spelling: true,
+            camel_case: true,
+            title_case: true,
+            no_space: true,
+        }
+    }
+
+    #[test]
+    fn test_punctuation() {
+        let rules = all_rules();
+        assert_eq!(apply("hello comma how are you question mark", &rules), "hello, how are you?");
+        // Punctuation guessed by the transcription model is replaced
+        assert_eq!(apply("Hello, comma, world. Full stop.", &rules), "Hello, world.");
+        assert_eq!(apply("it works exclamation point then more", &rules), "it works! Then more");
+        assert_eq!(apply("items colon. one semi colon two", &rules), "items: one; two");
+    }
+
+    #[test]
+    fn test_ordinary_words_only_end_clauses() {
+        let rules = all_rules();
+        assert_eq!(apply("the trial period ended", &rules), "the trial period ended");
+        assert_eq!(apply("use a colon here", &rules), "use a colon here");
+        assert_eq!(apply("we came to a full stop sign", &rules), "we came to a full stop sign");
+        // At the end, before punctuation the model added or before another command
+        assert_eq!(apply("that is all period", &rules), "that is all.");
+        assert_eq!(apply("Dear Sam colon, thanks", &rules), "Dear Sam: thanks");
+        assert_eq!(apply("done full stop new paragraph next", &rules), "done.\n\nNext");
+        assert_eq!(apply("Period.", &rules), ".");
+    }
+
+    #[test]
+    fn test_new_lines() {
+        let rules = all_rules();
+        assert_eq!(apply("Dear Sam, new line. thanks for the note", &rules), "Dear Sam,\nThanks for the note");
+        assert_eq!(apply("first part new paragraph second part", &rules), "first part\n\nSecond part");
+        assert_eq!(apply("one newline two", &rules), "one\nTwo");
+    }
+
+    #[test]
+    fn test_spelling() {
+        let rules = all_rules();
+        assert_eq!(apply("my code is alpha bravo charlie", &rules), "my code is ABC");
+        assert_eq!(apply("Call sign X-ray, Yankee, Zulu.", &rules), "Call sign XYZ.");
+        // A single NATO word is usually meant literally
+        assert_eq!(apply("meet at the hotel lobby", &rules), "meet at the hotel lobby");
+    }
+
+    #[test]
+    fn test_casing_and_no_space() {
+        let rules = all_rules();
+        assert_eq!(apply("set camel case user name to five", &rules), "set userNameToFive");
+        assert_eq!(apply("set camel case user name, then save", &rules), "set userName, then save");
+        assert_eq!(apply("title case hello world comma done", &rules), "HelloWorld, done");
+        assert_eq!(apply("no space hello there", &rules), "hellothere");
+        assert_eq!(apply("trailing camel case", &rules), "trailing");
+    }
+
+    #[test]
+    fn test_rules_toggle_separately() {
+        let rules = DictationRules {
+            comma: true,
+            new_paragraph: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            apply("alpha bravo comma new line camel case x period", &rules),
+            "alpha bravo, new line camel case x period"
+        );
+        assert_eq!(apply("one new paragraph two", &rules), "one\n\nTwo");
+
+        let text = "Keep  this\nexactly comma as is";
+        assert_eq!(apply(text, &DictationRules::default()), text);
+    }
+
+    #[test]
+    fn test_keeps_speaker_lines() {
+        let rules = all_rules();
+        assert_eq!(apply("A: hi comma there\nB: fine period", &rules), "A: hi, there\nB: fine.");
+    }
+}
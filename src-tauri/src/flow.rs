@@ -1,17 +1,31 @@
-use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
-use cpal::{Device, Sample, SampleFormat, SampleRate, StreamConfig};
+use cpal::traits::{DeviceTrait, StreamTrait};
+use cpal::{Device, SampleFormat, SampleRate, StreamConfig};
 use crossbeam_channel::RecvTimeoutError;
 use serde::{Deserialize, Serialize};
 use std::env;
 use std::fs;
 use std::path::PathBuf;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
-use tokio::sync::{oneshot, RwLock};
+use tokio::sync::{mpsc, oneshot, RwLock, Semaphore};
+use tokio::task::{JoinHandle, JoinSet};
 use tokio_util::sync::CancellationToken;
-use crate::stream_processor::AudioStreamProcessor;
+use crate::stream_processor::{AudioStreamProcessor, EncodedAudio};
 use crate::audio_output::AudioOutputManager;
+use crate::chunking::{self, AudioChunk};
+use crate::context;
+use crate::dictation::{self, DictationRules};
+use crate::input_device::{self, DeviceEvent, DisconnectPolicy, RateConverter};
+use crate::replacements::{self, ReplacementRule};
+use crate::voice_edit::{self, EditCommand};
+use crate::retry::RetryPolicy;
+use crate::rewrite::{self, FailurePolicy, RewriteOutput, RewriteProvider, RewriteSettings};
+use crate::template::Variables;
+use crate::segmenter::{self, SegmentConfig, SilenceSegmenter, WINDOW_SIZE};
+use crate::transcript::{RewriteRecord, Transcript};
+use crate::transcription::{TranscriptionRequest, TranscriptionTarget};
+use crate::usage::FlowUsage;
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
@@ -33,30 +47,148 @@ pub enum FlowMode {
     RecordAndTranscribe {
         stop_signal: oneshot::Receiver<()>,
     },
+    /// Record a spoken instruction, such as "make this more formal", and
+    /// apply it to selected text
+    RecordCommand {
+        stop_signal: oneshot::Receiver<()>,
+        /// Text selected when recording started; the result replaces it
+        selection: String,
+    },
     /// Skip recording and encoding, transcribe existing audio data (WebM format)
     TranscribeOnly {
         audio_data: Vec<u8>,
+        /// Length of the recording, for usage accounting
+        duration_secs: f64,
+        /// Transcripts of chunks that succeeded in an earlier attempt
+        chunk_transcripts: Vec<Option<Transcript>>,
+        /// Selected text, when retrying a voice command
+        selection: Option<String>,
     },
 }
 
+impl FlowMode {
+    /// Selected text the recording is an instruction for
+    fn selection(&self) -> Option<String> {
+        match self {
+            FlowMode::RecordAndTranscribe { .. } => None,
+            FlowMode::RecordCommand { selection, .. } => Some(selection.clone()),
+            FlowMode::TranscribeOnly { selection, .. } => selection.clone(),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum FlowEvent {
     StateChanged(FlowState),
     SampleCount(usize),
-    TranscriptionResult(String),
+    TranscriptionResult(Transcript), // Final text, with segment and word timing of the raw transcription if requested
     AudioFileSaved(String), // Path to the saved audio file (WebM format)
-    AudioDataReady(Vec<u8>), // Audio buffer ready for transcription (WebM format, for retry functionality)
+    AudioDataReady { audio_data: Vec<u8>, duration_secs: f64 }, // Audio buffer ready for transcription (WebM format, for retry functionality)
     WaveformChunk { bins: Vec<f32>, avg_rms: f32 },
-    Error(String),
+    PartialTranscript { index: usize, text: String }, // Live transcript of one recorded segment
+    VoiceEdit { command: EditCommand, spoken: String }, // Edit command the transcript started with, and the words spoken for it
+    PartialRewrite(String), // Rewritten text received so far while the rewrite streams; the result may still differ
+    RewriteFailed(AudioError), // Rewrite failed or returned no text; sent unless the failure policy is silent
+    InputDevice(DeviceEvent), // The input device was lost or replaced while recording
+    ChunkTranscripts(Vec<Option<Transcript>>), // Per-chunk results of a partially failed long recording, for retry
+    Usage(FlowUsage), // Audio length and rewrite tokens once transcribed, sent before the result or error
+    Error(AudioError),
 }
 
 pub type FlowCallback = Arc<dyn Fn(FlowEvent) + Send + Sync>;
 
-#[derive(Debug)]
+/// What went wrong, so the UI can react differently to e.g. a bad key and a rate limit
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorKind {
+    /// API key missing, invalid or without permission (HTTP 401/403)
+    Auth,
+    /// Too many requests (HTTP 429)
+    RateLimited,
+    /// The request timed out
+    Timeout,
+    /// The server could not be reached or the connection dropped
+    Network,
+    /// The server failed (HTTP 5xx)
+    Server,
+    /// The server rejected the request (other 4xx), e.g. an unknown model
+    BadRequest,
+    /// The response could not be understood
+    InvalidResponse,
+    /// No usable input device, or the audio stream failed
+    Device,
+    /// Encoding, decoding or resampling audio failed
+    Audio,
+    /// Missing or invalid settings
+    Config,
+    /// Stopped by the user
+    Cancelled,
+    Internal,
+}
+
+impl ErrorKind {
+    /// Kind for a non-success HTTP status
+    pub fn from_status(status: reqwest::StatusCode) -> Self {
+        match status.as_u16() {
+            401 | 403 => ErrorKind::Auth,
+            408 => ErrorKind::Timeout,
+            429 => ErrorKind::RateLimited,
+            500..=599 => ErrorKind::Server,
+            _ => ErrorKind::BadRequest,
+        }
+    }
+
+    /// Whether the same request may succeed if sent again later
+    pub fn is_transient(self) -> bool {
+        matches!(
+            self,
+            ErrorKind::RateLimited | ErrorKind::Timeout | ErrorKind::Network | ErrorKind::Server
+        )
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
 pub struct AudioError {
+    pub kind: ErrorKind,
     pub message: String,
 }
 
+impl AudioError {
+    pub fn new(kind: ErrorKind, message: impl Into<String>) -> Self {
+        AudioError {
+            kind,
+            message: message.into(),
+        }
+    }
+
+    pub fn cancelled(message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::Cancelled, message)
+    }
+
+    /// Error for a request that could not be sent or whose response could not be read
+    pub fn request(context: &str, e: reqwest::Error) -> Self {
+        let kind = if e.is_timeout() {
+            ErrorKind::Timeout
+        } else if e.is_decode() {
+            ErrorKind::InvalidResponse
+        } else if e.is_builder() {
+            ErrorKind::Internal
+        } else {
+            ErrorKind::Network
+        };
+        Self::new(kind, format!("{}: {}", context, e))
+    }
+
+    /// Error for a response with a non-success status
+    pub fn http_status(status: reqwest::StatusCode, message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::from_status(status), message)
+    }
+
+    pub fn is_transient(&self) -> bool {
+        self.kind.is_transient()
+    }
+}
+
 impl std::fmt::Display for AudioError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", self.message)
@@ -67,32 +199,90 @@ impl std::error::Error for AudioError {}
 
 impl From<cpal::BuildStreamError> for AudioError {
     fn from(e: cpal::BuildStreamError) -> Self {
-        AudioError {
-            message: format!("Stream build error: {}", e),
-        }
+        AudioError::new(ErrorKind::Device, format!("Stream build error: {}", e))
     }
 }
 
 impl From<cpal::PlayStreamError> for AudioError {
     fn from(e: cpal::PlayStreamError) -> Self {
-        AudioError {
-            message: format!("Stream play error: {}", e),
-        }
+        AudioError::new(ErrorKind::Device, format!("Stream play error: {}", e))
     }
 }
 
 impl From<cpal::DevicesError> for AudioError {
     fn from(e: cpal::DevicesError) -> Self {
-        AudioError {
-            message: format!("Device enumeration error: {}", e),
-        }
+        AudioError::new(ErrorKind::Device, format!("Device enumeration error: {}", e))
     }
 }
 
 impl From<cpal::SupportedStreamConfigsError> for AudioError {
     fn from(e: cpal::SupportedStreamConfigsError) -> Self {
-        AudioError {
-            message: format!("Stream config error: {}", e),
+        AudioError::new(ErrorKind::Device, format!("Stream config error: {}", e))
+    }
+}
+
+/// Per-flow settings snapshot taken from the flow manager when the flow starts
+#[derive(Debug, Clone)]
+pub struct FlowConfig {
+    pub rewrite_enabled: bool,
+    pub omit_final_punctuation: bool,
+    /// Spoken formatting commands applied locally before any rewrite
+    pub dictation_rules: DictationRules,
+    /// Treat "scratch that" and similar at the start as edits of earlier typed text
+    pub voice_edits: bool,
+    /// User find/replace rules applied to the final text
+    pub replacements: Vec<ReplacementRule>,
+    /// Id and template of the selected rewrite prompt
+    pub rewrite_prompt_id: String,
+    pub rewrite_prompt: String,
+    /// Values for the prompt's variables other than the transcript
+    pub prompt_variables: Variables,
+    /// Provider, model and request options of the rewrite step
+    pub rewrite: RewriteSettings,
+    /// Stream the rewrite and report the text as it arrives
+    pub stream_rewrite: bool,
+    /// Whether a failed rewrite falls back to the transcript or fails the flow
+    pub rewrite_failure_policy: FailurePolicy,
+    /// Global OpenAI key, empty to use `OPENAI_API_KEY`
+    pub api_key: String,
+    /// Transcribe silence-delimited segments while recording
+    pub live_transcription: bool,
+    /// ISO-639-1 language hint, `None` to auto-detect
+    pub language: Option<String>,
+    /// Vocabulary and spelling hints sent with every transcription request
+    pub transcription_prompt: Option<String>,
+    /// Recent transcripts, oldest first, sent as prior context with the
+    /// transcription and rewrite requests
+    pub context: Vec<String>,
+    /// Request segment and word timestamps
+    pub timestamps: bool,
+    /// Translate the speech into English with the backend instead of transcribing it
+    pub translate: bool,
+    /// Label speaker turns; needs a diarizing model
+    pub diarize: bool,
+    /// Language the rewrite step should produce; runs the rewrite even when
+    /// `rewrite_enabled` is off
+    pub rewrite_target_language: Option<String>,
+    /// Retries for transient failures of transcription and rewrite requests
+    pub retry: RetryPolicy,
+    /// Input devices to record from, in order of preference, before the
+    /// system default
+    pub input_devices: Vec<String>,
+    /// Whether losing the input device mid-recording switches to another
+    /// one, ends the recording or fails the flow
+    pub disconnect_policy: DisconnectPolicy,
+}
+
+impl FlowConfig {
+    fn transcription_request(&self, audio_data: Vec<u8>, model: &str) -> TranscriptionRequest {
+        TranscriptionRequest {
+            audio_data,
+            model: model.to_string(),
+            language: self.language.clone(),
+            prompt: context::transcription_prompt(self.transcription_prompt.as_deref(), &self.context),
+            timestamps: self.timestamps,
+            translate: self.translate,
+            diarize: self.diarize,
         }
     }
 }
@@ -101,26 +291,23 @@ pub struct Flow {
     state: Arc<RwLock<FlowState>>,
     callback: FlowCallback,
     cancellation_token: CancellationToken,
-    model: String,
-    rewrite_enabled: bool,
-    omit_final_punctuation: bool,
+    /// Selected backend and model first, then the fallbacks in the order they are tried
+    targets: Arc<Vec<TranscriptionTarget>>,
     audio_manager: Arc<Mutex<AudioOutputManager>>,
-    rewrite_prompt: String,
-    api_key: String,
+    config: Arc<FlowConfig>,
 }
 
 impl Flow {
-    pub fn new(callback: FlowCallback, model: String, rewrite_enabled: bool, omit_final_punctuation: bool, audio_manager: Arc<Mutex<AudioOutputManager>>, rewrite_prompt: String, api_key: String) -> Self {
+    /// `targets` must hold at least the selected backend and model
+    pub fn new(callback: FlowCallback, targets: Vec<TranscriptionTarget>, audio_manager: Arc<Mutex<AudioOutputManager>>, config: FlowConfig) -> Self {
+        assert!(!targets.is_empty(), "Flow needs at least one transcription target");
         Self {
             state: Arc::new(RwLock::new(FlowState::Idle)),
             callback,
             cancellation_token: CancellationToken::new(),
-            model,
-            rewrite_enabled,
-            omit_final_punctuation,
+            targets: Arc::new(targets),
             audio_manager,
-            rewrite_prompt,
-            api_key,
+            config: Arc::new(config),
         }
     }
 
@@ -178,26 +365,41 @@ impl Flow {
 
     /// Main flow method: either records and transcribes, or transcribes existing audio data
     pub async fn run(&self, mode: FlowMode) -> Result<(), AudioError> {
-        let audio_data = match mode {
-            FlowMode::RecordAndTranscribe { stop_signal } => {
+        let selection = mode.selection();
+        let (audio_data, duration_secs, live_task, chunk_transcripts) = match mode {
+            FlowMode::RecordAndTranscribe { stop_signal } | FlowMode::RecordCommand { stop_signal, .. } => {
                 // Set initial state and emit audio feedback for starting recording
                 self.play_sound("boowomp.mp3");
                 self.set_state(FlowState::Recording).await;
 
+                // In live mode, segments cut during recording are transcribed as they arrive
+                let (segment_sender, live_task) = if self.config.live_transcription {
+                    let (sender, receiver) = mpsc::unbounded_channel();
+                    let task = tokio::spawn(Self::transcribe_live_segments(
+                        Arc::clone(&self.targets),
+                        Arc::clone(&self.config),
+                        Arc::clone(&self.callback),
+                        self.cancellation_token.clone(),
+                        receiver,
+                    ));
+                    (Some(sender), Some(task))
+                } else {
+                    (None, None)
+                };
+
                 // Start streaming audio recording (now includes encoding)
-                let audio_data = match self.record_audio(stop_signal).await {
-                    Ok(data) => data,
+                let EncodedAudio { data: audio_data, duration_secs } = match self.record_audio(stop_signal, segment_sender).await {
+                    Ok(audio) => audio,
                     Err(e) => {
-                        self.play_sound("pipe.mp3");
-                        self.set_state(FlowState::Error).await;
-                        self.emit_event(FlowEvent::Error(e.message.clone()));
-                        return Err(e);
+                        if let Some(task) = &live_task {
+                            task.abort();
+                        }
+                        return self.fail(e).await;
                     }
                 };
 
                 // Check if cancelled
                 if self.cancellation_token.is_cancelled() {
-                    self.play_sound("pipe.mp3");
                     self.set_state(FlowState::Cancelled).await;
                     return Ok(());
                 }
@@ -207,65 +409,157 @@ impl Flow {
                 self.set_state(FlowState::Processing).await;
 
                 // Emit audio data for potential retry functionality
-                self.emit_event(FlowEvent::AudioDataReady(audio_data.clone()));
+                self.emit_event(FlowEvent::AudioDataReady {
+                    audio_data: audio_data.clone(),
+                    duration_secs,
+                });
 
                 // Save audio file to disk (fails gracefully if not possible)
                 if let Some(saved_path) = self.save_audio_file(&audio_data) {
                     self.emit_event(FlowEvent::AudioFileSaved(saved_path));
                 }
 
-                audio_data
+                (audio_data, duration_secs, live_task, Vec::new())
             }
-            FlowMode::TranscribeOnly { audio_data } => {
+            FlowMode::TranscribeOnly { audio_data, duration_secs, chunk_transcripts, .. } => {
                 // Set to processing state
                 self.set_state(FlowState::Processing).await;
 
                 // Check if cancelled
                 if self.cancellation_token.is_cancelled() {
-                    self.play_sound("pipe.mp3");
                     self.set_state(FlowState::Cancelled).await;
                     return Ok(());
                 }
 
-                audio_data
+                (audio_data, duration_secs, None, chunk_transcripts)
             }
         };
 
-        // Transcribe with OpenAI
-        let mut transcribed_text = match self.transcribe_audio(audio_data).await {
-            Ok(text) => text,
-            Err(e) => {
-                self.play_sound("pipe.mp3");
-                self.set_state(FlowState::Error).await;
-                self.emit_event(FlowEvent::Error(e.message.clone()));
-                return Err(e);
-            }
+        // Transcribe with the configured backend, reusing live segments if there are any
+        let transcription = match live_task {
+            Some(task) => self.finish_live_transcription(task, audio_data).await,
+            None => self.transcribe_audio(audio_data, chunk_transcripts).await,
+        };
+        let mut transcript = match transcription {
+            Ok(transcript) => transcript,
+            Err(e) => return self.fail(e).await,
+        };
+        transcript.raw_text = transcript.text.clone();
+
+        let mut usage = FlowUsage {
+            audio_secs: duration_secs,
+            transcription_model: transcript.model.clone(),
+            ..Default::default()
         };
 
-        // Apply rewriting if enabled
-        if self.rewrite_enabled {
+        if let Some(selection) = selection {
+            return self.finish_command(transcript, &selection, usage).await;
+        }
+
+        // Applied only once the flow succeeds, so a failed flow leaves typed
+        // text alone and its retry does not edit it twice
+        let mut voice_edit = None;
+        if self.config.voice_edits
+            && let Some((command, rest)) = voice_edit::parse(&transcript.text)
+        {
+            println!("Voice edit: {:?}", command);
+            let spoken = std::mem::replace(&mut transcript.text, rest);
+            voice_edit = Some(FlowEvent::VoiceEdit { command, spoken });
+        }
+
+        transcript.text = dictation::apply(&transcript.text, &self.config.dictation_rules);
+
+        // Apply rewriting if enabled or a translation is requested
+        let wants_rewrite = self.config.rewrite_enabled || self.config.rewrite_target_language.is_some();
+        if wants_rewrite && !transcript.text.is_empty() {
             println!("Rewrite enabled, attempting to rewrite transcribed text...");
-            match self.rewrite_transcribed_text(&transcribed_text).await {
-                Ok(rewritten_text) => {
+            match self.rewrite_transcribed_text(&transcript.text).await {
+                Ok((output, model)) => {
                     println!("Rewrite successful");
-                    transcribed_text = rewritten_text;
-                }
-                Err(e) => {
-                    eprintln!("Rewrite failed, using original transcription: {}", e.message);
+                    transcript.rewrite = Some(RewriteRecord {
+                        prompt_id: self.config.rewrite_enabled.then(|| self.config.rewrite_prompt_id.clone()),
+                        model: model.clone(),
+                        text: output.text.clone(),
+                    });
+                    transcript.text = output.text;
+                    usage.rewrite_model = Some(model);
+                    usage.rewrite_tokens = output.usage;
                 }
+                Err(e) if e.kind == ErrorKind::Cancelled => return self.fail_billed(e, usage).await,
+                Err(e) => match self.config.rewrite_failure_policy {
+                    FailurePolicy::FallbackSilent => {
+                        eprintln!("Rewrite failed, using original transcription: {}", e.message);
+                    }
+                    FailurePolicy::FallbackWarn => {
+                        eprintln!("Rewrite failed, using original transcription: {}", e.message);
+                        self.emit_event(FlowEvent::RewriteFailed(e));
+                    }
+                    FailurePolicy::Fail => {
+                        eprintln!("Rewrite failed: {}", e.message);
+                        self.emit_event(FlowEvent::RewriteFailed(e.clone()));
+                        return self.fail_billed(e, usage).await;
+                    }
+                },
             }
         }
 
-        if self.omit_final_punctuation {
-            transcribed_text = transcribed_text
+        transcript.text = replacements::apply(&self.config.replacements, &transcript.text);
+
+        if self.config.omit_final_punctuation {
+            transcript.text = transcript.text
                 .trim_end_matches(&['.', '!', '?', ';', ','][..])
                 .trim_end()
                 .to_string();
         }
 
+        if let Some(event) = voice_edit {
+            self.emit_event(event);
+        }
+        self.complete(transcript, usage).await
+    }
+
+    /// Apply the transcript as an instruction to the selected text and
+    /// report the result, which replaces the selection
+    ///
+    /// Pasting the instruction over the selection would lose it, so a failed
+    /// rewrite always fails the flow.
+    async fn finish_command(&self, mut transcript: Transcript, selection: &str, mut usage: FlowUsage) -> Result<(), AudioError> {
+        if transcript.text.trim().is_empty() {
+            // Nothing was said; the empty result leaves the selection alone
+            println!("No instruction heard, leaving the selection as it is");
+            transcript.text.clear();
+            return self.complete(transcript, usage).await;
+        }
+
+        println!("Applying voice command to the selection...");
+        let prompt = rewrite::build_command_prompt(&transcript.text, selection);
+        let (output, model) = match self.request_rewrite(&prompt).await {
+            Ok(result) => result,
+            Err(e) => return self.fail_billed(e, usage).await,
+        };
+        transcript.rewrite = Some(RewriteRecord {
+            prompt_id: None,
+            model: model.clone(),
+            text: output.text.clone(),
+        });
+        transcript.text = output.text;
+        usage.rewrite_model = Some(model);
+        usage.rewrite_tokens = output.usage;
+        self.complete(transcript, usage).await
+    }
+
+    /// Fail a flow whose transcription was already paid for; its usage still
+    /// counts toward the budget
+    async fn fail_billed(&self, error: AudioError, usage: FlowUsage) -> Result<(), AudioError> {
+        self.emit_event(FlowEvent::Usage(usage));
+        self.fail(error).await
+    }
+
+    async fn complete(&self, transcript: Transcript, usage: FlowUsage) -> Result<(), AudioError> {
         self.set_state(FlowState::Completed).await;
         self.play_sound("done.wav");
-        self.emit_event(FlowEvent::TranscriptionResult(transcribed_text));
+        self.emit_event(FlowEvent::Usage(usage));
+        self.emit_event(FlowEvent::TranscriptionResult(transcript));
         Ok(())
     }
 
@@ -274,8 +568,9 @@ impl Flow {
     async fn record_audio(
         &self,
         stop_signal: oneshot::Receiver<()>,
-    ) -> Result<Vec<u8>, AudioError> {
-        let device = Self::find_input_device()?;
+        segment_sender: Option<mpsc::UnboundedSender<AudioChunk>>,
+    ) -> Result<EncodedAudio, AudioError> {
+        let device = input_device::find(&self.config.input_devices)?;
         let (config, sample_format) = Self::get_best_config(&device)?;
         let sample_rate = config.sample_rate.0;
 
@@ -291,13 +586,15 @@ impl Flow {
 
         let callback = Arc::clone(&self.callback);
         let cancellation_token = self.cancellation_token.clone();
+        let input_devices = self.config.input_devices.clone();
+        let disconnect_policy = self.config.disconnect_policy;
 
         // Spawn the audio recording thread
         let _audio_handle = tokio::task::spawn_blocking(move || {
             Self::run_audio_recording_thread(
-                device,
-                config,
-                sample_format,
+                (device, config, sample_format),
+                input_devices,
+                disconnect_policy,
                 sample_sender,
                 stop_receiver,
                 audio_result_sender,
@@ -311,6 +608,7 @@ impl Flow {
             Self::run_processing_thread(
                 sample_rate,
                 sample_receiver_clone,
+                segment_sender,
             )
         });
 
@@ -330,7 +628,7 @@ impl Flow {
                 // Cancellation requested
                 _ = cancellation_token.cancelled() => {
                     println!("Recording cancelled");
-                    return Err(AudioError { message: "Recording cancelled".to_string() });
+                    return Err(AudioError::cancelled("Recording cancelled"));
                 }
 
                 // Audio thread finished
@@ -341,10 +639,10 @@ impl Flow {
                             break;
                         }
                         Ok(Err(e)) => {
-                            return Err(AudioError { message: format!("Audio thread error: {}", e) });
+                            return Err(AudioError::new(ErrorKind::Device, format!("Audio thread error: {}", e)));
                         }
                         Err(_) => {
-                            return Err(AudioError { message: "Audio thread communication error".to_string() });
+                            return Err(AudioError::new(ErrorKind::Internal, "Audio thread communication error"));
                         }
                     }
                 }
@@ -356,15 +654,15 @@ impl Flow {
 
         // Wait for processing thread to complete and return WebM data
         match processing_handle.await {
-            Ok(Ok(webm_data)) => {
-                println!("[Main Thread] Processing complete, WebM data ready: {} bytes", webm_data.len());
-Ok(webm_data)
+            Ok(Ok(audio)) => {
+                println!("[Main Thread] Processing complete, WebM data ready: {} bytes", audio.data.len());
+                Ok(audio)
             }
             Ok(Err(e)) => {
-                Err(AudioError { message: format!("Processing thread error: {}", e) })
+                Err(AudioError::new(ErrorKind::Audio, format!("Processing thread error: {}", e)))
             }
             Err(e) => {
-                Err(AudioError { message: format!("Processing thread join error: {}", e) })
+                Err(AudioError::new(ErrorKind::Internal, format!("Processing thread join error: {}", e)))
             }
         }
     }
@@ -373,8 +671,9 @@ Ok(webm_data)
     fn run_processing_thread(
         input_sample_rate: u32,
         sample_receiver: crossbeam_channel::Receiver<Vec<f32>>,
-    ) -> Result<Vec<u8>, String> {
-        (|| -> Result<Vec<u8>, String> {
+        segment_sender: Option<mpsc::UnboundedSender<AudioChunk>>,
+    ) -> Result<EncodedAudio, String> {
+        (|| -> Result<EncodedAudio, String> {
             // Calculate chunk size: 100ms of audio at input sample rate
             let chunk_size = ((input_sample_rate as f32 * 0.1) as usize).max(960);
 
@@ -388,6 +687,12 @@ Ok(webm_data)
                 chunk_size,
             ).map_err(|e| format!("Failed to create processor: {}", e))?;
 
+            // Separate encoder for live segments; the full recording is still kept for retry
+            let mut segment_encoder = match segment_sender {
+                Some(sender) => Some(SegmentEncoder::new(input_sample_rate, chunk_size, sender)?),
+                None => None,
+            };
+
             let mut last_stats_print = Instant::now();
             let stats_interval = Duration::from_secs(10);
             let mut total_received = 0usize;
@@ -404,6 +709,10 @@ Ok(webm_data)
                         processor.push_samples(&samples)
                             .map_err(|e| format!("Failed to process samples: {}", e))?;
 
+                        if let Some(encoder) = segment_encoder.as_mut() {
+                            encoder.push(&samples)?;
+                        }
+
                         // Print stats periodically
                         if last_stats_print.elapsed() >= stats_interval {
                             let stats = processor.stats();
@@ -436,262 +745,107 @@ Ok(webm_data)
                 }
             }
 
+            // Send the last live segment before finalizing the full recording
+            if let Some(encoder) = segment_encoder {
+                encoder.finish()?;
+            }
+
             // Finalize and return WebM data
-            let webm_data = processor.finalize()
+            let audio = processor.finalize()
                 .map_err(|e| format!("Failed to finalize processor: {}", e))?;
             
             println!("[Processing Thread] Total samples processed: {}", total_sample_count);
             println!("[Processing Thread] Expected duration: {:.2}s at {}Hz", 
                 total_sample_count as f64 / input_sample_rate as f64, input_sample_rate);
             
-            Ok(webm_data)
+            Ok(audio)
         })()
     }
 
     fn run_audio_recording_thread(
-        device: Device,
-        config: StreamConfig,
-        sample_format: SampleFormat,
+        input: OpenedInput,
+        input_devices: Vec<String>,
+        disconnect_policy: DisconnectPolicy,
         sample_sender: crossbeam_channel::Sender<Vec<f32>>,
-        stop_receiver: oneshot::Receiver<()>,
+        mut stop_receiver: oneshot::Receiver<()>,
         result_sender: oneshot::Sender<Result<(), String>>,
         callback: FlowCallback,
     ) {
         let result = (|| -> Result<(), String> {
-            let channels = config.channels;
-
-            // Track total samples captured
-            let total_captured = Arc::new(std::sync::atomic::AtomicUsize::new(0));
-            let total_captured_clone = total_captured.clone();
-
-            // Find the best supported config according to our preference order:
-            // 1. 48000 Hz f32
-            // 2. 48000 Hz i16
-            // 3. 48000 Hz i32
-            // 4. >48000 Hz f32, i16, i32 (lowest above 48000 preferred)
-            // 5. Any f32, i16, i32 (lowest sample rate preferred)
-            // Otherwise, bail.
-
-            // Use the provided config and sample format; only set buffer size here
-            let mut config = config.clone();
-            config.buffer_size = cpal::BufferSize::Fixed(2048);
-
-            let sample_format = sample_format;
-
-            let recording_active = Arc::new(AtomicBool::new(true));
-            let recording_active_clone = Arc::clone(&recording_active);
-
-            // Waveform computation state
-            let waveform_buf = Arc::new(Mutex::new(Vec::<f32>::new()));
-            let total_mono_captured = Arc::new(std::sync::atomic::AtomicUsize::new(0));
-            const WINDOW_SIZE: usize = 2048;
-            const BIN_SIZE: usize = 8;
-
-            let total_cap_f32 = total_captured_clone.clone();
-            let stream = match sample_format {
-                SampleFormat::F32 => {
-                    let first_callback = Arc::new(AtomicBool::new(true));
-                    let first_callback_clone = first_callback.clone();
-                    // Clones for callback and waveform state
-                    let cb = callback.clone();
-                    let wf_buf = waveform_buf.clone();
-                    let mono_count_f32 = total_mono_captured.clone();
-                    device.build_input_stream(
-                        &config,
-                        move |data: &[f32], _: &cpal::InputCallbackInfo| {
-                            if recording_active_clone.load(Ordering::Relaxed) {
-                                if first_callback_clone.load(Ordering::Relaxed) {
-                                    println!("[Audio Callback] First callback - data.len()={}, channels={}, samples_per_callback={}",
-                                        data.len(), channels, data.len() / channels as usize);
-                                    first_callback_clone.store(false, Ordering::Relaxed);
-                                }
-                                total_cap_f32.fetch_add(data.len(), Ordering::Relaxed);
-                                let mono_data = Self::mix_to_mono(data, channels);
-
-                                // Update mono sample count and emit
-                                mono_count_f32.fetch_add(mono_data.len(), Ordering::Relaxed);
-                                let count = mono_count_f32.load(Ordering::Relaxed);
-                                (cb)(FlowEvent::SampleCount(count));
-
-                                // Accumulate and emit waveform bins per 2048-sample window
-                                {
-                                    let mut buf = wf_buf.lock().unwrap();
-                                    buf.extend_from_slice(&mono_data);
-                                    while buf.len() >= WINDOW_SIZE {
-                                        let window = &buf[..WINDOW_SIZE];
-
-                                        // Compute bins (256 bins of 8 samples RMS) and avg RMS
-                                        let mut bins: Vec<f32> = Vec::with_capacity(WINDOW_SIZE / BIN_SIZE);
-                                        let mut sum_sq_total: f32 = 0.0;
-                                        for chunk in window.chunks(BIN_SIZE) {
-                                            let mut sum_sq = 0.0f32;
-                                            for &s in chunk {
-                                                let ss = s * s;
-                                                sum_sq += ss;
-                                                sum_sq_total += ss;
-                                            }
-                                            bins.push((sum_sq / BIN_SIZE as f32).sqrt());
-                                        }
-                                        let avg_rms = (sum_sq_total / WINDOW_SIZE as f32).sqrt();
-
-                                        (cb)(FlowEvent::WaveformChunk { bins, avg_rms });
-
-                                        // Remove processed window
-                                        buf.drain(..WINDOW_SIZE);
-                                    }
-                                }
-
-                                let _ = sample_sender.send(mono_data);
-                            }
-                        },
-                        |err| eprintln!("Audio stream error: {}", err),
-                        None,
-                    )
-                }
-                SampleFormat::I16 => {
-                    let total_cap_i16 = total_captured_clone.clone();
-                    let first_callback = Arc::new(AtomicBool::new(true));
-                    let first_callback_clone = first_callback.clone();
-                    // Clones for callback and waveform state
-                    let cb = callback.clone();
-                    let wf_buf = waveform_buf.clone();
-                    let mono_count_i16 = total_mono_captured.clone();
-                    device.build_input_stream(
-                        &config,
-                        move |data: &[i16], _: &cpal::InputCallbackInfo| {
-                            if recording_active_clone.load(Ordering::Relaxed) {
-                                if first_callback_clone.load(Ordering::Relaxed) {
-                                    println!("[Audio Callback] First callback - data.len()={}, channels={}, samples_per_callback={}",
-                                        data.len(), channels, data.len() / channels as usize);
-                                    first_callback_clone.store(false, Ordering::Relaxed);
-                                }
-                                total_cap_i16.fetch_add(data.len(), Ordering::Relaxed);
-                                let float_data: Vec<f32> =
-                                    data.iter().map(|&s| s.to_sample::<f32>()).collect();
-                                let mono_data = Self::mix_to_mono(&float_data, channels);
-
-                                // Update mono sample count and emit
-                                mono_count_i16.fetch_add(mono_data.len(), Ordering::Relaxed);
-                                let count = mono_count_i16.load(Ordering::Relaxed);
-                                (cb)(FlowEvent::SampleCount(count));
-
-                                // Accumulate and emit waveform bins per 2048-sample window
-                                {
-                                    let mut buf = wf_buf.lock().unwrap();
-                                    buf.extend_from_slice(&mono_data);
-                                    while buf.len() >= WINDOW_SIZE {
-                                        let window = &buf[..WINDOW_SIZE];
-
-                                        // Compute bins (256 bins of 8 samples RMS) and avg RMS
-                                        let mut bins: Vec<f32> = Vec::with_capacity(WINDOW_SIZE / BIN_SIZE);
-                                        let mut sum_sq_total: f32 = 0.0;
-                                        for chunk in window.chunks(BIN_SIZE) {
-                                            let mut sum_sq = 0.0f32;
-                                            for &s in chunk {
-                                                let ss = s * s;
-                                                sum_sq += ss;
-                                                sum_sq_total += ss;
-                                            }
-                                            bins.push((sum_sq / BIN_SIZE as f32).sqrt());
-                                        }
-                                        let avg_rms = (sum_sq_total / WINDOW_SIZE as f32).sqrt();
-
-                                        (cb)(FlowEvent::WaveformChunk { bins, avg_rms });
-
-                                        // Remove processed window
-                                        buf.drain(..WINDOW_SIZE);
-                                    }
-                                }
-
-                                let _ = sample_sender.send(mono_data);
-                            }
-                        },
-                        |err| eprintln!("Audio stream error: {}", err),
-                        None,
-                    )
-                }
-                SampleFormat::I32 => {
-                    let total_cap_i32 = total_captured_clone.clone();
-                    let first_callback = Arc::new(AtomicBool::new(true));
-                    let first_callback_clone = first_callback.clone();
-                    // Clones for callback and waveform state
-                    let cb = callback.clone();
-                    let wf_buf = waveform_buf.clone();
-                    let mono_count_i32 = total_mono_captured.clone();
-                    device.build_input_stream(
-                        &config,
-                        move |data: &[i32], _: &cpal::InputCallbackInfo| {
-                            if recording_active_clone.load(Ordering::Relaxed) {
-                                if first_callback_clone.load(Ordering::Relaxed) {
-                                    println!("[Audio Callback] First callback - data.len()={}, channels={}, samples_per_callback={}",
-                                        data.len(), channels, data.len() / channels as usize);
-                                    first_callback_clone.store(false, Ordering::Relaxed);
-                                }
-                                total_cap_i32.fetch_add(data.len(), Ordering::Relaxed);
-                                let float_data: Vec<f32> =
-                                    data.iter().map(|&s| s.to_sample::<f32>()).collect();
-                                let mono_data = Self::mix_to_mono(&float_data, channels);
-
-                                // Update mono sample count and emit
-                                mono_count_i32.fetch_add(mono_data.len(), Ordering::Relaxed);
-                                let count = mono_count_i32.load(Ordering::Relaxed);
-                                (cb)(FlowEvent::SampleCount(count));
-
-                                // Accumulate and emit waveform bins per 2048-sample window
-                                {
-                                    let mut buf = wf_buf.lock().unwrap();
-                                    buf.extend_from_slice(&mono_data);
-                                    while buf.len() >= WINDOW_SIZE {
-                                        let window = &buf[..WINDOW_SIZE];
-
-                                        // Compute bins (256 bins of 8 samples RMS) and avg RMS
-                                        let mut bins: Vec<f32> = Vec::with_capacity(WINDOW_SIZE / BIN_SIZE);
-                                        let mut sum_sq_total: f32 = 0.0;
-                                        for chunk in window.chunks(BIN_SIZE) {
-                                            let mut sum_sq = 0.0f32;
-                                            for &s in chunk {
-                                                let ss = s * s;
-                                                sum_sq += ss;
-                                                sum_sq_total += ss;
-                                            }
-                                            bins.push((sum_sq / BIN_SIZE as f32).sqrt());
-                                        }
-                                        let avg_rms = (sum_sq_total / WINDOW_SIZE as f32).sqrt();
-
-                                        (cb)(FlowEvent::WaveformChunk { bins, avg_rms });
-
-                                        // Remove processed window
-                                        buf.drain(..WINDOW_SIZE);
-                                    }
-                                }
-
-                                let _ = sample_sender.send(mono_data);
-                            }
-                        },
-                        |err| eprintln!("Audio stream error: {}", err),
-                        None,
-                    )
-                }
-                _ => {
-                    return Err("Unsupported sample format".to_string());
+            // The processing thread expects this rate; a device switched to
+            // later is converted to it
+            let recording_rate = input.1.sample_rate.0;
+
+            let capture = Capture {
+                active: Arc::new(AtomicBool::new(true)),
+                total_captured: Arc::new(AtomicUsize::new(0)),
+                total_mono_captured: Arc::new(AtomicUsize::new(0)),
+                waveform_buf: Arc::new(Mutex::new(Vec::new())),
+                sample_sender,
+                callback: callback.clone(),
+            };
+            // Stream errors, e.g. the device being unplugged, end up here
+            let (error_sender, error_receiver) = crossbeam_channel::unbounded::<cpal::StreamError>();
+
+            let mut stream = Self::start_input_stream(&input, recording_rate, &capture, &error_sender)?;
+            // Devices in a row that failed before delivering any audio
+            let mut failures = 0;
+            'recording: loop {
+                let captured_before = capture.total_mono_captured.load(Ordering::Relaxed);
+                let Some(message) = Self::wait_for_device_loss(&mut stop_receiver, &error_receiver) else {
+                    println!("Audio thread: Stop signal received");
+                    break;
+                };
+                drop(stream);
+                // A failing stream may report more than once
+                while error_receiver.try_recv().is_ok() {}
+
+                eprintln!("Input device lost: {}", message);
+                (callback)(FlowEvent::InputDevice(DeviceEvent::Lost { message: message.clone() }));
+                if capture.total_mono_captured.load(Ordering::Relaxed) > captured_before {
+                    failures = 0;
                 }
-            }
-            .map_err(|e| format!("Failed to build stream: {}", e))?;
 
-            // Start the stream
-            stream
-                .play()
-                .map_err(|e| format!("Failed to play stream: {}", e))?;
+                match disconnect_policy {
+                    DisconnectPolicy::Fail => return Err(format!("Input device failed: {}", message)),
+                    DisconnectPolicy::Stop => break,
+                    DisconnectPolicy::Reconnect => {}
+                }
 
-            // Wait for stop signal
-            let _ = stop_receiver.blocking_recv();
-            println!("Audio thread: Stop signal received");
+                // A device that cannot be started counts as lost as well
+                stream = loop {
+                    failures += 1;
+                    if failures > input_device::MAX_RECONNECTS {
+                        eprintln!("Input devices failed {} times in a row, ending the recording", failures - 1);
+                        (callback)(FlowEvent::InputDevice(DeviceEvent::Unavailable));
+                        break 'recording;
+                    }
+                    let (name, input) = match Self::reopen_input_device(&input_devices, &mut stop_receiver) {
+                        Reopened::Device(name, input) => (name, input),
+                        Reopened::Stopped => break 'recording,
+                        Reopened::Unavailable => {
+                            (callback)(FlowEvent::InputDevice(DeviceEvent::Unavailable));
+                            break 'recording;
+                        }
+                    };
+                    match Self::start_input_stream(&input, recording_rate, &capture, &error_sender) {
+                        Ok(stream) => {
+                            println!("Recording continues on {} at {} Hz", name, input.1.sample_rate.0);
+                            (callback)(FlowEvent::InputDevice(DeviceEvent::Reconnected { device: name }));
+                            break stream;
+                        }
+                        Err(message) => {
+                            eprintln!("Could not record from {}: {}", name, message);
+                            (callback)(FlowEvent::InputDevice(DeviceEvent::Lost { message }));
+                        }
+                    }
+                };
+            }
 
             // Stop recording
-            recording_active.store(false, Ordering::Relaxed);
-            drop(stream);
+            capture.active.store(false, Ordering::Relaxed);
 
-            let final_count = total_captured.load(Ordering::Relaxed);
+            let final_count = capture.total_captured.load(Ordering::Relaxed);
             println!("[Audio Thread] Total samples captured: {}", final_count);
 
             Ok(())
@@ -700,189 +854,459 @@ Ok(webm_data)
         let _ = result_sender.send(result);
     }
 
-    /// Rewrite transcribed text using GPT-5.4 to handle dictation issues
-    /// (phonetic alphabet, punctuation, formatting commands, etc.)
-    async fn rewrite_transcribed_text(&self, transcribed_text: &str) -> Result<String, AudioError> {
-        let api_key = if self.api_key.trim().is_empty() {
-            env::var("OPENAI_API_KEY").map_err(|_| AudioError {
-                message: "OPENAI_API_KEY environment variable not set".to_string(),
-            })?
-        } else {
-            self.api_key.clone()
-        };
+    /// Wait for the stop signal or for the input device to go away; returns
+    /// the stream error in the latter case
+    ///
+    /// Other stream errors, e.g. ALSA overruns, are only logged, since the
+    /// stream keeps running after them.
+    fn wait_for_device_loss(
+        stop_receiver: &mut oneshot::Receiver<()>,
+        error_receiver: &crossbeam_channel::Receiver<cpal::StreamError>,
+    ) -> Option<String> {
+        loop {
+            if !matches!(stop_receiver.try_recv(), Err(oneshot::error::TryRecvError::Empty)) {
+                return None;
+            }
+            match error_receiver.recv_timeout(Duration::from_millis(50)) {
+                Ok(error @ cpal::StreamError::DeviceNotAvailable) => return Some(error.to_string()),
+                Ok(error) => eprintln!("Audio stream error: {}", error),
+                Err(_) => {}
+            }
+        }
+    }
 
-        if api_key.trim().is_empty() {
-            return Err(AudioError {
-                message: "OpenAI API key is empty".to_string(),
+    /// Wait for the chosen device, a fallback or the default to be usable
+    /// again, giving up after `RECONNECT_TIMEOUT` or when the recording is stopped
+    fn reopen_input_device(input_devices: &[String], stop_receiver: &mut oneshot::Receiver<()>) -> Reopened {
+        let deadline = Instant::now() + input_device::RECONNECT_TIMEOUT;
+        loop {
+            if !matches!(stop_receiver.try_recv(), Err(oneshot::error::TryRecvError::Empty)) {
+                return Reopened::Stopped;
+            }
+            let reopened = input_device::find(input_devices).and_then(|device| {
+                let (config, sample_format) = Self::get_best_config(&device)?;
+                Ok((device, config, sample_format))
             });
+            match reopened {
+                Ok(input) => {
+                    let name = input.0.name().unwrap_or_else(|_| "unknown device".to_string());
+                    return Reopened::Device(name, input);
+                }
+                Err(e) if Instant::now() >= deadline => {
+                    eprintln!("No input device to continue recording on: {}", e);
+                    return Reopened::Unavailable;
+                }
+                Err(_) => std::thread::sleep(Duration::from_millis(250)),
+            }
         }
+    }
 
-        let client = reqwest::Client::builder()
-            .timeout(Duration::from_secs(60))
-            .build()
-            .map_err(|e| AudioError {
-                message: format!("Failed to create HTTP client: {}", e),
-            })?;
-
-        let rewrite_prompt = self.rewrite_prompt.replace("{}", transcribed_text);
-
-        let request_body = serde_json::json!({
-            "model": "gpt-5.4",
-            "input": rewrite_prompt,
-            "reasoning": {
-                "effort": "minimal"
-            },
-            "max_output_tokens": 16384,
-            "service_tier": "priority"
-        });
+    /// Build and play an input stream for `input`
+    fn start_input_stream(
+        input: &OpenedInput,
+        recording_rate: u32,
+        capture: &Capture,
+        error_sender: &crossbeam_channel::Sender<cpal::StreamError>,
+    ) -> Result<cpal::Stream, String> {
+        let (device, config, sample_format) = input;
+        let stream = Self::build_input_stream(device, config, *sample_format, recording_rate, capture, error_sender)?;
+        stream
+            .play()
+            .map_err(|e| format!("Failed to play stream: {}", e))?;
+        Ok(stream)
+    }
 
-        println!("Sending rewrite request to GPT-5.4...");
+    /// Open an input stream that hands mono samples at `recording_rate` to `capture`
+    fn build_input_stream(
+        device: &Device,
+        config: &StreamConfig,
+        sample_format: SampleFormat,
+        recording_rate: u32,
+        capture: &Capture,
+        error_sender: &crossbeam_channel::Sender<cpal::StreamError>,
+    ) -> Result<cpal::Stream, String> {
+        fn build<T>(
+            device: &Device,
+            config: &StreamConfig,
+            recording_rate: u32,
+            capture: Capture,
+            error_sender: crossbeam_channel::Sender<cpal::StreamError>,
+        ) -> Result<cpal::Stream, cpal::BuildStreamError>
+        where
+            T: cpal::SizedSample,
+            f32: cpal::FromSample<T>,
+        {
+            let channels = config.channels;
+            let mut converter = RateConverter::new(config.sample_rate.0, recording_rate);
+            let mut first_callback = true;
+            device.build_input_stream(
+                config,
+                move |data: &[T], _: &cpal::InputCallbackInfo| {
+                    if !capture.active.load(Ordering::Relaxed) {
+                        return;
+                    }
+                    if first_callback {
+                        println!("[Audio Callback] First callback - data.len()={}, channels={}, samples_per_callback={}",
+                            data.len(), channels, data.len() / channels as usize);
+                        first_callback = false;
+                    }
+                    let float_data: Vec<f32> = data.iter().map(|&s| s.to_sample::<f32>()).collect();
+                    capture.push(&float_data, channels, &mut converter);
+                },
+                move |err| {
+                    let _ = error_sender.send(err);
+                },
+                None,
+            )
+        }
 
-        let request_future = client
-            .post("https://api.openai.com/v1/responses")
-            .header("Content-Type", "application/json")
-            .header("Authorization", format!("Bearer {}", api_key))
-            .json(&request_body)
-            .send();
+        // Use the provided config and sample format; only set buffer size here
+        let mut config = config.clone();
+        config.buffer_size = cpal::BufferSize::Fixed(2048);
+
+        let capture = capture.clone();
+        let error_sender = error_sender.clone();
+        match sample_format {
+            SampleFormat::F32 => build::<f32>(device, &config, recording_rate, capture, error_sender),
+            SampleFormat::I16 => build::<i16>(device, &config, recording_rate, capture, error_sender),
+            SampleFormat::I32 => build::<i32>(device, &config, recording_rate, capture, error_sender),
+            _ => return Err("Unsupported sample format".to_string()),
+        }
+        .map_err(|e| format!("Failed to build stream: {}", e))
+    }
 
-        // Wait for either response or cancellation
-        let response = tokio::select! {
-            result = request_future => {
-                result.map_err(|e| AudioError { message: format!("Failed to send rewrite request: {}", e) })?
-            }
-            _ = self.cancellation_token.cancelled() => {
-                return Err(AudioError { message: "Rewrite cancelled".to_string() });
-            }
+    /// Rewrite transcribed text with the configured provider to handle
+    /// dictation issues (phonetic alphabet, punctuation, formatting commands, etc.)
+    ///
+    /// Returns the provider's answer together with its "provider/model" label.
+    async fn rewrite_transcribed_text(&self, transcribed_text: &str) -> Result<(RewriteOutput, String), AudioError> {
+        let variables = Variables {
+            transcript: transcribed_text.to_string(),
+            ..self.config.prompt_variables.clone()
         };
+        let rewrite_prompt = rewrite::build_prompt(
+            Some(self.config.rewrite_prompt.as_str()).filter(|_| self.config.rewrite_enabled),
+            &variables,
+            self.config.rewrite_target_language.as_deref(),
+        )
+        .map_err(|e| AudioError::new(ErrorKind::Config, format!("Invalid rewrite prompt: {}", e)))?;
+        let rewrite_prompt = rewrite::with_prior_context(rewrite_prompt, &self.config.context);
+        self.request_rewrite(&rewrite_prompt).await
+    }
 
-        if !response.status().is_success() {
-            let status = response.status();
-            let error_text = response
-                .text()
-                .await
-                .unwrap_or_else(|_| "Unknown error".to_string());
-            return Err(AudioError {
-                message: format!("GPT-5.4 API error {}: {}", status, error_text),
-            });
-        }
-
-        #[derive(Deserialize)]
-        struct GPTResponse {
-            output: Vec<GPTOutputItem>,
-        }
+    /// Send a prompt to the rewrite provider, with retries; returns the output
+    /// and the provider's label
+    async fn request_rewrite(&self, prompt: &str) -> Result<(RewriteOutput, String), AudioError> {
+        let provider = RewriteProvider::create(&self.config.rewrite, &self.config.api_key)?;
+        let output = self
+            .config
+            .retry
+            .run("Rewrite", &self.cancellation_token, || async {
+                let request = async {
+                    if self.config.stream_rewrite {
+                        provider
+                            .rewrite_streaming(prompt, |text| {
+                                self.emit_event(FlowEvent::PartialRewrite(text.to_string()))
+                            })
+                            .await
+                    } else {
+                        provider.rewrite(prompt).await
+                    }
+                };
+                // Wait for either response or cancellation
+                tokio::select! {
+                    result = request => result,
+                    _ = self.cancellation_token.cancelled() => Err(AudioError::cancelled("Rewrite cancelled")),
+                }
+            })
+            .await?;
+        Ok((output, provider.label()))
+    }
 
-        #[derive(Deserialize)]
-        struct GPTOutputItem {
-            #[serde(rename = "type")]
-            output_type: String,
-            content: Option<Vec<GPTContentItem>>, // Optional because reasoning items don't have content
-        }
+    /// Transcribe live segments in order as the processing thread sends them
+    ///
+    /// Each segment goes through the retry policy and fallback chain like a
+    /// chunk. Returns the segments with their transcripts, `None` where
+    /// transcription failed.
+    async fn transcribe_live_segments(
+        targets: Arc<Vec<TranscriptionTarget>>,
+        config: Arc<FlowConfig>,
+        callback: FlowCallback,
+        cancellation_token: CancellationToken,
+        mut segment_receiver: mpsc::UnboundedReceiver<AudioChunk>,
+    ) -> LiveSegments {
+        let mut segments = LiveSegments::default();
+
+        while let Some(segment) = segment_receiver.recv().await {
+            let index = segments.chunks.len();
+            let label = format!("Live segment {}", index);
+            let result = Self::transcribe_with_fallback(
+                &targets,
+                None,
+                &config,
+                &cancellation_token,
+                &label,
+                &segment.audio_data,
+            )
+            .await;
 
-        #[derive(Deserialize)]
-        struct GPTContentItem {
-            text: String,
+            let transcript = match result {
+                Ok(transcript) => {
+                    println!("{} transcribed: {} chars", label, transcript.text.len());
+                    (callback)(FlowEvent::PartialTranscript { index, text: transcript.text.clone() });
+                    Some(transcript.offset(segment.start_secs))
+                }
+                Err(e) if e.kind == ErrorKind::Cancelled => break,
+                Err(e) => {
+                    eprintln!("{} failed: {}", label, e.message);
+                    None
+                }
+            };
+            segments.chunks.push(segment);
+            segments.transcripts.push(transcript);
         }
 
-        // Get the raw response text first for debugging
-        let response_text = response.text().await.map_err(|e| AudioError {
-            message: format!("Failed to get response text: {}", e),
-        })?;
+        segments
+    }
 
-        // Try to parse it
-        let gpt_response: GPTResponse = serde_json::from_str(&response_text).map_err(|e| AudioError {
-            message: format!("Failed to parse rewrite response: {} | Raw response: {}", e, response_text),
-        })?;
+    /// Reconcile live partials into the final transcript
+    ///
+    /// Waits for the remaining segments and joins them, transcribing failed
+    /// segments again so the ones that succeeded are not paid for twice. Only
+    /// if no segments were produced is the full recording transcribed.
+    async fn finish_live_transcription(
+        &self,
+        mut live_task: JoinHandle<LiveSegments>,
+        audio_data: Vec<u8>,
+    ) -> Result<Transcript, AudioError> {
+        let segments = tokio::select! {
+            result = &mut live_task => result.ok(),
+            _ = self.cancellation_token.cancelled() => {
+                live_task.abort();
+                return Err(AudioError::cancelled("Transcription cancelled"));
+            }
+        };
 
-        // Extract the rewritten text from the response structure
-        // Find the "message" type output item and get its content
-        let rewritten_text = gpt_response
-            .output
-            .iter()
-            .find(|item| item.output_type == "message")
-            .and_then(|item| item.content.as_ref())
-            .and_then(|content| content.first())
-            .map(|content| content.text.clone())
-            .unwrap_or_else(|| {
-                eprintln!("Could not extract text from GPT-5.4 response, using original");
-                transcribed_text.to_string()
-            });
+        match segments {
+            Some(LiveSegments { chunks, mut transcripts }) if !chunks.is_empty() => {
+                let failed = transcripts.iter().filter(|t| t.is_none()).count();
+                if failed > 0 {
+                    println!("Transcribing {} failed live segments again", failed);
+                    // The retry of a failed flow splits the recording its own
+                    // way, so these results are not kept for it
+                    self.transcribe_missing_chunks(chunks, &mut transcripts).await?;
+                }
+                Ok(Transcript::join(transcripts.into_iter().flatten()))
+            }
+            _ => {
+                println!("No live segments, transcribing the full recording");
+                self.transcribe_audio(audio_data, Vec::new()).await
+            }
+        }
+    }
 
-        Ok(rewritten_text)
+    /// Transcribe a recording, splitting it into chunks first if it is too long
+    /// for a single request
+    async fn transcribe_audio(&self, audio_data: Vec<u8>, chunk_transcripts: Vec<Option<Transcript>>) -> Result<Transcript, AudioError> {
+        // Decoding and re-encoding a long recording is CPU-bound
+        let split = tokio::task::spawn_blocking(move || {
+            let chunks = chunking::split_long_recording(&audio_data);
+            (audio_data, chunks)
+        })
+        .await
+        .map_err(|e| AudioError::new(ErrorKind::Internal, format!("Chunking task failed: {}", e)))?;
+
+        match split {
+            (_, Ok(Some(chunks))) => self.transcribe_chunks(chunks, chunk_transcripts).await,
+            (audio_data, Ok(None)) => self.transcribe_single(audio_data).await,
+            (audio_data, Err(e)) => {
+                eprintln!("Could not split recording, sending it whole: {}", e);
+                self.transcribe_single(audio_data).await
+            }
+        }
     }
 
-    async fn transcribe_audio(&self, audio_data: Vec<u8>) -> Result<String, AudioError> {
-        let api_key = if self.api_key.trim().is_empty() {
-            env::var("OPENAI_API_KEY").map_err(|_| AudioError {
-                message: "OPENAI_API_KEY environment variable not set".to_string(),
-            })?
+    /// Transcribe chunks in parallel, up to each backend's concurrency limit,
+    /// and join the results in order
+    ///
+    /// Chunks with a transcript from an earlier attempt are skipped. If any
+    /// chunk still fails, the per-chunk results are emitted for a later retry.
+    async fn transcribe_chunks(&self, chunks: Vec<AudioChunk>, previous: Vec<Option<Transcript>>) -> Result<Transcript, AudioError> {
+        let mut transcripts = if previous.len() == chunks.len() {
+            previous
         } else {
-            self.api_key.clone()
+            vec![None; chunks.len()]
         };
+        match self.transcribe_missing_chunks(chunks, &mut transcripts).await {
+            Ok(()) => Ok(Transcript::join(transcripts.into_iter().flatten())),
+            Err(e) if e.kind == ErrorKind::Cancelled => Err(e),
+            Err(e) => {
+                self.emit_event(FlowEvent::ChunkTranscripts(transcripts));
+                Err(e)
+            }
+        }
+    }
+
+    /// Fill in the chunks without a transcript in `transcripts`, which has one
+    /// entry per chunk; fails if any chunk still has none
+    async fn transcribe_missing_chunks(
+        &self,
+        chunks: Vec<AudioChunk>,
+        transcripts: &mut [Option<Transcript>],
+    ) -> Result<(), AudioError> {
+        let total = chunks.len();
+
+        // One limit per target, so chunks falling back to a slower backend
+        // respect that backend's limit rather than the selected one's
+        let limits: Arc<Vec<Semaphore>> = Arc::new(
+            self.targets
+                .iter()
+                .map(|target| Semaphore::new(target.backend.max_concurrent_requests().max(1)))
+                .collect(),
+        );
+        println!(
+            "Transcribing {} chunks with {}, {} at a time",
+            total,
+            self.targets[0].label(),
+            limits[0].available_permits()
+        );
 
-        if api_key.trim().is_empty() {
-            return Err(AudioError {
-                message: "OpenAI API key is empty".to_string(),
+        let mut tasks = JoinSet::new();
+        for (index, chunk) in chunks.into_iter().enumerate() {
+            if transcripts[index].is_some() {
+                continue;
+            }
+            let targets = Arc::clone(&self.targets);
+            let limits = Arc::clone(&limits);
+            let config = Arc::clone(&self.config);
+            let cancellation_token = self.cancellation_token.clone();
+            tasks.spawn(async move {
+                let label = format!("Chunk {}", index);
+                let result = Self::transcribe_with_fallback(
+                    &targets,
+                    Some(&limits),
+                    &config,
+                    &cancellation_token,
+                    &label,
+                    &chunk.audio_data,
+                )
+                .await;
+                (index, result.map(|transcript| transcript.offset(chunk.start_secs)))
             });
         }
 
-        let client = reqwest::Client::builder()
-            .timeout(Duration::from_secs(60))
-            .build()
-            .map_err(|e| AudioError {
-                message: format!("Failed to create HTTP client: {}", e),
-            })?;
-
-        let form = reqwest::multipart::Form::new()
-            .part(
-                "file",
-                reqwest::multipart::Part::bytes(audio_data)
-                    .file_name("audio.webm")
-                    .mime_str("audio/webm")
-                    .map_err(|e| AudioError {
-                        message: format!("Failed to create file part: {}", e),
-                    })?,
-            )
-            .text("model", self.model.clone());
+        let mut last_error = None;
+        loop {
+            tokio::select! {
+                next = tasks.join_next() => match next {
+                    Some(Ok((index, Ok(transcript)))) => transcripts[index] = Some(transcript),
+                    Some(Ok((_, Err(e)))) => last_error = Some(e),
+                    Some(Err(e)) => {
+                        last_error = Some(AudioError::new(ErrorKind::Internal, format!("Chunk task failed: {}", e)))
+                    }
+                    None => break,
+                },
+                _ = self.cancellation_token.cancelled() => {
+                    tasks.abort_all();
+                    return Err(AudioError::cancelled("Transcription cancelled"));
+                }
+            }
+        }
 
-        println!("Sending transcription request to OpenAI...");
+        let failed = transcripts.iter().filter(|t| t.is_none()).count();
+        if failed > 0 {
+            // Report the kind of the last failure, e.g. a rate limit
+            let last_error = last_error.unwrap_or_else(|| AudioError::new(ErrorKind::Internal, "unknown error"));
+            return Err(AudioError::new(
+                last_error.kind,
+                format!(
+                    "{} of {} chunks failed to transcribe, retry to transcribe the rest: {}",
+                    failed, total, last_error.message
+                ),
+            ));
+        }
 
-        let request_future = client
-            .post("https://api.openai.com/v1/audio/transcriptions")
-            .header("Authorization", format!("Bearer {}", api_key))
-            .multipart(form)
-            .send();
+        Ok(())
+    }
 
-        // Wait for either response or cancellation
-        let response = tokio::select! {
-            result = request_future => {
-                result.map_err(|e| AudioError { message: format!("Failed to send request: {}", e) })?
-            }
-            _ = self.cancellation_token.cancelled() => {
-                return Err(AudioError { message: "Transcription cancelled".to_string() });
+    /// Transcribe with each target in turn until one succeeds
+    ///
+    /// Every target gets the full retry policy before the next one is tried.
+    /// `limits`, if given, holds one concurrency limit per target. A cancel
+    /// ends the chain at once; otherwise the last target's error is returned.
+    async fn transcribe_with_fallback(
+        targets: &[TranscriptionTarget],
+        limits: Option<&[Semaphore]>,
+        config: &FlowConfig,
+        cancellation_token: &CancellationToken,
+        label: &str,
+        audio_data: &[u8],
+    ) -> Result<Transcript, AudioError> {
+        let mut last_error: Option<AudioError> = None;
+        for (i, target) in targets.iter().enumerate() {
+            if last_error.is_some() {
+                println!("{}: falling back to {}", label, target.label());
             }
-        };
+            let _permit = match limits {
+                Some(limits) => limits[i].acquire().await.ok(),
+                None => None,
+            };
 
-        if !response.status().is_success() {
-            let status = response.status();
-            let error_text = response
-                .text()
-                .await
-                .unwrap_or_else(|_| "Unknown error".to_string());
-            return Err(AudioError {
-                message: format!("OpenAI API error {}: {}", status, error_text),
-            });
-        }
+            let result = config
+                .retry
+                .run(label, cancellation_token, || async {
+                    let request = config.transcription_request(audio_data.to_vec(), &target.model);
+
+                    // Wait for either the transcription or cancellation
+                    tokio::select! {
+                        result = target.backend.transcribe(request) => result,
+                        _ = cancellation_token.cancelled() => {
+                            Err(AudioError::cancelled("Transcription cancelled"))
+                        }
+                    }
+                })
+                .await;
 
-        #[derive(Deserialize)]
-        struct OpenAIResponse {
-            text: String,
+            match result {
+                Ok(mut transcript) => {
+                    transcript.model = target.label();
+                    return Ok(transcript);
+                }
+                Err(e) if e.kind == ErrorKind::Cancelled => return Err(e),
+                Err(e) => {
+                    eprintln!("{} failed with {}: {}", label, target.label(), e.message);
+                    last_error = Some(e);
+                }
+            }
         }
+        Err(last_error.unwrap_or_else(|| AudioError::new(ErrorKind::Config, "No transcription model configured")))
+    }
 
-        let openai_response: OpenAIResponse = response.json().await.map_err(|e| AudioError {
-            message: format!("Failed to parse response: {}", e),
-        })?;
+    async fn transcribe_single(&self, audio_data: Vec<u8>) -> Result<Transcript, AudioError> {
+        println!("Transcribing with {}", self.targets[0].label());
+        Self::transcribe_with_fallback(
+            &self.targets,
+            None,
+            &self.config,
+            &self.cancellation_token,
+            "Transcription",
+            &audio_data,
+        )
+        .await
+    }
 
-        Ok(openai_response.text)
+    /// End the flow with an error. A cancel ends silently in the cancelled
+    /// state, but is still reported so the UI can offer a retry.
+    async fn fail(&self, error: AudioError) -> Result<(), AudioError> {
+        let state = if error.kind == ErrorKind::Cancelled {
+            FlowState::Cancelled
+        } else {
+            self.play_sound("pipe.mp3");
+            FlowState::Error
+        };
+        self.set_state(state).await;
+        self.emit_event(FlowEvent::Error(error.clone()));
+        Err(error)
     }
 
     async fn set_state(&self, new_state: FlowState) {
@@ -903,46 +1327,10 @@ Ok(webm_data)
         }
     }
 
-    fn find_input_device() -> Result<Device, AudioError> {
-        let host = cpal::default_host();
-
-        // Check for custom device from environment variable
-        if let Ok(device_name) = env::var("MUSE_INPUT_DEVICE") {
-            println!("Looking for custom input device: {}", device_name);
-
-            let devices = host.input_devices().map_err(AudioError::from)?;
-
-            for device in devices {
-                if let Ok(name) = device.name() {
-                    if name.to_lowercase() == device_name.to_lowercase() {
-                        println!("Found custom input device: {}", name);
-                        return Ok(device);
-                    }
-                }
-            }
-
-            println!(
-                "Custom device '{}' not found, falling back to default",
-                device_name
-            );
-        }
-
-        // Fall back to default input device
-        let device = host.default_input_device().ok_or_else(|| AudioError {
-            message: "No input device found".to_string(),
-        })?;
-
-        if let Ok(name) = device.name() {
-            println!("Using default input device: {}", name);
-        }
-
-        Ok(device)
-    }
-
     fn get_best_config(device: &Device) -> Result<(StreamConfig, SampleFormat), AudioError> {
-        let supported_configs = device.supported_input_configs().map_err(|_| AudioError {
-            message: "Unsupported format".to_string(),
-        })?;
+        let supported_configs = device
+            .supported_input_configs()
+            .map_err(|_| AudioError::new(ErrorKind::Device, "Unsupported format"))?;
 
         // Prefer 48000 Hz, mono if possible. Prefer F32, then I16, then I32.
         let mut best: Option<(u32, bool, SampleFormat, cpal::SupportedStreamConfigRange)> = None;
@@ -977,9 +1365,7 @@ Ok(webm_data)
             }
         }
 
-        let (_score, _mono, fmt, range) = best.ok_or_else(|| AudioError {
-            message: "Unsupported format".to_string(),
-        })?;
+        let (_score, _mono, fmt, range) = best.ok_or_else(|| AudioError::new(ErrorKind::Device, "Unsupported format"))?;
 
         // Choose 48000 if supported, otherwise use min sample rate in range.
         let picked_rate = if range.min_sample_rate() <= SampleRate(48000)
@@ -1016,3 +1402,157 @@ Ok(webm_data)
         mono_data
     }
 }
+
+/// An input device with the stream configuration chosen for it
+type OpenedInput = (Device, StreamConfig, SampleFormat);
+
+/// Outcome of waiting for an input device after losing one
+enum Reopened {
+    Device(String, OpenedInput),
+    /// No device could be opened in time
+    Unavailable,
+    /// The recording was stopped meanwhile
+    Stopped,
+}
+
+/// State shared by the input stream callbacks; outlives a single stream so a
+/// recording can continue on another device
+/// Segments cut during a live recording and their transcripts, in order
+#[derive(Default)]
+struct LiveSegments {
+    chunks: Vec<AudioChunk>,
+    transcripts: Vec<Option<Transcript>>,
+}
+
+#[derive(Clone)]
+struct Capture {
+    active: Arc<AtomicBool>,
+    /// Interleaved samples as delivered by the devices
+    total_captured: Arc<AtomicUsize>,
+    total_mono_captured: Arc<AtomicUsize>,
+    waveform_buf: Arc<Mutex<Vec<f32>>>,
+    sample_sender: crossbeam_channel::Sender<Vec<f32>>,
+    callback: FlowCallback,
+}
+
+impl Capture {
+    fn push(&self, data: &[f32], channels: u16, converter: &mut RateConverter) {
+        self.total_captured.fetch_add(data.len(), Ordering::Relaxed);
+        let mono_data = converter.process(&Flow::mix_to_mono(data, channels));
+
+        // Update mono sample count and emit
+        let count = self.total_mono_captured.fetch_add(mono_data.len(), Ordering::Relaxed) + mono_data.len();
+        (self.callback)(FlowEvent::SampleCount(count));
+
+        // Accumulate and emit waveform bins per 2048-sample window
+        {
+            let mut buf = self.waveform_buf.lock().unwrap();
+            buf.extend_from_slice(&mono_data);
+            while buf.len() >= WINDOW_SIZE {
+                // Compute bins (256 bins of 8 samples RMS) and avg RMS
+                let (bins, avg_rms) = segmenter::window_rms(&buf[..WINDOW_SIZE]);
+                (self.callback)(FlowEvent::WaveformChunk { bins, avg_rms });
+
+                // Remove processed window
+                buf.drain(..WINDOW_SIZE);
+            }
+        }
+
+        let _ = self.sample_sender.send(mono_data);
+    }
+}
+
+/// Minimum live segment length; shorter pauses don't end a segment
+const LIVE_SEGMENT_MIN_SECS: f32 = 3.0;
+
+/// Live segments are cut here even without a pause
+const LIVE_SEGMENT_MAX_SECS: f32 = 15.0;
+
+/// Encodes silence-delimited segments of the recording as standalone WebM
+/// files and sends them off for live transcription
+struct SegmentEncoder {
+    segmenter: SilenceSegmenter,
+    processor: AudioStreamProcessor,
+    input_sample_rate: u32,
+    chunk_size: usize,
+    /// Input samples pushed so far, and where the current segment started
+    position: usize,
+    segment_start: usize,
+    sender: mpsc::UnboundedSender<AudioChunk>,
+}
+
+impl SegmentEncoder {
+    fn new(input_sample_rate: u32, chunk_size: usize, sender: mpsc::UnboundedSender<AudioChunk>) -> Result<Self, String> {
+        let config = SegmentConfig::new(
+            input_sample_rate,
+            LIVE_SEGMENT_MIN_SECS,
+            LIVE_SEGMENT_MAX_SECS,
+            segmenter::DEFAULT_SILENCE_RMS,
+        );
+        Ok(Self {
+            segmenter: SilenceSegmenter::new(config),
+            processor: Self::create_processor(input_sample_rate, chunk_size)?,
+            input_sample_rate,
+            chunk_size,
+            position: 0,
+            segment_start: 0,
+            sender,
+        })
+    }
+
+    fn create_processor(input_sample_rate: u32, chunk_size: usize) -> Result<AudioStreamProcessor, String> {
+        AudioStreamProcessor::new(input_sample_rate, 48000, 64000, chunk_size)
+            .map_err(|e| format!("Failed to create segment processor: {}", e))
+    }
+
+    fn push(&mut self, samples: &[f32]) -> Result<(), String> {
+        let mut start = 0;
+        for cut in self.segmenter.push(samples) {
+            self.push_to_processor(&samples[start..cut.offset])?;
+            self.finish_segment(cut.voiced)?;
+            self.segment_start = self.position + cut.offset;
+            start = cut.offset;
+        }
+        self.push_to_processor(&samples[start..])?;
+        self.position += samples.len();
+        Ok(())
+    }
+
+    fn push_to_processor(&mut self, samples: &[f32]) -> Result<(), String> {
+        self.processor
+            .push_samples(samples)
+            .map(|_| ())
+            .map_err(|e| format!("Failed to process segment samples: {}", e))
+    }
+
+    /// Finalize the current segment and start a new one. Silent segments are dropped.
+    fn finish_segment(&mut self, voiced: bool) -> Result<(), String> {
+        let next = Self::create_processor(self.input_sample_rate, self.chunk_size)?;
+        let processor = std::mem::replace(&mut self.processor, next);
+        if voiced {
+            let audio = processor
+                .finalize()
+                .map_err(|e| format!("Failed to finalize segment: {}", e))?;
+            self.send(audio.data);
+        }
+        Ok(())
+    }
+
+    /// Send the trailing segment at the end of the recording
+    fn finish(self) -> Result<(), String> {
+        if self.segmenter.segment_len() > 0 && self.segmenter.segment_voiced() {
+            let start_secs = self.segment_start as f64 / self.input_sample_rate as f64;
+            let audio = self
+                .processor
+                .finalize()
+                .map_err(|e| format!("Failed to finalize segment: {}", e))?;
+            let _ = self.sender.send(AudioChunk { start_secs, audio_data: audio.data });
+        }
+        Ok(())
+    }
+
+    fn send(&self, audio_data: Vec<u8>) {
+        let start_secs = self.segment_start as f64 / self.input_sample_rate as f64;
+        let _ = self.sender.send(AudioChunk { start_secs, audio_data });
+    }
+}
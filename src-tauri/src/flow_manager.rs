@@ -1,12 +1,27 @@
-use crate::flow::{Flow, FlowCallback, FlowEvent, FlowMode, FlowState};
+use crate::flow::{AudioError, ErrorKind, Flow, FlowCallback, FlowConfig, FlowEvent, FlowMode, FlowState};
+use crate::app_profile::{self, AppProfile, FocusedApp};
 use crate::audio_output::AudioOutputManager;
+use crate::context;
+use crate::dictation::DictationRules;
+use crate::input_device::{self, DisconnectPolicy};
+use crate::replacements::{self, ReplacementRule};
+use crate::retry::{self, RetryPolicy};
+use crate::template::{self, Variables};
+use crate::rewrite::{self, FailurePolicy, RewriteSettings};
+use crate::transcript::{SpeakerTurn, Transcript, TranscriptSegment, TranscriptWord};
+use crate::transcription::{self, BackendSettings, FallbackModel, TranscriptionBackend, TranscriptionTarget};
+use crate::typing::{LiveTyping, TypingEdit};
+use crate::voice_edit::{self, EditCommand};
+use crate::usage::{self, FlowUsage, Period, UsageRecord, UsageTotal};
 use serde::{Deserialize, Serialize};
-use std::sync::{Arc, Mutex};
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex, Weak};
 use std::fs;
 use std::path::PathBuf;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use tauri::{AppHandle, Emitter};
-use tokio::sync::{oneshot, RwLock};
+use tauri_plugin_clipboard_manager::ClipboardExt;
+use tokio::sync::{mpsc, oneshot, RwLock};
 use directories::ProjectDirs;
 
 // Global state
@@ -17,6 +32,14 @@ enum CallbackMode {
     RetryOnly, // Handle only essential events for retry
 }
 
+/// Text for the output task of one flow, in the order the flow produced it
+enum OutputUpdate {
+    Partial(String), // Streamed rewrite so far, typed as it grows
+    Final(String),   // Result; the typed text is corrected to match it exactly
+    Abandon(String), // The flow failed, so anything typed so far is replaced by this if focus did not move
+    Edit { command: EditCommand, spoken: String }, // Spoken edit of earlier typed text
+}
+
 const DEFAULT_PROMPT_TEXT: &str = "Please fix and rewrite the following dictated text to handle common speech-to-text issues:\n\
 - Convert phonetic alphabet spelling (alpha bravo charlie) to actual letters (\"ABC\"); choose upper or lowercase based on context\n\
 - When appropriate, convert spoken numbers to numerals: \"one two three\" → \"123\"\n\
@@ -27,7 +50,7 @@ const DEFAULT_PROMPT_TEXT: &str = "Please fix and rewrite the following dictated
 - \"no space hello there\" -> \"hellothere\"\n\
 - Fix any other obvious dictation artifacts\n\
 \n\
-Original text: {}\n\
+Original text: {{transcript}}\n\
 \n\
 Return ONLY the corrected text, no explanations or formatting:";
 
@@ -42,21 +65,96 @@ pub struct RewritePrompt {
 pub struct TranscriptionHistoryEntry {
     pub text: String,
     pub timestamp: u64,
+    /// Timing of the raw transcription, empty unless timestamps were requested
+    pub segments: Vec<TranscriptSegment>,
+    pub words: Vec<TranscriptWord>,
+    /// Speaker turns, empty unless the recording was diarized
+    #[serde(default)]
+    pub turns: Vec<SpeakerTurn>,
+    /// Saved recording the times refer to
+    pub audio_path: Option<String>,
+    /// Backend and model that produced the text, e.g. "openai/whisper-1"
+    #[serde(default)]
+    pub model: String,
+    /// Text as transcribed, before dictation commands and rewriting
+    #[serde(default)]
+    pub raw_text: String,
+    /// Rewrite output, before final punctuation was removed; `None` if no
+    /// rewrite ran or it failed
+    #[serde(default)]
+    pub rewritten_text: Option<String>,
+    #[serde(default)]
+    pub prompt_id: Option<String>,
+    /// "provider/model" label of the rewrite
+    #[serde(default)]
+    pub rewrite_model: Option<String>,
+}
+
+/// Which text of a history entry to use
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum TextVersion {
+    /// The text that was output
+    #[default]
+    Final,
+    Raw,
+    Rewritten,
 }
 
 pub struct FlowManager {
     current_flow: Option<Arc<Flow>>,
     stop_sender: Option<oneshot::Sender<()>>,
     retry_audio_data: Option<Vec<u8>>,
+    retry_audio_duration_secs: f64,
+    retry_chunk_transcripts: Vec<Option<Transcript>>,
+    /// Selected text of the voice command the retry audio belongs to
+    retry_selection: Option<String>,
+    last_audio_path: Option<String>,
+    /// Translation options of the current recording, kept for retries
+    active_translation: TranslationOverrides,
+    /// Selected text of the current voice command recording
+    active_selection: Option<String>,
+    /// Profile of the application focused when the current recording started
+    active_profile: Option<AppProfile>,
+    last_focused_app: Option<FocusedApp>,
+    /// Text typed by recent recordings, oldest first, for voice edits
+    recent_insertions: Vec<String>,
+    transcription_backend: String,
+    backend_settings: BTreeMap<String, BackendSettings>,
     model: String,
+    fallback_models: Vec<FallbackModel>,
+    allow_remote_fallback: bool,
+    language: String,
+    transcription_prompt: String,
+    context_entries: u32,
+    context_window_minutes: u32,
+    transcription_mode: String,
+    max_retries: u32,
+    retry_initial_delay_ms: u64,
+    monthly_budget_usd: f64,
     rewrite_enabled: bool,
+    rewrite_settings: RewriteSettings,
+    stream_rewrite: bool,
+    rewrite_failure_policy: FailurePolicy,
+    translate_to_english: bool,
+    rewrite_target_language: String,
     omit_final_punctuation: bool,
+    dictation_rules: DictationRules,
+    live_transcription: bool,
+    input_device: String,
+    input_device_fallbacks: Vec<String>,
+    disconnect_policy: DisconnectPolicy,
     selected_prompt_id: String,
     custom_prompts: Vec<RewritePrompt>,
     api_key: String,
     shortcuts: String,
+    command_shortcut: String,
     output_mode: String,
+    voice_edits: bool,
+    app_profiles: Vec<AppProfile>,
+    replacement_rules: Vec<ReplacementRule>,
     transcription_history: Vec<TranscriptionHistoryEntry>,
+    usage_records: Vec<UsageRecord>,
     audio_manager: Arc<Mutex<AudioOutputManager>>,
 }
 
@@ -67,62 +165,138 @@ impl FlowManager {
             current_flow: None,
             stop_sender: None,
             retry_audio_data: None,
+            retry_audio_duration_secs: 0.0,
+            retry_chunk_transcripts: Vec::new(),
+            retry_selection: None,
+            last_audio_path: None,
+            active_translation: TranslationOverrides::default(),
+            active_selection: None,
+            active_profile: None,
+            last_focused_app: None,
+            recent_insertions: Vec::new(),
+            transcription_backend: settings.transcription_backend,
+            backend_settings: settings.backend_settings,
             model: settings.model,
+            fallback_models: settings.fallback_models,
+            allow_remote_fallback: settings.allow_remote_fallback,
+            language: settings.language,
+            transcription_prompt: settings.transcription_prompt,
+            context_entries: settings.context_entries,
+            context_window_minutes: settings.context_window_minutes,
+            transcription_mode: settings.transcription_mode,
+            max_retries: settings.max_retries,
+            retry_initial_delay_ms: settings.retry_initial_delay_ms,
+            monthly_budget_usd: settings.monthly_budget_usd,
             rewrite_enabled: settings.rewrite_enabled,
+            rewrite_settings: settings.rewrite_settings,
+            stream_rewrite: settings.stream_rewrite,
+            rewrite_failure_policy: settings.rewrite_failure_policy,
+            translate_to_english: settings.translate_to_english,
+            rewrite_target_language: settings.rewrite_target_language,
             omit_final_punctuation: settings.omit_final_punctuation,
+            dictation_rules: settings.dictation_rules,
+            live_transcription: settings.live_transcription,
+            input_device: settings.input_device,
+            input_device_fallbacks: settings.input_device_fallbacks,
+            disconnect_policy: settings.disconnect_policy,
             selected_prompt_id: settings.selected_prompt_id,
             custom_prompts: settings.custom_prompts,
             api_key: settings.api_key,
             shortcuts: settings.shortcuts,
+            command_shortcut: settings.command_shortcut,
             output_mode: settings.output_mode,
+            voice_edits: settings.voice_edits,
+            app_profiles: settings.app_profiles,
+            replacement_rules: settings.replacement_rules,
             transcription_history: Vec::new(),
+            usage_records: Self::load_usage(),
             audio_manager,
         }
     }
 
-    fn create_flow_callback(app_handle: AppHandle, flow_manager_state: FlowManagerState, mode: CallbackMode) -> FlowCallback {
+    fn create_flow_callback(
+        app_handle: AppHandle,
+        flow_manager_state: FlowManagerState,
+        mode: CallbackMode,
+        output_mode: String,
+        selection: Option<String>,
+    ) -> FlowCallback {
         let app_handle_clone = app_handle.clone();
         let flow_manager_weak = Arc::downgrade(&flow_manager_state);
+        // Typing happens on one task so streamed updates cannot overtake each other
+        let (output_sender, output_receiver) = mpsc::unbounded_channel();
+        tokio::spawn(Self::run_output_task(output_receiver, app_handle.clone(), flow_manager_weak.clone(), output_mode));
         Arc::new(move |event| {
             match (&mode, event) {
                 // Events always handled
                 (_, FlowEvent::StateChanged(state)) => {
                     let _ = app_handle_clone.emit("flow-state-changed", &state);
                 }
-                (_, FlowEvent::TranscriptionResult(text)) => {
-                    let text_for_history = text.clone();
-                    let text_for_output = text.clone();
-                    let app_handle_for_output = app_handle_clone.clone();
+                (_, FlowEvent::TranscriptionResult(transcript)) => {
+                    let text = transcript.text.clone();
                     if let Some(manager_arc) = flow_manager_weak.upgrade() {
                         tokio::spawn(async move {
                             let mut manager_guard = manager_arc.write().await;
                             if let Some(manager) = manager_guard.as_mut() {
                                 manager.clear_audio_data();
-                                manager.add_to_history(text_for_history);
-                                let output_mode = manager.get_output_mode().to_string();
-                                drop(manager_guard);
-                                
-                                if output_mode == "type" {
-                                    if let Err(e) = crate::type_text_impl(&text_for_output) {
-                                        eprintln!("Failed to type text: {}", e);
-                                        let _ = crate::copy_to_clipboard_impl(&text_for_output, &app_handle_for_output);
-                                    }
-                                } else {
-                                    let _ = crate::copy_to_clipboard_impl(&text_for_output, &app_handle_for_output);
+                                manager.active_selection = None;
+                                // Nothing is left of a recording that was only an edit command
+                                if !transcript.text.is_empty() {
+                                    manager.add_to_history(transcript);
                                 }
                             }
                         });
                     }
-                    let _ = app_handle_clone.emit("transcription-result", &text);
+                    let _ = output_sender.send(OutputUpdate::Final(text.clone()));
+                    if !text.is_empty() {
+                        let _ = app_handle_clone.emit("transcription-result", &text);
+                    }
                     let _ = app_handle_clone.emit("retry-available", false);
                 }
+                (_, FlowEvent::ChunkTranscripts(transcripts)) => {
+                    // Keep finished chunks so a retry only transcribes the failed ones
+                    if let Some(manager_arc) = flow_manager_weak.upgrade() {
+                        tokio::spawn(async move {
+                            let mut manager_guard = manager_arc.write().await;
+                            if let Some(manager) = manager_guard.as_mut() {
+                                manager.retry_chunk_transcripts = transcripts;
+                            }
+                        });
+                    }
+                }
+                (_, FlowEvent::Usage(usage)) => {
+                    if let Some(manager_arc) = flow_manager_weak.upgrade() {
+                        tokio::spawn(async move {
+                            let mut manager_guard = manager_arc.write().await;
+                            if let Some(manager) = manager_guard.as_mut() {
+                                manager.record_usage(usage);
+                            }
+                        });
+                    }
+                }
+                (_, FlowEvent::VoiceEdit { command, spoken }) => {
+                    let _ = output_sender.send(OutputUpdate::Edit { command, spoken });
+                }
+                (_, FlowEvent::PartialRewrite(text)) => {
+                    let _ = app_handle_clone.emit("partial-rewrite", &text);
+                    let _ = output_sender.send(OutputUpdate::Partial(text));
+                }
+                (_, FlowEvent::RewriteFailed(error)) => {
+                    let _ = app_handle_clone.emit("rewrite-failed", &error);
+                }
+                (_, FlowEvent::InputDevice(event)) => {
+                    let _ = app_handle_clone.emit("input-device", &event);
+                }
                 (_, FlowEvent::Error(error)) => {
+                    // A voice command types over the selection, so a failed one puts it back
+                    let _ = output_sender.send(OutputUpdate::Abandon(selection.clone().unwrap_or_default()));
                     // Emit retry availability when there's an error and we have audio data
                     let app_handle_clone2 = app_handle_clone.clone();
                     if let Some(manager_arc) = flow_manager_weak.upgrade() {
                         tokio::spawn(async move {
-                            let manager_guard = manager_arc.read().await;
-                            if let Some(manager) = manager_guard.as_ref() {
+                            let mut manager_guard = manager_arc.write().await;
+                            if let Some(manager) = manager_guard.as_mut() {
+                                manager.active_selection = None;
                                 let retry_available = manager.has_retry_data();
                                 let _ = app_handle_clone2.emit("retry-available", retry_available);
                             }
@@ -139,16 +313,28 @@ impl FlowManager {
                     let payload = WaveformChunkPayload { bins, avg_rms };
                     let _ = app_handle_clone.emit("waveform-chunk", payload);
                 }
+                (CallbackMode::Full, FlowEvent::PartialTranscript { index, text }) => {
+                    let payload = PartialTranscriptPayload { index, text };
+                    let _ = app_handle_clone.emit("partial-transcript", payload);
+                }
                 (CallbackMode::Full, FlowEvent::AudioFileSaved(path)) => {
                     let _ = app_handle_clone.emit("audio-file-saved", &path);
+                    if let Some(manager_arc) = flow_manager_weak.upgrade() {
+                        tokio::spawn(async move {
+                            let mut manager_guard = manager_arc.write().await;
+                            if let Some(manager) = manager_guard.as_mut() {
+                                manager.last_audio_path = Some(path);
+                            }
+                        });
+                    }
                 }
-                (CallbackMode::Full, FlowEvent::AudioDataReady(audio_data)) => {
+                (CallbackMode::Full, FlowEvent::AudioDataReady { audio_data, duration_secs }) => {
                     // Store audio data directly in FlowManager
                     if let Some(manager_arc) = flow_manager_weak.upgrade() {
                         tokio::spawn(async move {
                             let mut manager_guard = manager_arc.write().await;
                             if let Some(manager) = manager_guard.as_mut() {
-                                manager.store_audio_data(audio_data);
+                                manager.store_audio_data(audio_data, duration_secs);
                             }
                         });
                     }
@@ -160,34 +346,210 @@ impl FlowManager {
         })
     }
 
-    pub async fn start_flow(&mut self, app_handle: AppHandle, flow_manager_state: FlowManagerState) -> Result<(), String> {
-        if !self.has_valid_api_key() {
-            return Err("OpenAI API key is required. Please set it in Settings or via OPENAI_API_KEY environment variable.".to_string());
+    /// Type or copy a flow's output as it arrives
+    ///
+    /// In "type" mode streamed partial text is typed right away and each
+    /// update only sends the keystrokes that differ from what is on screen.
+    /// If typing fails, or focus moved before text had to be deleted, the
+    /// typed text is left alone and the final text goes to the clipboard.
+    async fn run_output_task(
+        mut receiver: mpsc::UnboundedReceiver<OutputUpdate>,
+        app_handle: AppHandle,
+        flow_manager_weak: Weak<RwLock<Option<FlowManager>>>,
+        output_mode: String,
+    ) {
+        let mut live = LiveTyping::default();
+        let typing = output_mode == "type";
+
+        while let Some(update) = receiver.recv().await {
+
+            let (target, is_final) = match &update {
+                OutputUpdate::Edit { command, spoken } => {
+                    if typing {
+                        Self::apply_voice_edit(&flow_manager_weak, command, spoken).await;
+                    }
+                    continue;
+                }
+                OutputUpdate::Partial(text) => (text.as_str(), false),
+                OutputUpdate::Final(text) => (text.as_str(), true),
+                // The selection is still there if nothing was typed over it
+                OutputUpdate::Abandon(_) if !live.has_typed() => return,
+                OutputUpdate::Abandon(restore) => (restore.as_str(), false),
+            };
+            if typing
+                && let Some(edit) = live.plan(target)
+                && !edit.is_empty()
+            {
+                // Only looked up when needed, since it runs xprop
+                let focused = if edit.backspaces > 0 || !live.has_typed() {
+                    app_profile::focused_window_async().await
+                } else {
+                    None
+                };
+                if !live.allows(&edit, focused.as_deref()) {
+                    println!("Focus moved, leaving the typed text as it is");
+                } else {
+                    match crate::type_edit_impl(&edit) {
+                        Ok(()) => live.typed(target, focused),
+                        Err(e) => {
+                            eprintln!("Failed to type text: {}", e);
+                            live.stop();
+                        }
+                    }
+                }
+            }
+            if is_final && !target.is_empty() && (!typing || live.is_stopped()) {
+                let _ = crate::copy_to_clipboard_impl(target, &app_handle);
+            }
+            if is_final
+                && typing
+                && !live.is_stopped()
+                && let Some(manager_arc) = flow_manager_weak.upgrade()
+            {
+                let mut manager_guard = manager_arc.write().await;
+                if let Some(manager) = manager_guard.as_mut() {
+                    voice_edit::record(&mut manager.recent_insertions, target);
+                }
+            }
+            if !matches!(update, OutputUpdate::Partial(_)) {
+                return;
+            }
         }
+    }
 
+    /// Apply a spoken edit to the text typed by earlier recordings
+    async fn apply_voice_edit(
+        flow_manager_weak: &Weak<RwLock<Option<FlowManager>>>,
+        command: &EditCommand,
+        spoken: &str,
+    ) {
+        let Some(manager_arc) = flow_manager_weak.upgrade() else { return };
+        let mut insertions = match manager_arc.read().await.as_ref() {
+            Some(manager) => manager.recent_insertions.clone(),
+            None => return,
+        };
+        let edit = match voice_edit::apply(command, &mut insertions) {
+            Some(edit) => edit,
+            // Most likely dictation that starts with "replace", so type it as said
+            None if matches!(command, EditCommand::Replace { .. }) => {
+                voice_edit::record(&mut insertions, spoken);
+                TypingEdit { backspaces: 0, text: spoken.to_string() }
+            }
+            None => {
+                println!("Nothing to apply {:?} to", command);
+                return;
+            }
+        };
+        if let Err(e) = crate::type_edit_impl(&edit) {
+            eprintln!("Failed to apply voice edit: {}", e);
+            return;
+        }
+        if let Some(manager) = manager_arc.write().await.as_mut() {
+            manager.recent_insertions = insertions;
+        }
+    }
+
+    /// Start recording for dictation into `focused_app`, which callers look
+    /// up before taking the manager lock
+    pub async fn start_flow(
+        &mut self,
+        app_handle: AppHandle,
+        flow_manager_state: FlowManagerState,
+        translation: TranslationOverrides,
+        focused_app: Option<FocusedApp>,
+    ) -> Result<(), AudioError> {
+        if let Some(language) = &translation.rewrite_target_language {
+            rewrite::validate_target_language(language).map_err(|message| AudioError::new(ErrorKind::Config, message))?;
+        }
+        // A shortcut may turn on translation for a model that cannot translate
+        transcription::validate_mode(
+            &self.transcription_backend,
+            &self.model,
+            &self.transcription_mode,
+            translation.translate_to_english.unwrap_or(self.translate_to_english),
+        )
+        .map_err(|message| AudioError::new(ErrorKind::Config, message))?;
+        self.start_recording(app_handle, flow_manager_state, translation, None, focused_app).await
+    }
+
+    /// Record a spoken instruction for `selection`, the text copied from the
+    /// focused application; the result replaces the selection
+    pub async fn start_command_flow(
+        &mut self,
+        app_handle: AppHandle,
+        flow_manager_state: FlowManagerState,
+        selection: String,
+        focused_app: Option<FocusedApp>,
+    ) -> Result<(), AudioError> {
+        self.start_recording(app_handle, flow_manager_state, TranslationOverrides::default(), Some(selection), focused_app)
+            .await
+    }
+
+    async fn start_recording(
+        &mut self,
+        app_handle: AppHandle,
+        flow_manager_state: FlowManagerState,
+        translation: TranslationOverrides,
+        selection: Option<String>,
+        focused_app: Option<FocusedApp>,
+    ) -> Result<(), AudioError> {
+        let profile = focused_app
+            .as_ref()
+            .and_then(|app| app_profile::find(&self.app_profiles, app))
+            .cloned();
+
+        if !self.has_required_api_key(profile.as_ref(), &translation, selection.is_some()) {
+            return Err(AudioError::new(
+                ErrorKind::Config,
+                "OpenAI API key is required. Please set it in Settings or via OPENAI_API_KEY environment variable.",
+            ));
+        }
+        self.check_budget()?;
+
+        // Set only now, since a rejected start must not change the running flow's settings
         self.cancel_flow().await;
+        self.last_audio_path = None;
+        self.active_translation = translation;
+        self.active_selection = selection.clone();
+        self.last_focused_app = focused_app;
+        self.active_profile = profile;
+        if let Some(app) = &self.last_focused_app {
+            println!("Focused app: {}", app.name());
+        }
+        if let Some(profile) = &self.active_profile {
+            println!("Using app profile for '{}'", profile.app);
+        }
 
         let (stop_sender, stop_receiver) = oneshot::channel();
 
-        let callback = Self::create_flow_callback(app_handle, flow_manager_state, CallbackMode::Full);
+        let output_mode = self.get_output_mode().to_string();
+        let callback = Self::create_flow_callback(
+            app_handle.clone(),
+            flow_manager_state,
+            CallbackMode::Full,
+            output_mode,
+            selection.clone(),
+        );
 
-        let prompt_text = self.get_selected_prompt_text();
-        let api_key = self.get_effective_api_key();
+        let targets = self
+            .create_transcription_targets()
+            .map_err(|message| AudioError::new(ErrorKind::Config, message))?;
         let flow = Arc::new(Flow::new(
             callback,
-            self.model.clone(),
-            self.rewrite_enabled,
-            self.omit_final_punctuation,
+            targets,
             Arc::clone(&self.audio_manager),
-            prompt_text,
-            api_key,
+            self.flow_config(&app_handle),
         ));
 
         self.current_flow = Some(Arc::clone(&flow));
         self.stop_sender = Some(stop_sender);
 
+        let mode = match selection {
+            Some(selection) => FlowMode::RecordCommand { stop_signal: stop_receiver, selection },
+            None => FlowMode::RecordAndTranscribe { stop_signal: stop_receiver },
+        };
         tokio::spawn(async move {
-            if let Err(e) = flow.run(FlowMode::RecordAndTranscribe { stop_signal: stop_receiver }).await {
+            if let Err(e) = flow.run(mode).await {
                 eprintln!("Flow error: {}", e);
             }
         });
@@ -195,7 +557,7 @@ impl FlowManager {
         Ok(())
     }
 
-    pub async fn stop_flow(&mut self) -> Result<(), String> {
+    pub async fn stop_flow(&mut self) -> Result<(), AudioError> {
         println!("Flow manager: Stopping flow");
         if let Some(sender) = self.stop_sender.take() {
             println!("Flow manager: Sending stop signal");
@@ -203,11 +565,11 @@ impl FlowManager {
                 Ok(_) => Ok(()),
                 Err(e) => {
                     eprintln!("Flow manager: Failed to send stop signal: {:?}", e);
-                    Err("Failed to send stop signal".to_string())
+                    Err(AudioError::new(ErrorKind::Internal, "Failed to send stop signal"))
                 }
             }
         } else {
-            Err("No active flow to stop".to_string())
+            Err(AudioError::new(ErrorKind::Internal, "No active flow to stop"))
         }
     }
 
@@ -227,44 +589,60 @@ impl FlowManager {
         }
     }
 
-    pub fn store_audio_data(&mut self, audio_data: Vec<u8>) {
+    pub fn store_audio_data(&mut self, audio_data: Vec<u8>, duration_secs: f64) {
         self.retry_audio_data = Some(audio_data);
+        self.retry_audio_duration_secs = duration_secs;
+        self.retry_chunk_transcripts.clear();
+        self.retry_selection = self.active_selection.clone();
     }
 
     pub fn clear_audio_data(&mut self) {
         self.retry_audio_data = None;
+        self.retry_chunk_transcripts.clear();
+        self.retry_selection = None;
     }
 
     pub fn has_retry_data(&self) -> bool {
         self.retry_audio_data.is_some()
     }
 
-    pub async fn retry_transcription(&mut self, app_handle: AppHandle, flow_manager_state: FlowManagerState) -> Result<(), String> {
+    pub async fn retry_transcription(&mut self, app_handle: AppHandle, flow_manager_state: FlowManagerState) -> Result<(), AudioError> {
         let audio_data = self.retry_audio_data.clone().ok_or_else(|| {
-            "No recorded audio available for retry".to_string()
+            AudioError::new(ErrorKind::Internal, "No recorded audio available for retry")
         })?;
+        let duration_secs = self.retry_audio_duration_secs;
+        let chunk_transcripts = self.retry_chunk_transcripts.clone();
+        let selection = self.retry_selection.clone();
+
+        self.check_budget()?;
 
         self.cancel_flow().await;
+        self.active_selection = selection.clone();
 
-        let callback = Self::create_flow_callback(app_handle, flow_manager_state, CallbackMode::RetryOnly);
+        let output_mode = self.get_output_mode().to_string();
+        let callback = Self::create_flow_callback(
+            app_handle.clone(),
+            flow_manager_state,
+            CallbackMode::RetryOnly,
+            output_mode,
+            selection.clone(),
+        );
 
-        let prompt_text = self.get_selected_prompt_text();
-        let api_key = self.get_effective_api_key();
+        let targets = self
+            .create_transcription_targets()
+            .map_err(|message| AudioError::new(ErrorKind::Config, message))?;
         let flow = Arc::new(Flow::new(
             callback,
-            self.model.clone(),
-            self.rewrite_enabled,
-            self.omit_final_punctuation,
+            targets,
             Arc::clone(&self.audio_manager),
-            prompt_text,
-            api_key,
+            self.flow_config(&app_handle),
         ));
         let flow_clone = Arc::clone(&flow);
 
         self.current_flow = Some(flow);
 
         tokio::spawn(async move {
-            if let Err(e) = flow_clone.run(FlowMode::TranscribeOnly { audio_data }).await {
+            if let Err(e) = flow_clone.run(FlowMode::TranscribeOnly { audio_data, duration_secs, chunk_transcripts, selection }).await {
                 eprintln!("Retry transcription error: {}", e);
             }
         });
@@ -272,34 +650,251 @@ impl FlowManager {
         Ok(())
     }
 
+    fn flow_config(&self, app_handle: &AppHandle) -> FlowConfig {
+        FlowConfig {
+            rewrite_enabled: self.rewrites(),
+            omit_final_punctuation: self.omit_final_punctuation,
+            dictation_rules: self.dictation_rules.clone(),
+            // Edits change typed text, so they only make sense when typing
+            voice_edits: self.voice_edits && self.active_selection.is_none() && self.get_output_mode() == "type",
+            replacements: self.replacement_rules.clone(),
+            rewrite_prompt_id: self.prompt_id().to_string(),
+            rewrite_prompt: self.get_selected_prompt_text(),
+            prompt_variables: self.prompt_variables(app_handle),
+            rewrite: self.rewrite_settings.clone(),
+            stream_rewrite: self.stream_rewrite,
+            rewrite_failure_policy: self.rewrite_failure_policy,
+            api_key: self.get_effective_api_key(),
+            live_transcription: self.live_transcription,
+            language: transcription::language_hint(&self.language),
+            transcription_prompt: Some(self.transcription_prompt.trim().to_string())
+                .filter(|prompt| !prompt.is_empty()),
+            // An instruction for selected text does not continue earlier dictation
+            context: if self.active_selection.is_some() { Vec::new() } else { self.conversation_context() },
+            timestamps: self.transcription_mode == transcription::TRANSCRIPTION_MODE_TIMESTAMPS,
+            translate: self.translates_to_english(),
+            diarize: self.transcription_mode == transcription::TRANSCRIPTION_MODE_DIARIZED,
+            rewrite_target_language: self.rewrite_target_language(),
+            retry: RetryPolicy {
+                max_retries: self.max_retries,
+                initial_delay: Duration::from_millis(self.retry_initial_delay_ms),
+            },
+            input_devices: self.input_devices(),
+            disconnect_policy: self.disconnect_policy,
+        }
+    }
+
+    /// Chosen input device, if any, then its fallbacks
+    fn input_devices(&self) -> Vec<String> {
+        Some(self.input_device.clone())
+            .filter(|device| !device.trim().is_empty())
+            .into_iter()
+            .chain(self.input_device_fallbacks.iter().cloned())
+            .collect()
+    }
+
+    /// Recent history texts to send as prior context, oldest first
+    fn conversation_context(&self) -> Vec<String> {
+        context::recent(
+            self.transcription_history.iter().map(|entry| (entry.timestamp, entry.text.as_str())),
+            now_secs(),
+            self.context_entries,
+            self.context_window_minutes,
+        )
+    }
+
+    /// Values for the selected prompt's variables, except the transcript
+    fn prompt_variables(&self, app_handle: &AppHandle) -> Variables {
+        let prompt = self.get_selected_prompt_text();
+        let clipboard = if self.rewrites() && template::uses(&prompt, template::CLIPBOARD) {
+            app_handle.clipboard().read_text().unwrap_or_else(|e| {
+                eprintln!("Could not read clipboard for the rewrite prompt: {}", e);
+                String::new()
+            })
+        } else {
+            String::new()
+        };
+        Variables {
+            transcript: String::new(),
+            date: template::today(),
+            previous_transcript: self.transcription_history.last().map(|e| e.text.clone()).unwrap_or_default(),
+            clipboard,
+            language: transcription::language_hint(&self.language).unwrap_or_default(),
+        }
+    }
+
     pub fn set_model(&mut self, model: String) -> Result<(), String> {
-        // Accept only allowed models
-        match model.as_str() {
-            "whisper-1" | "gpt-4o-transcribe" => {
-                self.model = model;
-                Ok(())
+        // Accept only models the selected backend supports
+        self.create_transcriber()?.validate_model(&model)?;
+        self.model = model;
+        Ok(())
+    }
+
+    pub fn set_transcription_backend(&mut self, backend: String) -> Result<(), String> {
+        if !transcription::AVAILABLE_BACKENDS.contains(&backend.as_str()) {
+            return Err(format!("Invalid transcription backend: {}", backend));
+        }
+        // Fails if the backend is missing required settings such as a base URL
+        transcription::create_backend(&backend, self.get_effective_api_key(), &self.get_backend_settings(&backend))?;
+        self.transcription_backend = backend;
+        Ok(())
+    }
+
+    pub fn set_backend_settings(&mut self, backend_settings: BTreeMap<String, BackendSettings>) -> Result<(), String> {
+        for (backend, settings) in &backend_settings {
+            if !transcription::AVAILABLE_BACKENDS.contains(&backend.as_str()) {
+                return Err(format!("Invalid transcription backend: {}", backend));
+            }
+            transcription::validate_backend_settings(settings)
+                .map_err(|e| format!("{}: {}", backend, e))?;
+        }
+        self.backend_settings = backend_settings;
+        Ok(())
+    }
+
+    fn get_backend_settings(&self, backend: &str) -> BackendSettings {
+        self.backend_settings.get(backend).cloned().unwrap_or_default()
+    }
+
+    fn create_transcriber(&self) -> Result<Arc<dyn TranscriptionBackend>, String> {
+        transcription::create_backend(
+            &self.transcription_backend,
+            self.get_effective_api_key(),
+            &self.get_backend_settings(&self.transcription_backend),
+        )
+    }
+
+    /// The selected backend and model followed by the configured fallbacks
+    ///
+    /// Fallbacks that cannot be used for this recording, e.g. because their
+    /// backend's base URL was cleared, they lack an API key or cannot
+    /// translate, are skipped.
+    fn create_transcription_targets(&self) -> Result<Vec<TranscriptionTarget>, String> {
+        let mut targets = vec![TranscriptionTarget {
+            backend: self.create_transcriber()?,
+            model: self.model.clone(),
+        }];
+        for fallback in &self.fallback_models {
+            let usable = self.check_fallback(fallback).and_then(|()| self.create_fallback_target(fallback));
+            match usable {
+                Ok(target) => targets.push(target),
+                Err(e) => eprintln!("Skipping fallback {}/{}: {}", fallback.backend, fallback.model, e),
+            }
+        }
+        Ok(targets)
+    }
+
+    fn check_fallback(&self, fallback: &FallbackModel) -> Result<(), String> {
+        transcription::validate_fallback(
+            fallback,
+            &self.transcription_backend,
+            &self.transcription_mode,
+            self.translates_to_english(),
+            self.allow_remote_fallback,
+        )?;
+        if self.missing_api_key(&fallback.backend) {
+            return Err("no OpenAI API key is set".to_string());
+        }
+        Ok(())
+    }
+
+    fn create_fallback_target(&self, fallback: &FallbackModel) -> Result<TranscriptionTarget, String> {
+        TranscriptionTarget::create(
+            &fallback.backend,
+            &fallback.model,
+            self.get_effective_api_key(),
+            &self.get_backend_settings(&fallback.backend),
+        )
+    }
+
+    pub fn set_fallback_models(&mut self, fallback_models: Vec<FallbackModel>) -> Result<(), String> {
+        for fallback in &fallback_models {
+            if !transcription::AVAILABLE_BACKENDS.contains(&fallback.backend.as_str()) {
+                return Err(format!("Invalid fallback backend: {}", fallback.backend));
             }
-            _ => Err("Invalid model".to_string()),
+            self.create_fallback_target(fallback)
+                .map_err(|e| format!("Fallback {}/{}: {}", fallback.backend, fallback.model, e))?;
         }
+        self.fallback_models = fallback_models;
+        Ok(())
+    }
+
+    /// Build the backend and model for a connection test, applying any unsaved
+    /// values from the settings window on top of the saved ones
+    pub fn connection_test_target(
+        &self,
+        backend: Option<String>,
+        settings: Option<BackendSettings>,
+        model: Option<String>,
+    ) -> Result<(Arc<dyn TranscriptionBackend>, String), String> {
+        let backend = backend.unwrap_or_else(|| self.transcription_backend.clone());
+        let settings = settings.unwrap_or_else(|| self.get_backend_settings(&backend));
+        let model = model.unwrap_or_else(|| self.model.clone());
+
+        let transcriber = transcription::create_backend(&backend, self.get_effective_api_key(), &settings)?;
+        transcriber.validate_model(&model)?;
+        Ok((transcriber, model))
     }
 
     pub fn set_rewrite_enabled(&mut self, enabled: bool) {
         self.rewrite_enabled = enabled;
     }
 
+    /// Prompt for the current recording, from its app profile if it has one
+    fn prompt_id(&self) -> &str {
+        self.active_profile.as_ref().map_or(&self.selected_prompt_id, |profile| &profile.prompt_id)
+    }
+
+    /// Whether the current recording is rewritten with the prompt
+    fn rewrites(&self) -> bool {
+        self.rewrites_with(self.active_profile.as_ref())
+    }
+
+    fn rewrites_with(&self, profile: Option<&AppProfile>) -> bool {
+        profile.map_or(self.rewrite_enabled, |profile| profile.rewrite_enabled)
+    }
+
+    fn prompt_exists(&self, id: &str) -> bool {
+        id == "default" || self.custom_prompts.iter().any(|p| p.id == id)
+    }
+
+    /// Whether the current recording is translated to English by the backend
+    fn translates_to_english(&self) -> bool {
+        self.active_translation.translate_to_english.unwrap_or(self.translate_to_english)
+    }
+
+    /// Language the rewrite step translates the current recording into, if any
+    fn rewrite_target_language(&self) -> Option<String> {
+        self.rewrite_target_language_with(&self.active_translation)
+    }
+
+    fn rewrite_target_language_with(&self, translation: &TranslationOverrides) -> Option<String> {
+        let language = translation
+            .rewrite_target_language
+            .as_deref()
+            .unwrap_or(&self.rewrite_target_language);
+        rewrite::target_language(language)
+    }
+
     fn get_config_path() -> Option<PathBuf> {
         ProjectDirs::from("com", "muse", "app")
             .map(|proj_dirs| proj_dirs.config_dir().join("settings.json"))
     }
 
+    /// The saved voice command shortcut, read before the manager is created
+    pub fn load_command_shortcut() -> String {
+        Self::load_settings().command_shortcut
+    }
+
     fn load_settings() -> PersistedSettings {
         if let Some(config_path) = Self::get_config_path() {
             if config_path.exists() {
                 match fs::read_to_string(&config_path) {
                     Ok(content) => {
                         match serde_json::from_str::<PersistedSettings>(&content) {
-                            Ok(settings) => {
+                            Ok(mut settings) => {
                                 println!("Loaded settings from {:?}", config_path);
+                                migrate_legacy_prompts(&mut settings.custom_prompts);
                                 return settings;
                             }
                             Err(e) => {
@@ -316,17 +911,47 @@ impl FlowManager {
         PersistedSettings::default()
     }
 
-    fn save_settings(&self) -> Result<(), String> {
-        let settings = PersistedSettings {
+    fn persisted_settings(&self) -> PersistedSettings {
+        PersistedSettings {
+            transcription_backend: self.transcription_backend.clone(),
+            backend_settings: self.backend_settings.clone(),
             model: self.model.clone(),
+            fallback_models: self.fallback_models.clone(),
+            allow_remote_fallback: self.allow_remote_fallback,
+            language: self.language.clone(),
+            transcription_prompt: self.transcription_prompt.clone(),
+            context_entries: self.context_entries,
+            context_window_minutes: self.context_window_minutes,
+            transcription_mode: self.transcription_mode.clone(),
+            max_retries: self.max_retries,
+            retry_initial_delay_ms: self.retry_initial_delay_ms,
+            monthly_budget_usd: self.monthly_budget_usd,
             rewrite_enabled: self.rewrite_enabled,
+            rewrite_settings: self.rewrite_settings.clone(),
+            stream_rewrite: self.stream_rewrite,
+            rewrite_failure_policy: self.rewrite_failure_policy,
+            translate_to_english: self.translate_to_english,
+            rewrite_target_language: self.rewrite_target_language.clone(),
             omit_final_punctuation: self.omit_final_punctuation,
+            dictation_rules: self.dictation_rules.clone(),
+            live_transcription: self.live_transcription,
+            input_device: self.input_device.clone(),
+            input_device_fallbacks: self.input_device_fallbacks.clone(),
+            disconnect_policy: self.disconnect_policy,
             selected_prompt_id: self.selected_prompt_id.clone(),
             custom_prompts: self.custom_prompts.clone(),
             api_key: self.api_key.clone(),
             shortcuts: self.shortcuts.clone(),
+            command_shortcut: self.command_shortcut.clone(),
             output_mode: self.output_mode.clone(),
-        };
+            voice_edits: self.voice_edits,
+            app_profiles: self.app_profiles.clone(),
+            replacement_rules: self.replacement_rules.clone(),
+        }
+    }
+
+    fn save_settings(&self) -> Result<(), String> {
+        let settings = self.persisted_settings();
 
         let config_path = Self::get_config_path()
             .ok_or_else(|| "Could not determine config directory".to_string())?;
@@ -347,62 +972,268 @@ impl FlowManager {
         Ok(())
     }
 
+    fn get_usage_path() -> Option<PathBuf> {
+        Self::get_config_dir().map(|dir| dir.join("usage.json"))
+    }
+
+    fn load_usage() -> Vec<UsageRecord> {
+        let Some(usage_path) = Self::get_usage_path().filter(|path| path.exists()) else {
+            return Vec::new();
+        };
+        match fs::read_to_string(&usage_path) {
+            Ok(content) => match serde_json::from_str::<Vec<UsageRecord>>(&content) {
+                Ok(records) => records,
+                Err(e) => {
+                    eprintln!("Failed to parse usage file: {}, starting a new one", e);
+                    Vec::new()
+                }
+            },
+            Err(e) => {
+                eprintln!("Failed to read usage file: {}, starting a new one", e);
+                Vec::new()
+            }
+        }
+    }
+
+    fn save_usage(&self) -> Result<(), String> {
+        let usage_path = Self::get_usage_path()
+            .ok_or_else(|| "Could not determine config directory".to_string())?;
+
+        if let Some(parent) = usage_path.parent() {
+            fs::create_dir_all(parent).map_err(|e| {
+                format!("Failed to create config directory: {}", e)
+            })?;
+        }
+
+        let json = serde_json::to_string(&self.usage_records)
+            .map_err(|e| format!("Failed to serialize usage: {}", e))?;
+
+        fs::write(&usage_path, json)
+            .map_err(|e| format!("Failed to write usage file: {}", e))
+    }
+
+    /// Price a completed flow and add it to the usage log
+    pub fn record_usage(&mut self, usage: FlowUsage) {
+        let cost_usd = usage::cost_usd(&usage);
+        println!(
+            "Usage: {:.1} s audio ({}), {:?} rewrite tokens, ${:.4}",
+            usage.audio_secs, usage.transcription_model, usage.rewrite_tokens, cost_usd
+        );
+        self.usage_records.push(UsageRecord {
+            timestamp: now_secs(),
+            usage,
+            cost_usd,
+        });
+        if let Err(e) = self.save_usage() {
+            eprintln!("Failed to save usage: {}", e);
+        }
+    }
+
+    /// Usage per day or month, for a time zone `utc_offset_minutes` east of UTC
+    pub fn usage_totals(&self, period: Period, utc_offset_minutes: i32) -> Vec<UsageTotal> {
+        usage::totals(&self.usage_records, period, i64::from(utc_offset_minutes) * 60)
+    }
+
+    /// Refuse to start a recording once this month's spend (in local time) reaches the budget
+    fn check_budget(&self) -> Result<(), AudioError> {
+        if self.monthly_budget_usd <= 0.0 {
+            return Ok(());
+        }
+        let spent = usage::month_cost(&self.usage_records, now_secs(), usage::local_utc_offset_secs());
+        if spent >= self.monthly_budget_usd {
+            return Err(AudioError::new(
+                ErrorKind::Config,
+                format!(
+                    "Monthly budget of ${:.2} reached (${:.2} spent). Raise it in Settings to keep recording.",
+                    self.monthly_budget_usd, spent
+                ),
+            ));
+        }
+        Ok(())
+    }
+
     fn get_selected_prompt_text(&self) -> String {
-        if self.selected_prompt_id == "default" {
+        let prompt_id = self.prompt_id();
+        if prompt_id == "default" {
             return DEFAULT_PROMPT_TEXT.to_string();
         }
 
         self.custom_prompts
             .iter()
-            .find(|p| p.id == self.selected_prompt_id)
+            .find(|p| p.id == prompt_id)
             .map(|p| p.text.clone())
             .unwrap_or_else(|| {
-                eprintln!("Selected prompt '{}' not found, using default", self.selected_prompt_id);
+                eprintln!("Selected prompt '{}' not found, using default", prompt_id);
                 DEFAULT_PROMPT_TEXT.to_string()
             })
     }
 
     pub fn options(&self) -> Options {
+        Self::options_from_settings(self.persisted_settings())
+    }
+
+    /// Options reported before the flow manager has been initialized
+    pub fn default_options() -> Options {
+        Self::options_from_settings(PersistedSettings::default())
+    }
+
+    fn options_from_settings(settings: PersistedSettings) -> Options {
         let mut all_prompts = vec![RewritePrompt {
             id: "default".to_string(),
             name: "Default (Built-in)".to_string(),
             text: DEFAULT_PROMPT_TEXT.to_string(),
         }];
-        all_prompts.extend(self.custom_prompts.clone());
+        all_prompts.extend(settings.custom_prompts);
 
         let api_key_from_env = std::env::var("OPENAI_API_KEY").is_ok();
 
         Options {
-            model: self.model.clone(),
-            rewrite_enabled: self.rewrite_enabled,
-            omit_final_punctuation: self.omit_final_punctuation,
-            selected_prompt_id: self.selected_prompt_id.clone(),
+            transcription_backend: settings.transcription_backend,
+            backend_settings: settings.backend_settings,
+            model: settings.model,
+            fallback_models: settings.fallback_models,
+            allow_remote_fallback: settings.allow_remote_fallback,
+            language: settings.language,
+            transcription_prompt: settings.transcription_prompt,
+            context_entries: settings.context_entries,
+            context_window_minutes: settings.context_window_minutes,
+            transcription_mode: settings.transcription_mode,
+            max_retries: settings.max_retries,
+            retry_initial_delay_ms: settings.retry_initial_delay_ms,
+            monthly_budget_usd: settings.monthly_budget_usd,
+            rewrite_enabled: settings.rewrite_enabled,
+            rewrite_settings: settings.rewrite_settings,
+            stream_rewrite: settings.stream_rewrite,
+            rewrite_failure_policy: settings.rewrite_failure_policy,
+            translate_to_english: settings.translate_to_english,
+            rewrite_target_language: settings.rewrite_target_language,
+            omit_final_punctuation: settings.omit_final_punctuation,
+            dictation_rules: settings.dictation_rules,
+            live_transcription: settings.live_transcription,
+            input_device: settings.input_device,
+            input_device_fallbacks: settings.input_device_fallbacks,
+            disconnect_policy: settings.disconnect_policy,
+            selected_prompt_id: settings.selected_prompt_id,
             custom_prompts: all_prompts,
-            api_key: self.api_key.clone(),
+            api_key: settings.api_key,
             api_key_from_env,
-            shortcuts: self.shortcuts.clone(),
-            output_mode: self.output_mode.clone(),
+            shortcuts: settings.shortcuts,
+            command_shortcut: settings.command_shortcut,
+            output_mode: settings.output_mode,
+            voice_edits: settings.voice_edits,
+            app_profiles: settings.app_profiles,
         }
     }
 
-    pub fn add_to_history(&mut self, text: String) {
-        let timestamp = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .map(|d| d.as_secs())
-            .unwrap_or(0);
-        self.transcription_history.push(TranscriptionHistoryEntry { text, timestamp });
+    pub fn add_to_history(&mut self, transcript: Transcript) {
+        let timestamp = now_secs();
+        let rewrite = transcript.rewrite;
+        self.transcription_history.push(TranscriptionHistoryEntry {
+            text: transcript.text,
+            timestamp,
+            segments: transcript.segments,
+            words: transcript.words,
+            turns: transcript.turns,
+            audio_path: self.last_audio_path.clone(),
+            model: transcript.model,
+            raw_text: transcript.raw_text,
+            rewritten_text: rewrite.as_ref().map(|r| r.text.clone()),
+            prompt_id: rewrite.as_ref().and_then(|r| r.prompt_id.clone()),
+            rewrite_model: rewrite.map(|r| r.model),
+        });
     }
 
     pub fn get_history(&self) -> Vec<TranscriptionHistoryEntry> {
         self.transcription_history.clone()
     }
 
-    pub fn get_history_entry(&self, index: usize) -> Option<String> {
-        self.transcription_history.get(index).map(|e| e.text.clone())
+    pub fn get_history_text(&self, index: usize, version: TextVersion) -> Result<String, String> {
+        let entry = self
+            .transcription_history
+            .get(index)
+            .ok_or_else(|| format!("No history entry at index {}", index))?;
+        match version {
+            TextVersion::Final => Ok(entry.text.clone()),
+            TextVersion::Raw => Ok(entry.raw_text.clone()),
+            TextVersion::Rewritten => entry
+                .rewritten_text
+                .clone()
+                .ok_or_else(|| "This transcription was not rewritten".to_string()),
+        }
+    }
+
+    /// Path of the recording a history entry was transcribed from, if it was saved
+    pub fn get_history_audio_path(&self, index: usize) -> Option<String> {
+        self.transcription_history.get(index).and_then(|e| e.audio_path.clone())
     }
 
+    /// Output mode of the current recording, from its app profile if it has one
     pub fn get_output_mode(&self) -> &str {
-        &self.output_mode
+        // Typing over the selection is what replaces it
+        if self.active_selection.is_some() {
+            return "type";
+        }
+        self.active_profile.as_ref().map_or(&self.output_mode, |profile| &profile.output_mode)
+    }
+
+    pub fn replacement_rules(&self) -> Vec<ReplacementRule> {
+        self.replacement_rules.clone()
+    }
+
+    /// Validate and save a replacement rule, returning it with its new id
+    pub fn add_replacement_rule(&mut self, mut rule: ReplacementRule) -> Result<ReplacementRule, String> {
+        replacements::validate(&rule)?;
+        let mut n = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_millis());
+        while self.replacement_rules.iter().any(|r| r.id == format!("rule-{}", n)) {
+            n += 1;
+        }
+        rule.id = format!("rule-{}", n);
+        self.replacement_rules.push(rule.clone());
+        self.save_settings()?;
+        Ok(rule)
+    }
+
+    pub fn remove_replacement_rule(&mut self, id: &str) -> Result<(), String> {
+        let count = self.replacement_rules.len();
+        self.replacement_rules.retain(|rule| rule.id != id);
+        if self.replacement_rules.len() == count {
+            return Err(format!("No replacement rule with id {}", id));
+        }
+        self.save_settings()
+    }
+
+    /// `text` after the saved rules, or after only `rule` if one is given
+    pub fn test_replacements(&self, text: &str, rule: Option<ReplacementRule>) -> Result<String, String> {
+        match rule {
+            Some(rule) => {
+                replacements::validate(&rule)?;
+                Ok(replacements::apply(&[rule], text))
+            }
+            None => Ok(replacements::apply(&self.replacement_rules, text)),
+        }
+    }
+
+    pub fn get_last_focused_app(&self) -> Option<FocusedApp> {
+        self.last_focused_app.clone()
+    }
+
+    /// Whether an OpenAI key is available for every step of a recording with
+    /// `profile` and `translation` that needs one; a voice command always
+    /// runs the rewrite step. Fallbacks without a key are left out of the
+    /// targets instead.
+    fn has_required_api_key(&self, profile: Option<&AppProfile>, translation: &TranslationOverrides, command: bool) -> bool {
+        let rewrite_needs_key = (command
+            || self.rewrites_with(profile)
+            || self.rewrite_target_language_with(translation).is_some())
+            && rewrite::requires_openai_key(&self.rewrite_settings);
+        !self.missing_api_key(&self.transcription_backend) && (!rewrite_needs_key || self.has_valid_api_key())
+    }
+
+    /// Whether transcribing with `backend` needs the global OpenAI key and none is set
+    fn missing_api_key(&self, backend: &str) -> bool {
+        transcription::requires_api_key(backend)
+            && self.get_backend_settings(backend).api_key.trim().is_empty()
+            && !self.has_valid_api_key()
     }
 
     fn has_valid_api_key(&self) -> bool {
@@ -421,23 +1252,159 @@ impl FlowManager {
             .map(|proj_dirs| proj_dirs.config_dir().to_path_buf())
     }
 
-    pub fn update_options(&mut self, patch: OptionsPatch) -> Result<OptionsPatch, String> {
+    /// Apply the changed options; a rejected patch is a `Config` error
+    pub fn update_options(&mut self, patch: OptionsPatch) -> Result<OptionsPatch, AudioError> {
+        self.apply_options(patch).map_err(|message| AudioError::new(ErrorKind::Config, message))
+    }
+
+    fn apply_options(&mut self, patch: OptionsPatch) -> Result<OptionsPatch, String> {
         let mut applied = OptionsPatch::default();
 
+        // Checked together, since a patch may change any of them
+        if patch.transcription_backend.is_some()
+            || patch.model.is_some()
+            || patch.transcription_mode.is_some()
+            || patch.translate_to_english.is_some()
+        {
+            transcription::validate_mode(
+                patch.transcription_backend.as_deref().unwrap_or(&self.transcription_backend),
+                patch.model.as_deref().unwrap_or(&self.model),
+                patch.transcription_mode.as_deref().unwrap_or(&self.transcription_mode),
+                patch.translate_to_english.unwrap_or(self.translate_to_english),
+            )?;
+        }
+        if patch.transcription_backend.is_some()
+            || patch.transcription_mode.is_some()
+            || patch.translate_to_english.is_some()
+            || patch.fallback_models.is_some()
+            || patch.allow_remote_fallback.is_some()
+        {
+            for fallback in patch.fallback_models.as_ref().unwrap_or(&self.fallback_models) {
+                transcription::validate_fallback(
+                    fallback,
+                    patch.transcription_backend.as_deref().unwrap_or(&self.transcription_backend),
+                    patch.transcription_mode.as_deref().unwrap_or(&self.transcription_mode),
+                    patch.translate_to_english.unwrap_or(self.translate_to_english),
+                    patch.allow_remote_fallback.unwrap_or(self.allow_remote_fallback),
+                )
+                .map_err(|e| format!("Fallback {}/{}: {}", fallback.backend, fallback.model, e))?;
+            }
+        }
+
+        // Backend settings and backend first so a model in the same patch is
+        // validated against them
+        if let Some(backend_settings) = patch.backend_settings {
+            self.set_backend_settings(backend_settings.clone())?;
+            applied.backend_settings = Some(backend_settings);
+        }
+        if let Some(backend) = patch.transcription_backend {
+            self.set_transcription_backend(backend.clone())?;
+            applied.transcription_backend = Some(backend);
+        }
         if let Some(model) = patch.model {
             self.set_model(model.clone())?;
             applied.model = Some(model);
         }
+        if let Some(fallback_models) = patch.fallback_models {
+            self.set_fallback_models(fallback_models.clone())?;
+            applied.fallback_models = Some(fallback_models);
+        }
+        if let Some(allow) = patch.allow_remote_fallback {
+            self.allow_remote_fallback = allow;
+            applied.allow_remote_fallback = Some(allow);
+        }
+        if let Some(language) = patch.language {
+            transcription::validate_language(&language)?;
+            self.language = language.clone();
+            applied.language = Some(language);
+        }
+        if let Some(prompt) = patch.transcription_prompt {
+            self.transcription_prompt = prompt.clone();
+            applied.transcription_prompt = Some(prompt);
+        }
+        if patch.context_entries.is_some() || patch.context_window_minutes.is_some() {
+            let entries = patch.context_entries.unwrap_or(self.context_entries);
+            let window_minutes = patch.context_window_minutes.unwrap_or(self.context_window_minutes);
+            context::validate_settings(entries, window_minutes)?;
+            self.context_entries = entries;
+            self.context_window_minutes = window_minutes;
+            applied.context_entries = patch.context_entries;
+            applied.context_window_minutes = patch.context_window_minutes;
+        }
+        if let Some(mode) = patch.transcription_mode {
+            self.transcription_mode = mode.clone();
+            applied.transcription_mode = Some(mode);
+        }
+        if patch.max_retries.is_some() || patch.retry_initial_delay_ms.is_some() {
+            let max_retries = patch.max_retries.unwrap_or(self.max_retries);
+            let initial_delay_ms = patch.retry_initial_delay_ms.unwrap_or(self.retry_initial_delay_ms);
+            retry::validate_settings(max_retries, initial_delay_ms)?;
+            self.max_retries = max_retries;
+            self.retry_initial_delay_ms = initial_delay_ms;
+            applied.max_retries = patch.max_retries;
+            applied.retry_initial_delay_ms = patch.retry_initial_delay_ms;
+        }
+        if let Some(budget) = patch.monthly_budget_usd {
+            usage::validate_budget(budget)?;
+            self.monthly_budget_usd = budget;
+            applied.monthly_budget_usd = Some(budget);
+        }
         if let Some(enabled) = patch.rewrite_enabled {
             self.set_rewrite_enabled(enabled);
             applied.rewrite_enabled = Some(enabled);
         }
+        if let Some(rewrite_settings) = patch.rewrite_settings {
+            rewrite::validate_settings(&rewrite_settings)?;
+            self.rewrite_settings = rewrite_settings.clone();
+            applied.rewrite_settings = Some(rewrite_settings);
+        }
+        if let Some(translate) = patch.translate_to_english {
+            self.translate_to_english = translate;
+            applied.translate_to_english = Some(translate);
+        }
+        if let Some(language) = patch.rewrite_target_language {
+            rewrite::validate_target_language(&language)?;
+            self.rewrite_target_language = language.clone();
+            applied.rewrite_target_language = Some(language);
+        }
         if let Some(omit) = patch.omit_final_punctuation {
             self.omit_final_punctuation = omit;
             applied.omit_final_punctuation = Some(omit);
         }
+        if let Some(rules) = patch.dictation_rules {
+            self.dictation_rules = rules.clone();
+            applied.dictation_rules = Some(rules);
+        }
+        if let Some(stream) = patch.stream_rewrite {
+            self.stream_rewrite = stream;
+            applied.stream_rewrite = Some(stream);
+        }
+        if let Some(policy) = patch.rewrite_failure_policy {
+            self.rewrite_failure_policy = policy;
+            applied.rewrite_failure_policy = Some(policy);
+        }
+        if let Some(live) = patch.live_transcription {
+            self.live_transcription = live;
+            applied.live_transcription = Some(live);
+        }
+        if patch.input_device.is_some() || patch.input_device_fallbacks.is_some() {
+            let device = patch.input_device.clone().unwrap_or_else(|| self.input_device.clone());
+            let fallbacks = patch
+                .input_device_fallbacks
+                .clone()
+                .unwrap_or_else(|| self.input_device_fallbacks.clone());
+            input_device::validate(&device, &fallbacks)?;
+            self.input_device = device;
+            self.input_device_fallbacks = fallbacks;
+            applied.input_device = patch.input_device;
+            applied.input_device_fallbacks = patch.input_device_fallbacks;
+        }
+        if let Some(policy) = patch.disconnect_policy {
+            self.disconnect_policy = policy;
+            applied.disconnect_policy = Some(policy);
+        }
         if let Some(selected_id) = patch.selected_prompt_id {
-            if selected_id == "default" || self.custom_prompts.iter().any(|p| p.id == selected_id) {
+            if self.prompt_exists(&selected_id) {
                 self.selected_prompt_id = selected_id.clone();
                 applied.selected_prompt_id = Some(selected_id);
             } else {
@@ -445,10 +1412,22 @@ impl FlowManager {
             }
         }
         if let Some(prompts) = patch.custom_prompts {
-            let filtered_prompts: Vec<RewritePrompt> = prompts
+            let mut filtered_prompts: Vec<RewritePrompt> = prompts
                 .into_iter()
                 .filter(|p| p.id != "default")
                 .collect();
+            migrate_legacy_prompts(&mut filtered_prompts);
+            for prompt in &filtered_prompts {
+                template::validate(&prompt.text).map_err(|e| format!("Prompt '{}': {}", prompt.name, e))?;
+            }
+            // Profiles saved along with the prompts are checked below
+            if patch.app_profiles.is_none()
+                && let Some(profile) = self.app_profiles.iter().find(|profile| {
+                    profile.prompt_id != "default" && !filtered_prompts.iter().any(|p| p.id == profile.prompt_id)
+                })
+            {
+                return Err(format!("The prompt is used by the app profile '{}'", profile.app.trim()));
+            }
             self.custom_prompts = filtered_prompts.clone();
             applied.custom_prompts = Some(filtered_prompts);
         }
@@ -460,14 +1439,36 @@ impl FlowManager {
             self.shortcuts = shortcuts.clone();
             applied.shortcuts = Some(shortcuts);
         }
+        // Checked by the caller, since parsing shortcuts is up to the shortcut plugin
+        if let Some(shortcut) = patch.command_shortcut {
+            self.command_shortcut = shortcut.clone();
+            applied.command_shortcut = Some(shortcut);
+        }
         if let Some(output_mode) = patch.output_mode {
-            match output_mode.as_str() {
-                "clipboard" | "type" => {
-                    self.output_mode = output_mode.clone();
-                    applied.output_mode = Some(output_mode);
+            if is_output_mode(&output_mode) {
+                self.output_mode = output_mode.clone();
+                applied.output_mode = Some(output_mode);
+            } else {
+                return Err(format!("Invalid output mode: {}", output_mode));
+            }
+        }
+        if let Some(voice_edits) = patch.voice_edits {
+            self.voice_edits = voice_edits;
+            applied.voice_edits = Some(voice_edits);
+        }
+        if let Some(profiles) = patch.app_profiles {
+            // Checked after the prompts so a profile may use a prompt added in the same save
+            app_profile::validate(&profiles)?;
+            for profile in &profiles {
+                if !self.prompt_exists(&profile.prompt_id) {
+                    return Err(format!("App profile '{}' uses an unknown prompt", profile.app.trim()));
+                }
+                if !is_output_mode(&profile.output_mode) {
+                    return Err(format!("App profile '{}': invalid output mode: {}", profile.app.trim(), profile.output_mode));
                 }
-                _ => return Err(format!("Invalid output mode: {}", output_mode)),
             }
+            self.app_profiles = profiles.clone();
+            applied.app_profiles = Some(profiles);
         }
 
         self.save_settings()?;
@@ -475,6 +1476,34 @@ impl FlowManager {
     }
 }
 
+fn is_output_mode(mode: &str) -> bool {
+    matches!(mode, "clipboard" | "type")
+}
+
+/// Convert prompts saved before templates, which marked the text with `{}`
+fn migrate_legacy_prompts(prompts: &mut [RewritePrompt]) {
+    for prompt in prompts {
+        if let Some(text) = template::migrate_legacy(&prompt.text) {
+            println!("Converted prompt '{}' to {{{{transcript}}}}", prompt.name);
+            prompt.text = text;
+        }
+    }
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Translation settings for a single recording, overriding the saved ones
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct TranslationOverrides {
+    pub translate_to_english: Option<bool>,
+    pub rewrite_target_language: Option<String>,
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct StatusResponse {
     pub state: FlowState,
@@ -487,44 +1516,184 @@ pub struct WaveformChunkPayload {
     pub avg_rms: f32,
 }
 
+#[derive(Serialize, Clone)]
+pub struct PartialTranscriptPayload {
+    pub index: usize,
+    pub text: String,
+}
+
 #[derive(Serialize, Deserialize, Clone)]
 pub struct Options {
+    pub transcription_backend: String,
+    pub backend_settings: BTreeMap<String, BackendSettings>,
     pub model: String,
+    pub fallback_models: Vec<FallbackModel>,
+    pub allow_remote_fallback: bool,
+    pub language: String,
+    pub transcription_prompt: String,
+    pub context_entries: u32,
+    pub context_window_minutes: u32,
+    pub transcription_mode: String,
+    pub max_retries: u32,
+    pub retry_initial_delay_ms: u64,
+    pub monthly_budget_usd: f64,
     pub rewrite_enabled: bool,
+    pub rewrite_settings: RewriteSettings,
+    pub stream_rewrite: bool,
+    pub rewrite_failure_policy: FailurePolicy,
+    pub translate_to_english: bool,
+    pub rewrite_target_language: String,
     pub omit_final_punctuation: bool,
+    pub dictation_rules: DictationRules,
+    pub live_transcription: bool,
+    pub input_device: String,
+    pub input_device_fallbacks: Vec<String>,
+    pub disconnect_policy: DisconnectPolicy,
     pub selected_prompt_id: String,
     pub custom_prompts: Vec<RewritePrompt>,
     pub api_key: String,
     pub api_key_from_env: bool,
     pub shortcuts: String,
+    pub command_shortcut: String,
     pub output_mode: String,
+    pub voice_edits: bool,
+    pub app_profiles: Vec<AppProfile>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Default)]
 pub struct OptionsPatch {
+    pub transcription_backend: Option<String>,
+    pub backend_settings: Option<BTreeMap<String, BackendSettings>>,
     pub model: Option<String>,
+    pub fallback_models: Option<Vec<FallbackModel>>,
+    pub allow_remote_fallback: Option<bool>,
+    pub language: Option<String>,
+    pub transcription_prompt: Option<String>,
+    pub context_entries: Option<u32>,
+    pub context_window_minutes: Option<u32>,
+    pub transcription_mode: Option<String>,
+    pub max_retries: Option<u32>,
+    pub retry_initial_delay_ms: Option<u64>,
+    pub monthly_budget_usd: Option<f64>,
     pub rewrite_enabled: Option<bool>,
+    pub rewrite_settings: Option<RewriteSettings>,
+    pub stream_rewrite: Option<bool>,
+    pub rewrite_failure_policy: Option<FailurePolicy>,
+    pub translate_to_english: Option<bool>,
+    pub rewrite_target_language: Option<String>,
     pub omit_final_punctuation: Option<bool>,
+    pub dictation_rules: Option<DictationRules>,
+    pub live_transcription: Option<bool>,
+    pub input_device: Option<String>,
+    pub input_device_fallbacks: Option<Vec<String>>,
+    pub disconnect_policy: Option<DisconnectPolicy>,
     pub selected_prompt_id: Option<String>,
     pub custom_prompts: Option<Vec<RewritePrompt>>,
     pub api_key: Option<String>,
     pub shortcuts: Option<String>,
+    pub command_shortcut: Option<String>,
     pub output_mode: Option<String>,
+    pub voice_edits: Option<bool>,
+    pub app_profiles: Option<Vec<AppProfile>>,
 }
 
 #[derive(Serialize, Deserialize, Clone)]
 struct PersistedSettings {
+    #[serde(default = "default_transcription_backend")]
+    pub transcription_backend: String,
+    #[serde(default)]
+    pub backend_settings: BTreeMap<String, BackendSettings>,
     pub model: String,
+    #[serde(default)]
+    pub fallback_models: Vec<FallbackModel>,
+    /// Let a local backend fall back to servers, which uploads the audio
+    #[serde(default)]
+    pub allow_remote_fallback: bool,
+    #[serde(default = "default_language")]
+    pub language: String,
+    #[serde(default)]
+    pub transcription_prompt: String,
+    /// History entries sent as prior context; 0 turns context off
+    #[serde(default)]
+    pub context_entries: u32,
+    /// Entries older than this are not used as context
+    #[serde(default = "default_context_window_minutes")]
+    pub context_window_minutes: u32,
+    #[serde(default = "default_transcription_mode")]
+    pub transcription_mode: String,
+    #[serde(default = "default_max_retries")]
+    pub max_retries: u32,
+    #[serde(default = "default_retry_initial_delay_ms")]
+    pub retry_initial_delay_ms: u64,
+    /// Monthly spending cap in USD; 0 means no cap
+    #[serde(default)]
+    pub monthly_budget_usd: f64,
     pub rewrite_enabled: bool,
+    #[serde(default)]
+    pub rewrite_settings: RewriteSettings,
+    #[serde(default)]
+    pub stream_rewrite: bool,
+    #[serde(default)]
+    pub rewrite_failure_policy: FailurePolicy,
+    #[serde(default)]
+    pub translate_to_english: bool,
+    #[serde(default)]
+    pub rewrite_target_language: String,
     pub omit_final_punctuation: bool,
+    #[serde(default)]
+    pub dictation_rules: DictationRules,
+    #[serde(default)]
+    pub live_transcription: bool,
+    /// Microphone to record from, empty for the system default
+    #[serde(default)]
+    pub input_device: String,
+    /// Microphones tried in order when the chosen one is not connected
+    #[serde(default)]
+    pub input_device_fallbacks: Vec<String>,
+    /// What a recording does when its microphone is disconnected
+    #[serde(default)]
+    pub disconnect_policy: DisconnectPolicy,
     pub selected_prompt_id: String,
     pub custom_prompts: Vec<RewritePrompt>,
     #[serde(default)]
     pub api_key: String,
     #[serde(default = "default_shortcuts")]
     pub shortcuts: String,
+    /// Shortcut for voice commands on selected text; registered at startup
+    #[serde(default = "default_command_shortcut")]
+    pub command_shortcut: String,
     #[serde(default = "default_output_mode")]
     pub output_mode: String,
+    #[serde(default)]
+    pub voice_edits: bool,
+    #[serde(default)]
+    pub app_profiles: Vec<AppProfile>,
+    #[serde(default)]
+    pub replacement_rules: Vec<ReplacementRule>,
+}
+
+fn default_transcription_backend() -> String {
+    transcription::BACKEND_OPENAI.to_string()
+}
+
+fn default_language() -> String {
+    transcription::LANGUAGE_AUTO.to_string()
+}
+
+fn default_transcription_mode() -> String {
+    transcription::TRANSCRIPTION_MODE_TEXT.to_string()
+}
+
+fn default_max_retries() -> u32 {
+    3
+}
+
+fn default_context_window_minutes() -> u32 {
+    5
+}
+
+fn default_retry_initial_delay_ms() -> u64 {
+    1000
 }
 
 fn default_output_mode() -> String {
@@ -535,17 +1704,47 @@ fn default_shortcuts() -> String {
     "Alt+Slash".to_string()
 }
 
+fn default_command_shortcut() -> String {
+    "Alt+Shift+Slash".to_string()
+}
+
 impl Default for PersistedSettings {
     fn default() -> Self {
         Self {
+            transcription_backend: default_transcription_backend(),
+            backend_settings: BTreeMap::new(),
             model: "whisper-1".to_string(),
+            fallback_models: Vec::new(),
+            allow_remote_fallback: false,
+            language: default_language(),
+            transcription_prompt: String::new(),
+            context_entries: 0,
+            context_window_minutes: default_context_window_minutes(),
+            transcription_mode: default_transcription_mode(),
+            max_retries: default_max_retries(),
+            retry_initial_delay_ms: default_retry_initial_delay_ms(),
+            monthly_budget_usd: 0.0,
             rewrite_enabled: false,
+            rewrite_settings: RewriteSettings::default(),
+            stream_rewrite: false,
+            rewrite_failure_policy: FailurePolicy::default(),
+            translate_to_english: false,
+            rewrite_target_language: String::new(),
             omit_final_punctuation: false,
+            dictation_rules: DictationRules::default(),
+            live_transcription: false,
+            input_device: String::new(),
+            input_device_fallbacks: Vec::new(),
+            disconnect_policy: DisconnectPolicy::default(),
             selected_prompt_id: "default".to_string(),
             custom_prompts: Vec::new(),
             api_key: String::new(),
             shortcuts: default_shortcuts(),
+            command_shortcut: default_command_shortcut(),
             output_mode: default_output_mode(),
+            voice_edits: false,
+            app_profiles: Vec::new(),
+            replacement_rules: Vec::new(),
         }
     }
 }
@@ -0,0 +1,264 @@
+//! Microphone selection
+//!
+//! A device chosen in settings is used when it is connected; otherwise the
+//! fallbacks are tried in order, e.g. a dock microphone after a USB headset,
+//! and then the system default. `MUSE_INPUT_DEVICE` still takes precedence
+//! over the settings. Devices are matched by name ignoring case, since cpal
+//! has no stable device id.
+//!
+//! When the device disappears mid-recording, e.g. a USB headset unplugged,
+//! the disconnect policy decides whether the recording moves on to the next
+//! device found this way, ends early with what was captured, or fails.
+
+use crate::flow::{AudioError, ErrorKind};
+use cpal::traits::{DeviceTrait, HostTrait};
+use cpal::Device;
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::time::Duration;
+
+/// How long a recording waits for an input device to come back after losing one
+pub const RECONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Devices in a row that may fail without delivering audio before a
+/// recording gives up, so one that fails as soon as it is opened does not
+/// keep the recording alive forever
+pub const MAX_RECONNECTS: u32 = 5;
+
+/// What a recording does when its input device fails
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum DisconnectPolicy {
+    /// Continue on the chosen device, a fallback or the system default,
+    /// whichever is connected first; end the recording if none is
+    #[default]
+    Reconnect,
+    /// End the recording and transcribe what was captured
+    Stop,
+    /// Fail the flow
+    Fail,
+}
+
+/// What happened to the input device of a running recording
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum DeviceEvent {
+    /// The device was disconnected, or a replacement could not be started
+    Lost { message: String },
+    /// Recording continues on `device`
+    Reconnected { device: String },
+    /// No device could be opened; the recording ended early
+    Unavailable,
+}
+
+/// A range of stream configurations an input device supports
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct InputConfig {
+    pub channels: u16,
+    pub min_sample_rate: u32,
+    pub max_sample_rate: u32,
+    /// e.g. "f32" or "i16"
+    pub sample_format: String,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct InputDevice {
+    pub name: String,
+    pub is_default: bool,
+    /// Empty if the device could not report its configurations
+    pub configs: Vec<InputConfig>,
+}
+
+/// Check the chosen device and its fallbacks before they are saved
+pub fn validate(device: &str, fallbacks: &[String]) -> Result<(), String> {
+    for (i, fallback) in fallbacks.iter().enumerate() {
+        let name = fallback.trim();
+        if name.is_empty() {
+            return Err("Fallback input devices need a name".to_string());
+        }
+        if name.eq_ignore_ascii_case(device.trim()) || fallbacks[..i].iter().any(|f| f.trim().eq_ignore_ascii_case(name)) {
+            return Err(format!("Input device '{}' is listed more than once", name));
+        }
+    }
+    Ok(())
+}
+
+/// Device names to look for, in order: the environment override, then the
+/// chosen device and its fallbacks
+fn wanted_names(preferred: &[String], env_override: Option<String>) -> Vec<String> {
+    env_override
+        .into_iter()
+        .chain(preferred.iter().cloned())
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .collect()
+}
+
+/// Index in `available` of the first wanted name that is connected
+fn pick(available: &[String], wanted: &[String]) -> Option<usize> {
+    wanted
+        .iter()
+        .find_map(|name| available.iter().position(|device| device.trim().eq_ignore_ascii_case(name)))
+}
+
+fn configs(device: &Device) -> Vec<InputConfig> {
+    match device.supported_input_configs() {
+        Ok(ranges) => ranges
+            .map(|range| InputConfig {
+                channels: range.channels(),
+                min_sample_rate: range.min_sample_rate().0,
+                max_sample_rate: range.max_sample_rate().0,
+                sample_format: range.sample_format().to_string(),
+            })
+            .collect(),
+        Err(e) => {
+            eprintln!("Could not read configurations of an input device: {}", e);
+            Vec::new()
+        }
+    }
+}
+
+/// Connected input devices with the configurations they support
+pub fn list() -> Result<Vec<InputDevice>, AudioError> {
+    let host = cpal::default_host();
+    let default_name = host.default_input_device().and_then(|device| device.name().ok());
+    let devices = host
+        .input_devices()
+        .map_err(AudioError::from)?
+        .filter_map(|device| {
+            let name = device.name().ok()?;
+            Some(InputDevice {
+                is_default: default_name.as_deref() == Some(name.as_str()),
+                configs: configs(&device),
+                name,
+            })
+        })
+        .collect();
+    Ok(devices)
+}
+
+/// The first connected device of `preferred`, or the system default
+pub fn find(preferred: &[String]) -> Result<Device, AudioError> {
+    let host = cpal::default_host();
+
+    let wanted = wanted_names(preferred, env::var("MUSE_INPUT_DEVICE").ok());
+    if !wanted.is_empty() {
+        let mut devices: Vec<Device> = host.input_devices().map_err(AudioError::from)?.collect();
+        let names: Vec<String> = devices.iter().map(|device| device.name().unwrap_or_default()).collect();
+        if let Some(index) = pick(&names, &wanted) {
+            println!("Using input device: {}", names[index]);
+            return Ok(devices.swap_remove(index));
+        }
+        println!("None of the input devices {:?} is connected, falling back to default", wanted);
+    }
+
+    let device = host
+        .default_input_device()
+        .ok_or_else(|| AudioError::new(ErrorKind::Device, "No input device found"))?;
+    if let Ok(name) = device.name() {
+        println!("Using default input device: {}", name);
+    }
+    Ok(device)
+}
+
+/// Linear resampler for a device that replaced the one a recording started
+/// on, so its samples can go to the same encoder; linear interpolation is
+/// plenty for speech
+pub struct RateConverter {
+    /// Input samples per output sample
+    step: f64,
+    /// Position of the next output sample, where 0 is the last sample of the
+    /// previous call
+    position: f64,
+    previous: Option<f32>,
+}
+
+impl RateConverter {
+    pub fn new(from: u32, to: u32) -> Self {
+        Self {
+            step: f64::from(from) / f64::from(to),
+            position: 0.0,
+            previous: None,
+        }
+    }
+
+    pub fn process(&mut self, samples: &[f32]) -> Vec<f32> {
+        if self.step == 1.0 {
+            return samples.to_vec();
+        }
+        let Some(&first) = samples.first() else {
+            return Vec::new();
+        };
+        let previous = self.previous.unwrap_or(first);
+        let at = |i: usize| if i == 0 { previous } else { samples[i - 1] };
+
+        let len = samples.len() as f64;
+        let mut output = Vec::with_capacity((len / self.step) as usize + 1);
+        while self.position < len {
+            let index = self.position as usize;
+            let fraction = (self.position - index as f64) as f32;
+            output.push(at(index) + (at(index + 1) - at(index)) * fraction);
+            self.position += self.step;
+        }
+        self.position -= len;
+        self.previous = samples.last().copied();
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn test_pick() {
+        let available = names(&["Built-in Microphone", "USB Headset", "Dock Mic"]);
+        assert_eq!(pick(&available, &names(&["usb headset", "Dock Mic"])), Some(1));
+        // A missing device falls through to the next one
+        assert_eq!(pick(&available, &names(&["Bluetooth", "dock mic"])), Some(2));
+        assert_eq!(pick(&available, &names(&["Bluetooth"])), None);
+        assert_eq!(pick(&available, &[]), None);
+    }
+
+    #[test]
+    fn test_wanted_names() {
+        let preferred = names(&["USB Headset", " ", "Dock Mic "]);
+        assert_eq!(wanted_names(&preferred, None), names(&["USB Headset", "Dock Mic"]));
+        assert_eq!(wanted_names(&preferred, Some("Studio".to_string()))[0], "Studio");
+    }
+
+    #[test]
+    fn test_rate_converter() {
+        let samples: Vec<f32> = (0..1000).map(|i| i as f32).collect();
+
+        let mut same = RateConverter::new(48000, 48000);
+        assert_eq!(same.process(&samples), samples);
+
+        let mut up = RateConverter::new(24000, 48000);
+        let output = up.process(&samples);
+        assert_eq!(output.len(), 2000);
+        // Halfway between two samples
+        assert_eq!(output[3], 0.5);
+
+        // Chunk boundaries do not change the result
+        let mut down = RateConverter::new(48000, 16000);
+        let whole = down.process(&samples);
+        let mut down = RateConverter::new(48000, 16000);
+        let chunked: Vec<f32> = samples.chunks(97).flat_map(|chunk| down.process(chunk)).collect();
+        assert_eq!(whole.len(), 334);
+        assert_eq!(chunked, whole);
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(validate("USB Headset", &names(&["Dock Mic"])).is_ok());
+        assert!(validate("", &[]).is_ok());
+        assert!(validate("", &names(&[" "])).is_err());
+        assert!(validate("USB Headset", &names(&["usb headset"])).is_err());
+        assert!(validate("", &names(&["Dock Mic", "DOCK MIC"])).is_err());
+    }
+}
@@ -1,23 +1,46 @@
 // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
 
+mod app_profile;
 mod flow;
 mod flow_manager;
 mod stream_processor;
+mod segmenter;
+mod chunking;
+mod context;
+mod dictation;
+mod input_device;
 mod audio_output;
+mod transcript;
+mod replacements;
+mod retry;
+mod rewrite;
+mod template;
+mod transcription;
+mod typing;
+mod usage;
+mod voice_edit;
+#[cfg(feature = "local-whisper")]
+mod local_whisper;
 pub mod ebml;
 pub mod opus;
 pub mod webm;
 
-use flow_manager::{FlowManager, FlowManagerState, StatusResponse, Options, OptionsPatch, TranscriptionHistoryEntry};
-use crate::flow::FlowState;
+use flow_manager::{FlowManager, FlowManagerState, StatusResponse, Options, OptionsPatch, TextVersion, TranscriptionHistoryEntry, TranslationOverrides};
+use crate::app_profile::FocusedApp;
+use crate::flow::{AudioError, ErrorKind, FlowState};
+use crate::input_device::InputDevice;
 use crate::audio_output::AudioOutputManager;
+use crate::replacements::ReplacementRule;
+use crate::transcription::BackendSettings;
+use crate::usage::{Period, UsageTotal};
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use tauri::{AppHandle, State, Emitter, Manager};
 use tauri::menu::{Menu, MenuItem, ContextMenu};
 use tokio::sync::RwLock;
 use tauri_plugin_clipboard_manager::ClipboardExt;
 use serde::Serialize;
-use enigo::{Enigo, Keyboard, Settings};
+use enigo::{Direction, Enigo, Key, Keyboard, Settings};
 
 #[cfg(desktop)]
 use tauri_plugin_global_shortcut::{Code, GlobalShortcutExt, Modifiers, Shortcut, ShortcutState};
@@ -46,7 +69,10 @@ async fn get_status(flow_manager: State<'_, FlowManagerState>) -> Result<StatusR
 async fn start_audio_stream(
     flow_manager: State<'_, FlowManagerState>,
     app_handle: AppHandle,
-) -> Result<String, String> {
+    translation: Option<TranslationOverrides>,
+) -> Result<String, AudioError> {
+    // Looked up before taking the lock, since it waits for external tools
+    let focused_app = app_profile::focused_app_async().await;
     let mut manager_guard = flow_manager.write().await;
 
     if let Some(manager) = manager_guard.as_mut() {
@@ -55,18 +81,18 @@ async fn start_audio_stream(
         match current_state {
             FlowState::Idle | FlowState::Completed | FlowState::Error | FlowState::Cancelled => {
                 let flow_manager_clone = Arc::clone(&flow_manager.inner());
-                manager.start_flow(app_handle, flow_manager_clone).await?;
+                manager.start_flow(app_handle, flow_manager_clone, translation.unwrap_or_default(), focused_app).await?;
                 Ok("Audio recording started successfully".to_string())
             }
-            _ => Err("Cannot start recording: flow is not idle".to_string()),
+            _ => Err(AudioError::new(ErrorKind::Internal, "Cannot start recording: flow is not idle")),
         }
     } else {
-        Err("Flow manager not initialized".to_string())
+        Err(AudioError::new(ErrorKind::Internal, "Flow manager not initialized"))
     }
 }
 
 #[tauri::command]
-async fn stop_audio_stream(flow_manager: State<'_, FlowManagerState>) -> Result<String, String> {
+async fn stop_audio_stream(flow_manager: State<'_, FlowManagerState>) -> Result<String, AudioError> {
     let mut manager_guard = flow_manager.write().await;
 
     if let Some(manager) = manager_guard.as_mut() {
@@ -77,22 +103,22 @@ async fn stop_audio_stream(flow_manager: State<'_, FlowManagerState>) -> Result<
                 manager.stop_flow().await?;
                 Ok("Recording stopped, starting transcription...".to_string())
             }
-            _ => Err("Cannot stop recording: not currently recording".to_string()),
+            _ => Err(AudioError::new(ErrorKind::Internal, "Cannot stop recording: not currently recording")),
         }
     } else {
-        Err("Flow manager not initialized".to_string())
+        Err(AudioError::new(ErrorKind::Internal, "Flow manager not initialized"))
     }
 }
 
 #[tauri::command]
-async fn cancel_transcription(flow_manager: State<'_, FlowManagerState>) -> Result<String, String> {
+async fn cancel_transcription(flow_manager: State<'_, FlowManagerState>) -> Result<String, AudioError> {
     let mut manager_guard = flow_manager.write().await;
 
     if let Some(manager) = manager_guard.as_mut() {
         manager.cancel_flow().await;
         Ok("Flow cancelled".to_string())
     } else {
-        Err("Flow manager not initialized".to_string())
+        Err(AudioError::new(ErrorKind::Internal, "Flow manager not initialized"))
     }
 }
 
@@ -102,7 +128,7 @@ async fn cancel_transcription(flow_manager: State<'_, FlowManagerState>) -> Resu
 async fn retry_transcription(
     flow_manager: State<'_, FlowManagerState>,
     app_handle: AppHandle,
-) -> Result<String, String> {
+) -> Result<String, AudioError> {
     let mut manager_guard = flow_manager.write().await;
 
     if let Some(manager) = manager_guard.as_mut() {
@@ -110,7 +136,7 @@ async fn retry_transcription(
         manager.retry_transcription(app_handle, flow_manager_clone).await?;
         Ok("Retrying transcription...".to_string())
     } else {
-        Err("Flow manager not initialized".to_string())
+        Err(AudioError::new(ErrorKind::Internal, "Flow manager not initialized"))
     }
 }
 #[tauri::command]
@@ -118,52 +144,62 @@ async fn set_transcription_model(
     flow_manager: State<'_, FlowManagerState>,
     app_handle: AppHandle,
     model: String,
-) -> Result<String, String> {
+) -> Result<String, AudioError> {
     let mut manager_guard = flow_manager.write().await;
 
     if let Some(manager) = manager_guard.as_mut() {
         let applied = manager.update_options(OptionsPatch {
             model: Some(model),
-            rewrite_enabled: None,
-            omit_final_punctuation: None,
-            selected_prompt_id: None,
-            custom_prompts: None,
-            api_key: None,
-            shortcuts: None,
-            output_mode: None,
+            ..Default::default()
         })?;
         let full = manager.options();
         let _ = app_handle.emit("options-changed", OptionsChangedEvent { full, patch: applied });
         Ok("Model updated".to_string())
     } else {
-        Err("Flow manager not initialized".to_string())
+        Err(AudioError::new(ErrorKind::Internal, "Flow manager not initialized"))
     }
 }
 
+#[tauri::command]
+async fn test_transcription_backend(
+    flow_manager: State<'_, FlowManagerState>,
+    backend: Option<String>,
+    settings: Option<BackendSettings>,
+    model: Option<String>,
+) -> Result<String, AudioError> {
+    // Build the backend under the lock, but don't hold it across the request
+    let (transcriber, model) = {
+        let manager_guard = flow_manager.read().await;
+        match manager_guard.as_ref() {
+            Some(manager) => manager
+                .connection_test_target(backend, settings, model)
+                .map_err(|message| AudioError::new(ErrorKind::Config, message))?,
+            None => return Err(AudioError::new(ErrorKind::Internal, "Flow manager not initialized")),
+        }
+    };
+
+    transcriber.test_connection(model.clone()).await?;
+    Ok(format!("Connected to '{}' using model '{}'", transcriber.name(), model))
+}
+
 #[tauri::command]
 async fn set_rewrite_enabled(
     flow_manager: State<'_, FlowManagerState>,
     app_handle: AppHandle,
     enabled: bool,
-) -> Result<String, String> {
+) -> Result<String, AudioError> {
     let mut manager_guard = flow_manager.write().await;
 
     if let Some(manager) = manager_guard.as_mut() {
         let applied = manager.update_options(OptionsPatch {
-            model: None,
             rewrite_enabled: Some(enabled),
-            omit_final_punctuation: None,
-            selected_prompt_id: None,
-            custom_prompts: None,
-            api_key: None,
-            shortcuts: None,
-            output_mode: None,
+            ..Default::default()
         })?;
         let full = manager.options();
         let _ = app_handle.emit("options-changed", OptionsChangedEvent { full, patch: applied });
         Ok("Rewrite setting updated".to_string())
     } else {
-        Err("Flow manager not initialized".to_string())
+        Err(AudioError::new(ErrorKind::Internal, "Flow manager not initialized"))
     }
 }
 
@@ -199,6 +235,78 @@ pub fn type_text_impl(text: &str) -> Result<String, String> {
     Ok("Text typed successfully".to_string())
 }
 
+/// Backspace over part of what was typed, then type the replacement
+pub fn type_edit_impl(edit: &typing::TypingEdit) -> Result<(), String> {
+    let mut enigo = Enigo::new(&Settings::default())
+        .map_err(|e| format!("Failed to initialize enigo: {}", e))?;
+    for _ in 0..edit.backspaces {
+        enigo.key(Key::Backspace, Direction::Click)
+            .map_err(|e| format!("Failed to press backspace: {}", e))?;
+    }
+    if !edit.text.is_empty() {
+        enigo.text(&edit.text)
+            .map_err(|e| format!("Failed to type text: {}", e))?;
+    }
+    Ok(())
+}
+
+/// How long to wait for the focused application to copy its selection
+const SELECTION_COPY_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Copy the focused application's selection with the copy keystroke and
+/// return it, leaving the clipboard as it was
+///
+/// Only text can be put back: an image or files on the clipboard are
+/// replaced by the selection. Blocks for up to `SELECTION_COPY_TIMEOUT`.
+fn copy_selection_impl(app_handle: &AppHandle) -> Result<String, String> {
+    let clipboard = app_handle.clipboard();
+    let previous = clipboard.read_text().ok();
+    if previous.is_some() {
+        // Cleared first, so an empty selection is not mistaken for old clipboard text
+        clipboard.write_text("")
+            .map_err(|e| format!("Failed to clear clipboard: {}", e))?;
+    }
+
+    let mut enigo = Enigo::new(&Settings::default())
+        .map_err(|e| format!("Failed to initialize enigo: {}", e))?;
+    // The shortcut's modifiers are still held and would change the keystroke
+    for modifier in [Key::Alt, Key::Shift, Key::Control, Key::Meta] {
+        let _ = enigo.key(modifier, Direction::Release);
+    }
+    #[cfg(target_os = "macos")]
+    let copy_modifier = Key::Meta;
+    #[cfg(not(target_os = "macos"))]
+    let copy_modifier = Key::Control;
+    enigo.key(copy_modifier, Direction::Press)
+        .and_then(|_| enigo.key(Key::Unicode('c'), Direction::Click))
+        .and_then(|_| enigo.key(copy_modifier, Direction::Release))
+        .map_err(|e| format!("Failed to send copy keystroke: {}", e))?;
+
+    let started = Instant::now();
+    let mut selection = String::new();
+    while selection.is_empty() && started.elapsed() < SELECTION_COPY_TIMEOUT {
+        std::thread::sleep(Duration::from_millis(20));
+        selection = clipboard.read_text().unwrap_or_default();
+    }
+
+    if let Some(previous) = previous
+        && let Err(e) = clipboard.write_text(previous)
+    {
+        eprintln!("Failed to restore clipboard: {}", e);
+    }
+    if selection.trim().is_empty() {
+        return Err("No text is selected".to_string());
+    }
+    Ok(selection)
+}
+
+/// `copy_selection_impl` on a blocking thread, so it does not stall the runtime
+pub async fn copy_selection(app_handle: AppHandle) -> Result<String, String> {
+    tokio::task::spawn_blocking(move || copy_selection_impl(&app_handle))
+        .await
+        .map_err(|e| format!("Failed to copy the selection: {}", e))?
+}
+
 #[tauri::command]
 async fn type_text(text: String) -> Result<String, String> {
     type_text_impl(&text)
@@ -216,24 +324,134 @@ async fn get_transcription_history(
     }
 }
 
+/// Application focused when the last recording started, so the settings can
+/// show which name to use in an app profile
+#[tauri::command]
+async fn get_last_focused_app(
+    flow_manager: State<'_, FlowManagerState>,
+) -> Result<Option<FocusedApp>, String> {
+    let manager_guard = flow_manager.read().await;
+    Ok(manager_guard.as_ref().and_then(|manager| manager.get_last_focused_app()))
+}
+
+/// Connected microphones with the configurations they support
+#[tauri::command]
+async fn list_input_devices() -> Result<Vec<InputDevice>, String> {
+    // Enumerating can block on some audio backends
+    tokio::task::spawn_blocking(input_device::list)
+        .await
+        .map_err(|e| format!("Failed to list input devices: {}", e))?
+        .map_err(|e| e.message)
+}
+
+#[tauri::command]
+async fn list_replacement_rules(
+    flow_manager: State<'_, FlowManagerState>,
+) -> Result<Vec<ReplacementRule>, String> {
+    let manager_guard = flow_manager.read().await;
+    Ok(manager_guard.as_ref().map(|manager| manager.replacement_rules()).unwrap_or_default())
+}
+
+#[tauri::command]
+async fn add_replacement_rule(
+    flow_manager: State<'_, FlowManagerState>,
+    rule: ReplacementRule,
+) -> Result<ReplacementRule, String> {
+    let mut manager_guard = flow_manager.write().await;
+    let manager = manager_guard.as_mut().ok_or("Flow manager not initialized")?;
+    manager.add_replacement_rule(rule)
+}
+
+#[tauri::command]
+async fn remove_replacement_rule(
+    flow_manager: State<'_, FlowManagerState>,
+    id: String,
+) -> Result<(), String> {
+    let mut manager_guard = flow_manager.write().await;
+    let manager = manager_guard.as_mut().ok_or("Flow manager not initialized")?;
+    manager.remove_replacement_rule(&id)
+}
+
+/// Preview replacements on sample text: only `rule` if given, otherwise all
+/// saved rules
+#[tauri::command]
+async fn test_replacement_rules(
+    flow_manager: State<'_, FlowManagerState>,
+    text: String,
+    rule: Option<ReplacementRule>,
+) -> Result<String, String> {
+    let manager_guard = flow_manager.read().await;
+    let manager = manager_guard.as_ref().ok_or("Flow manager not initialized")?;
+    manager.test_replacements(&text, rule)
+}
+
+/// Usage and estimated cost per day or month, oldest first. The frontend
+/// passes its UTC offset so days match the user's calendar.
+#[tauri::command]
+async fn get_usage_totals(
+    flow_manager: State<'_, FlowManagerState>,
+    period: Period,
+    utc_offset_minutes: Option<i32>,
+) -> Result<Vec<UsageTotal>, String> {
+    let manager_guard = flow_manager.read().await;
+    if let Some(manager) = manager_guard.as_ref() {
+        Ok(manager.usage_totals(period, utc_offset_minutes.unwrap_or(0)))
+    } else {
+        Ok(vec![])
+    }
+}
+
+/// Copy a history entry's final text, or its raw or rewritten version
 #[tauri::command]
 async fn copy_history_entry(
     flow_manager: State<'_, FlowManagerState>,
     app_handle: AppHandle,
     index: usize,
+    version: Option<TextVersion>,
 ) -> Result<String, String> {
     let manager_guard = flow_manager.read().await;
     if let Some(manager) = manager_guard.as_ref() {
-        if let Some(text) = manager.get_history_entry(index) {
-            copy_to_clipboard_impl(&text, &app_handle)
-        } else {
-            Err(format!("No history entry at index {}", index))
-        }
+        let text = manager.get_history_text(index, version.unwrap_or_default())?;
+        copy_to_clipboard_impl(&text, &app_handle)
     } else {
         Err("Flow manager not initialized".to_string())
     }
 }
 
+/// Play the saved recording of a history entry from `start` seconds, e.g. the
+/// start of a word or segment
+#[tauri::command]
+async fn play_history_audio(
+    flow_manager: State<'_, FlowManagerState>,
+    audio_manager: State<'_, Arc<Mutex<AudioOutputManager>>>,
+    index: usize,
+    start: f64,
+) -> Result<(), String> {
+    let audio_path = {
+        let manager_guard = flow_manager.read().await;
+        let manager = manager_guard.as_ref().ok_or("Flow manager not initialized")?;
+        manager
+            .get_history_audio_path(index)
+            .ok_or_else(|| format!("No saved recording for history entry {}", index))?
+    };
+
+    let samples = tokio::task::spawn_blocking(move || -> Result<Vec<f32>, String> {
+        let data = std::fs::read(&audio_path)
+            .map_err(|e| format!("Failed to read recording {}: {}", audio_path, e))?;
+        let pcm = webm::decode_opus_track(&data).map_err(|e| format!("Failed to decode recording: {}", e))?;
+        let offset = ((start.max(0.0) * 48000.0) as usize).min(pcm.len());
+        Ok(pcm[offset..].to_vec())
+    })
+    .await
+    .map_err(|e| format!("Playback task failed: {}", e))??;
+
+    audio_manager
+        .lock()
+        .map_err(|_| "Audio output unavailable".to_string())?
+        .play_samples(samples);
+    Ok(())
+}
+
 #[derive(Serialize, Clone)]
 struct OptionsChangedEvent {
     full: Options,
@@ -246,17 +464,7 @@ async fn get_options(flow_manager: State<'_, FlowManagerState>) -> Result<Option
     if let Some(manager) = manager_guard.as_ref() {
         Ok(manager.options())
     } else {
-        Ok(Options {
-            model: "whisper-1".to_string(),
-            rewrite_enabled: false,
-            omit_final_punctuation: false,
-            selected_prompt_id: "default".to_string(),
-            custom_prompts: vec![],
-            api_key: String::new(),
-            api_key_from_env: std::env::var("OPENAI_API_KEY").is_ok(),
-            shortcuts: "Alt+Slash".to_string(),
-            output_mode: "clipboard".to_string(),
-        })
+        Ok(FlowManager::default_options())
     }
 }
 
@@ -265,7 +473,11 @@ async fn update_options(
     flow_manager: State<'_, FlowManagerState>,
     app_handle: AppHandle,
     patch: OptionsPatch,
-) -> Result<Options, String> {
+) -> Result<Options, AudioError> {
+    #[cfg(desktop)]
+    if let Some(shortcut) = &patch.command_shortcut {
+        validate_command_shortcut(shortcut).map_err(|message| AudioError::new(ErrorKind::Config, message))?;
+    }
     let mut manager_guard = flow_manager.write().await;
     if let Some(manager) = manager_guard.as_mut() {
         let applied = manager.update_options(patch)?;
@@ -273,7 +485,7 @@ async fn update_options(
         let _ = app_handle.emit("options-changed", OptionsChangedEvent { full: full.clone(), patch: applied });
         Ok(full)
     } else {
-        Err("Flow manager not initialized".to_string())
+        Err(AudioError::new(ErrorKind::Internal, "Flow manager not initialized"))
     }
 }
 
@@ -583,6 +795,33 @@ fn get_shortcut_from_env() -> String {
     std::env::var(env_key).unwrap_or_else(|_| get_default_shortcut().to_string())
 }
 
+/// Shortcut for voice commands on selected text; the environment overrides
+/// the saved setting
+#[cfg(desktop)]
+fn get_command_shortcut() -> String {
+    #[cfg(target_os = "macos")]
+    let env_key = "MUSE_COMMAND_SHORTCUT_MACOS";
+
+    #[cfg(target_os = "windows")]
+    let env_key = "MUSE_COMMAND_SHORTCUT_WINDOWS";
+
+    #[cfg(target_os = "linux")]
+    let env_key = "MUSE_COMMAND_SHORTCUT_LINUX";
+
+    std::env::var(env_key).unwrap_or_else(|_| FlowManager::load_command_shortcut())
+}
+
+/// Check that a voice command shortcut parses and differs from the recording shortcut
+#[cfg(desktop)]
+fn validate_command_shortcut(shortcut_str: &str) -> Result<Shortcut, String> {
+    let shortcut = parse_shortcut(shortcut_str)
+        .map_err(|e| format!("Invalid voice command shortcut '{}': {}", shortcut_str, e))?;
+    if parse_shortcut(&get_shortcut_from_env()).ok() == Some(shortcut) {
+        return Err("Voice command shortcut is the same as the recording shortcut".to_string());
+    }
+    Ok(shortcut)
+}
+
 #[cfg(desktop)]
 fn parse_shortcut(shortcut_str: &str) -> Result<Shortcut, String> {
     let parts: Vec<&str> = shortcut_str.split('+').collect();
@@ -671,7 +910,16 @@ pub fn run() {
             type_text,
             get_transcription_history,
             copy_history_entry,
+            play_history_audio,
+            get_usage_totals,
+            get_last_focused_app,
+            list_input_devices,
+            list_replacement_rules,
+            add_replacement_rule,
+            remove_replacement_rule,
+            test_replacement_rules,
             set_transcription_model,
+            test_transcription_backend,
             set_rewrite_enabled,
             get_options,
             update_options,
@@ -688,16 +936,25 @@ pub fn run() {
             {
                 let shortcut_string = get_shortcut_from_env();
                 println!("Setting up global shortcut: {}", shortcut_string);
+                let command_shortcut_string = get_command_shortcut();
 
                 match parse_shortcut(&shortcut_string) {
                     Ok(shortcut) => {
+                        let command_shortcut = match validate_command_shortcut(&command_shortcut_string) {
+                            Ok(command_shortcut) => Some(command_shortcut),
+                            Err(e) => {
+                                eprintln!("❌ {}", e);
+                                None
+                            }
+                        };
                         let flow_manager_for_handler = flow_manager.clone();
                         let app_handle_for_handler = app.handle().clone();
 
                         let handler_result = app.handle().plugin(
                             tauri_plugin_global_shortcut::Builder::new()
-                                .with_handler(move |_app, _triggered_shortcut, event| {
+                                .with_handler(move |_app, triggered_shortcut, event| {
                                     if event.state() == ShortcutState::Pressed {
+                                        let is_command = command_shortcut.as_ref() == Some(triggered_shortcut);
                                         let flow_manager_clone = flow_manager_for_handler.clone();
                                         let app_handle_clone = app_handle_for_handler.clone();
 
@@ -710,13 +967,34 @@ pub fn run() {
 
                                                 match current_state {
                                                     FlowState::Idle | FlowState::Completed | FlowState::Error | FlowState::Cancelled => {
+                                                        // Both looked up before taking the lock, since they wait for other programs
+                                                        let focused_app = app_profile::focused_app_async().await;
+                                                        let selection = if is_command {
+                                                            match copy_selection(app_handle_clone.clone()).await {
+                                                                Ok(selection) => Some(selection),
+                                                                Err(message) => {
+                                                                    eprintln!("❌ Failed to copy the selection: {}", message);
+                                                                    // Shown in the main window, since the shortcut has nowhere to report it
+                                                                    let _ = app_handle_clone.emit("flow-error", AudioError::new(ErrorKind::Internal, message));
+                                                                    return;
+                                                                }
+                                                            }
+                                                        } else {
+                                                            None
+                                                        };
                                                         let mut manager_guard = flow_manager_clone.write().await;
                                                         if let Some(manager) = manager_guard.as_mut() {
                                                             let flow_manager_clone_for_start = Arc::clone(&flow_manager_clone);
-                                                            match manager.start_flow(app_handle_clone.clone(), flow_manager_clone_for_start).await {
+                                                            let started = match selection {
+                                                                Some(selection) => manager.start_command_flow(app_handle_clone.clone(), flow_manager_clone_for_start, selection, focused_app).await,
+                                                                None => manager.start_flow(app_handle_clone.clone(), flow_manager_clone_for_start, TranslationOverrides::default(), focused_app).await,
+                                                            };
+                                                            match started {
                                                                 Ok(_) => println!("✅ Recording started via global shortcut"),
                                                                 Err(e) => {
                                                                     eprintln!("❌ Failed to start recording: {}", e);
+                                                                    // Shown in the main window, since the shortcut has nowhere to report it
+                                                                    let _ = app_handle_clone.emit("flow-error", &e);
                                                                 }
                                                             }
                                                         }
@@ -754,6 +1032,12 @@ pub fn run() {
                                     Ok(_) => println!("✅ Global shortcut registered successfully: {}", shortcut_string),
                                     Err(e) => eprintln!("❌ Failed to register global shortcut: {}", e),
                                 }
+                                if let Some(command_shortcut) = command_shortcut {
+                                    match app.global_shortcut().register(command_shortcut) {
+                                        Ok(_) => println!("✅ Voice command shortcut registered successfully: {}", command_shortcut_string),
+                                        Err(e) => eprintln!("❌ Failed to register voice command shortcut: {}", e),
+                                    }
+                                }
                             }
                             Err(e) => eprintln!("❌ Failed to initialize global shortcut plugin: {}", e),
                         }
@@ -0,0 +1,270 @@
+//! Offline transcription with whisper.cpp
+//!
+//! Models are ggml checkpoints (e.g. `ggml-base.en.bin` from the whisper.cpp
+//! releases) placed in the `models` folder of the settings directory. The model
+//! name in settings is the file name, with or without the `.bin` extension.
+//! Audio never leaves the machine: the recorded WebM is decoded back to PCM,
+//! resampled to 16 kHz and run through whisper on the CPU.
+
+use crate::flow::{AudioError, ErrorKind};
+use crate::transcript::{Transcript, TranscriptSegment, TranscriptWord};
+use crate::transcription::{TranscriptionBackend, TranscriptionFuture, TranscriptionRequest, BACKEND_LOCAL};
+use crate::webm;
+use rubato::{FftFixedIn, Resampler};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+use whisper_rs::{FullParams, SamplingStrategy, WhisperContext, WhisperContextParameters};
+
+/// Sample rate of recorded audio (Opus native rate)
+const SOURCE_SAMPLE_RATE: usize = 48000;
+
+/// Sample rate whisper expects
+const WHISPER_SAMPLE_RATE: usize = 16000;
+
+/// Most recently loaded model, kept so consecutive flows skip the load
+static LOADED_MODEL: OnceLock<Mutex<Option<(PathBuf, Arc<WhisperContext>)>>> = OnceLock::new();
+
+pub struct LocalWhisperBackend {
+    models_dir: PathBuf,
+}
+
+impl LocalWhisperBackend {
+    pub fn new(models_dir: PathBuf) -> Self {
+        Self { models_dir }
+    }
+
+    fn model_path(&self, model: &str) -> PathBuf {
+        if model.ends_with(".bin") {
+            self.models_dir.join(model)
+        } else {
+            self.models_dir.join(format!("{}.bin", model))
+        }
+    }
+
+    async fn transcribe_impl(&self, request: TranscriptionRequest) -> Result<Transcript, AudioError> {
+        let model_path = self.model_path(&request.model);
+
+        // Decoding and inference are CPU-bound; keep them off the async runtime
+        tokio::task::spawn_blocking(move || {
+            let samples = decode_to_whisper_pcm(&request.audio_data)?;
+            if samples.is_empty() {
+                return Ok(Transcript::default());
+            }
+            let context = load_model(&model_path)?;
+            run_whisper(&context, &samples, &request)
+        })
+        .await
+        .map_err(|e| AudioError::new(ErrorKind::Internal, format!("Local transcription task failed: {}", e)))?
+    }
+}
+
+impl TranscriptionBackend for LocalWhisperBackend {
+    fn name(&self) -> &str {
+        BACKEND_LOCAL
+    }
+
+    fn validate_model(&self, model: &str) -> Result<(), String> {
+        let path = self.model_path(model);
+        if path.is_file() {
+            Ok(())
+        } else {
+            Err(format!(
+                "Model file not found: {}. Download a ggml model (e.g. ggml-base.en.bin) into {}",
+                path.display(),
+                self.models_dir.display()
+            ))
+        }
+    }
+
+    fn transcribe(&self, request: TranscriptionRequest) -> TranscriptionFuture<'_> {
+        Box::pin(self.transcribe_impl(request))
+    }
+
+    fn max_concurrent_requests(&self) -> usize {
+        // Whisper already uses every core for a single chunk
+        1
+    }
+}
+
+fn load_model(path: &Path) -> Result<Arc<WhisperContext>, AudioError> {
+    let mut loaded = LOADED_MODEL.get_or_init(|| Mutex::new(None)).lock().unwrap();
+    if let Some((loaded_path, context)) = loaded.as_ref() {
+        if loaded_path == path {
+            return Ok(Arc::clone(context));
+        }
+    }
+
+    let path_str = path
+        .to_str()
+        .ok_or_else(|| AudioError::new(ErrorKind::Config, format!("Model path is not valid UTF-8: {:?}", path)))?;
+
+    println!("Loading whisper model from {:?}", path);
+    let context = WhisperContext::new_with_params(path_str, WhisperContextParameters::default())
+        .map_err(|e| {
+            AudioError::new(ErrorKind::Config, format!("Failed to load whisper model {:?}: {}", path, e))
+        })?;
+    let context = Arc::new(context);
+
+    *loaded = Some((path.to_path_buf(), Arc::clone(&context)));
+    Ok(context)
+}
+
+fn run_whisper(
+    context: &WhisperContext,
+    samples: &[f32],
+    request: &TranscriptionRequest,
+) -> Result<Transcript, AudioError> {
+    let whisper_error =
+        |e: whisper_rs::WhisperError| AudioError::new(ErrorKind::Internal, format!("Whisper error: {}", e));
+
+    let mut state = context.create_state().map_err(whisper_error)?;
+
+    let threads = std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(4)
+        .min(8);
+
+    let mut params = FullParams::new(SamplingStrategy::Greedy { best_of: 1 });
+    params.set_n_threads(threads as i32);
+    params.set_language(Some(request.language.as_deref().unwrap_or("auto")));
+    params.set_translate(request.translate);
+    if let Some(prompt) = request.prompt.as_deref() {
+        params.set_initial_prompt(prompt);
+    }
+    params.set_print_progress(false);
+    params.set_print_realtime(false);
+    params.set_print_special(false);
+    params.set_print_timestamps(false);
+    params.set_token_timestamps(request.timestamps);
+
+    println!("Running whisper on {:.2}s of audio with {} threads", samples.len() as f32 / WHISPER_SAMPLE_RATE as f32, threads);
+    state.full(params, samples).map_err(whisper_error)?;
+
+    let segment_count = state.full_n_segments().map_err(whisper_error)?;
+    let mut transcript = Transcript::default();
+    for i in 0..segment_count {
+        let text = state.full_get_segment_text(i).map_err(whisper_error)?;
+        transcript.text.push_str(&text);
+        if !request.timestamps {
+            continue;
+        }
+
+        transcript.segments.push(TranscriptSegment {
+            start: centis_to_secs(state.full_get_segment_t0(i).map_err(whisper_error)?),
+            end: centis_to_secs(state.full_get_segment_t1(i).map_err(whisper_error)?),
+            text: text.trim().to_string(),
+        });
+        for token in 0..state.full_n_tokens(i).map_err(whisper_error)? {
+            let token_text = state.full_get_token_text(i, token).map_err(whisper_error)?;
+            let data = state.full_get_token_data(i, token).map_err(whisper_error)?;
+            push_token(&mut transcript.words, &token_text, centis_to_secs(data.t0), centis_to_secs(data.t1));
+        }
+    }
+
+    transcript.text = transcript.text.trim().to_string();
+    Ok(transcript)
+}
+
+/// Whisper reports times in units of 10 ms
+fn centis_to_secs(centis: i64) -> f64 {
+    centis as f64 / 100.0
+}
+
+/// Add a token to the word list. Tokens are word pieces: one starting with a
+/// space begins a new word, anything else continues the previous one.
+/// Special tokens such as `[_BEG_]` or `<|endoftext|>` are skipped.
+fn push_token(words: &mut Vec<TranscriptWord>, text: &str, start: f64, end: f64) {
+    if text.starts_with("[_") || text.starts_with("<|") || text.trim().is_empty() {
+        return;
+    }
+    match words.last_mut() {
+        Some(word) if !text.starts_with(' ') => {
+            word.word.push_str(text);
+            word.end = end;
+        }
+        _ => words.push(TranscriptWord {
+            start,
+            end,
+            word: text.trim().to_string(),
+        }),
+    }
+}
+
+/// Decode recorded WebM/Opus audio to 16 kHz mono PCM
+fn decode_to_whisper_pcm(audio_data: &[u8]) -> Result<Vec<f32>, AudioError> {
+    let pcm = webm::decode_opus_track(audio_data)
+        .map_err(|e| AudioError::new(ErrorKind::Audio, format!("Failed to decode recording: {}", e)))?;
+
+    resample_to_whisper_rate(&pcm)
+}
+
+fn resample_to_whisper_rate(input: &[f32]) -> Result<Vec<f32>, AudioError> {
+    let resample_error =
+        |e: &dyn std::fmt::Display| AudioError::new(ErrorKind::Audio, format!("Failed to resample audio: {}", e));
+
+    let mut resampler = FftFixedIn::<f32>::new(SOURCE_SAMPLE_RATE, WHISPER_SAMPLE_RATE, 1024, 2, 1)
+        .map_err(|e| resample_error(&e))?;
+    let delay = resampler.output_delay();
+
+    let mut output = Vec::with_capacity(input.len() / 3 + 1024);
+    let mut position = 0;
+    while input.len() - position >= resampler.input_frames_next() {
+        let frames = resampler.input_frames_next();
+        let chunk = resampler
+            .process(&[&input[position..position + frames]], None)
+            .map_err(|e| resample_error(&e))?;
+        output.extend_from_slice(&chunk[0]);
+        position += frames;
+    }
+    if position < input.len() {
+        let chunk = resampler
+            .process_partial(Some(&[&input[position..]]), None)
+            .map_err(|e| resample_error(&e))?;
+        output.extend_from_slice(&chunk[0]);
+    }
+
+    // Push out the samples still held back by the resampler delay
+    let chunk = resampler
+        .process_partial::<&[f32]>(None, None)
+        .map_err(|e| resample_error(&e))?;
+    output.extend_from_slice(&chunk[0]);
+
+    let expected_len = input.len() * WHISPER_SAMPLE_RATE / SOURCE_SAMPLE_RATE;
+    output.drain(..delay.min(output.len()));
+    output.truncate(expected_len);
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resample_length() {
+        let input = vec![0.1f32; SOURCE_SAMPLE_RATE * 2 + 123];
+        let output = resample_to_whisper_rate(&input).unwrap();
+        assert_eq!(output.len(), input.len() * WHISPER_SAMPLE_RATE / SOURCE_SAMPLE_RATE);
+    }
+
+    #[test]
+    fn test_model_path() {
+        let backend = LocalWhisperBackend::new(PathBuf::from("/models"));
+        assert_eq!(backend.model_path("ggml-base.en"), PathBuf::from("/models/ggml-base.en.bin"));
+        assert_eq!(backend.model_path("ggml-base.en.bin"), PathBuf::from("/models/ggml-base.en.bin"));
+        assert!(backend.validate_model("definitely-missing").is_err());
+    }
+
+    #[test]
+    fn test_push_token() {
+        let mut words = Vec::new();
+        push_token(&mut words, "[_BEG_]", 0.0, 0.0);
+        push_token(&mut words, " Hel", 0.1, 0.3);
+        push_token(&mut words, "lo", 0.3, 0.5);
+        push_token(&mut words, " world", 0.6, 1.0);
+        push_token(&mut words, "<|endoftext|>", 1.0, 1.0);
+
+        let text: Vec<&str> = words.iter().map(|w| w.word.as_str()).collect();
+        assert_eq!(text, vec!["Hello", "world"]);
+        assert_eq!((words[0].start, words[0].end), (0.1, 0.5));
+    }
+}
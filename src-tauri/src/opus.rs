@@ -411,6 +411,78 @@ impl Drop for BufferedOpusEncoder {
 // BufferedOpusEncoder is safe to send between threads
 unsafe impl Send for BufferedOpusEncoder {}
 
+/// Maximum samples in a single opus packet (120ms at 48kHz)
+const MAX_DECODED_FRAME_SIZE: usize = 5760;
+
+/// A mono 48kHz opus decoder, used to read recorded audio back as PCM.
+pub struct OpusFrameDecoder {
+    /// The raw opus decoder pointer
+    decoder: *mut OpusDecoder,
+    /// Temporary buffer for decoding
+    pcm_buffer: Vec<f32>,
+}
+
+impl OpusFrameDecoder {
+    /// Create a new opus decoder for mono audio at 48kHz
+    pub fn new() -> Result<Self, OpusError> {
+        let mut error: i32 = 0;
+
+        let decoder = unsafe { opus_decoder_create(SAMPLE_RATE, 1, &mut error as *mut i32) };
+
+        if error != 0 {
+            return Err(OpusError::from_code(error));
+        }
+
+        if decoder.is_null() {
+            return Err(OpusError::AllocFail);
+        }
+
+        Ok(Self {
+            decoder,
+            pcm_buffer: vec![0.0f32; MAX_DECODED_FRAME_SIZE],
+        })
+    }
+
+    /// Decode a single opus packet into f32 samples (-1.0 to 1.0 range)
+    ///
+    /// # Arguments
+    /// * `packet` - One complete opus packet
+    ///
+    /// # Returns
+    /// The decoded mono samples, or an error
+    pub fn decode_f32(&mut self, packet: &[u8]) -> Result<Vec<f32>, OpusError> {
+        let decoded_len = unsafe {
+            opus_decode_float(
+                self.decoder,
+                packet.as_ptr(),
+                packet.len() as i32,
+                self.pcm_buffer.as_mut_ptr(),
+                MAX_DECODED_FRAME_SIZE as i32,
+                0, // no forward error correction
+            )
+        };
+
+        if decoded_len < 0 {
+            return Err(OpusError::from_code(decoded_len));
+        }
+
+        Ok(self.pcm_buffer[..decoded_len as usize].to_vec())
+    }
+}
+
+impl Drop for OpusFrameDecoder {
+    fn drop(&mut self) {
+        if !self.decoder.is_null() {
+            unsafe {
+                opus_decoder_destroy(self.decoder);
+            }
+        }
+    }
+}
+
+// OpusFrameDecoder is safe to send between threads
+unsafe impl Send for OpusFrameDecoder {}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -487,6 +559,28 @@ mod tests {
         assert_eq!(encoder.buffered_samples(), 0);
     }
 
+    #[test]
+    fn test_decode_roundtrip() {
+        let mut encoder = BufferedOpusEncoder::new(64000).unwrap();
+        let samples: Vec<f32> = (0..FRAME_SIZE)
+            .map(|i| (i as f32 * 440.0 * 2.0 * std::f32::consts::PI / SAMPLE_RATE as f32).sin() * 0.5)
+            .collect();
+        encoder.add_samples_f32(&samples).unwrap();
+        let frames = encoder.take_frames();
+        assert_eq!(frames.len(), 1);
+
+        let mut decoder = OpusFrameDecoder::new().unwrap();
+        let decoded = decoder.decode_f32(&frames[0]).unwrap();
+        assert_eq!(decoded.len(), FRAME_SIZE);
+    }
+
+    #[test]
+    fn test_decode_invalid_packet() {
+        let mut decoder = OpusFrameDecoder::new().unwrap();
+        // A code 3 packet with no frame count byte is malformed
+        assert!(decoder.decode_f32(&[0x03]).is_err());
+    }
+
     #[test]
     fn test_take_frames_clears() {
         let mut encoder = BufferedOpusEncoder::new(64000).unwrap();
@@ -0,0 +1,141 @@
+//! User replacement dictionary
+//!
+//! Find/replace rules fix words the transcription keeps getting wrong, such
+//! as "mews voice" → "MuseVoice", without a rewrite round-trip. Rules run in
+//! order on the final text, so a later rule sees the output of earlier ones.
+
+use regex::{NoExpand, Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+
+/// How a rule's `find` text is matched
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchKind {
+    /// Anywhere, even inside words
+    #[default]
+    Literal,
+    /// Only where it is not part of a longer word
+    WholeWord,
+    /// A regular expression; the replacement may use `$1` or `${name}`
+    Regex,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ReplacementRule {
+    /// Assigned when the rule is added
+    #[serde(default)]
+    pub id: String,
+    pub find: String,
+    pub replace: String,
+    #[serde(default)]
+    pub kind: MatchKind,
+    #[serde(default)]
+    pub case_sensitive: bool,
+}
+
+impl ReplacementRule {
+    fn regex(&self) -> Result<Regex, String> {
+        let pattern = match self.kind {
+            MatchKind::Literal => regex::escape(&self.find),
+            MatchKind::WholeWord => {
+                // `\b` only applies next to word characters, so "C++" still
+                // matches before a space
+                let is_word = |c: Option<char>| c.is_some_and(|c| c.is_alphanumeric() || c == '_');
+                let start = if is_word(self.find.chars().next()) { r"\b" } else { "" };
+                let end = if is_word(self.find.chars().next_back()) { r"\b" } else { "" };
+                format!("{}{}{}", start, regex::escape(&self.find), end)
+            }
+            MatchKind::Regex => self.find.clone(),
+        };
+        RegexBuilder::new(&pattern)
+            .case_insensitive(!self.case_sensitive)
+            .build()
+            .map_err(|e| format!("Invalid pattern '{}': {}", self.find, e))
+    }
+
+    fn apply(&self, regex: &Regex, text: &str) -> String {
+        match self.kind {
+            MatchKind::Regex => regex.replace_all(text, self.replace.as_str()).into_owned(),
+            _ => regex.replace_all(text, NoExpand(&self.replace)).into_owned(),
+        }
+    }
+}
+
+/// Check a rule before it is saved
+pub fn validate(rule: &ReplacementRule) -> Result<(), String> {
+    if rule.find.is_empty() {
+        return Err("A replacement rule needs text to find".to_string());
+    }
+    let regex = rule.regex()?;
+    if regex.is_match("") {
+        return Err(format!("Pattern '{}' matches empty text", rule.find));
+    }
+    Ok(())
+}
+
+/// Apply the rules in order; rules that no longer compile are skipped
+pub fn apply(rules: &[ReplacementRule], text: &str) -> String {
+    let mut text = text.to_string();
+    for rule in rules {
+        match rule.regex() {
+            Ok(regex) => text = rule.apply(&regex, &text),
+            Err(e) => eprintln!("Skipping replacement rule: {}", e),
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(find: &str, replace: &str, kind: MatchKind, case_sensitive: bool) -> ReplacementRule {
+        ReplacementRule {
+            id: String::new(),
+            find: find.to_string(),
+            replace: replace.to_string(),
+            kind,
+            case_sensitive,
+        }
+    }
+
+    #[test]
+    fn test_literal() {
+        let rules = [rule("mews voice", "MuseVoice", MatchKind::Literal, false)];
+        assert_eq!(apply(&rules, "I use Mews Voice and mews voice daily"), "I use MuseVoice and MuseVoice daily");
+
+        // Replacements are inserted as-is
+        let rules = [rule("price", "$1", MatchKind::Literal, true)];
+        assert_eq!(apply(&rules, "price, Price"), "$1, Price");
+    }
+
+    #[test]
+    fn test_whole_word() {
+        let rules = [rule("cat", "dog", MatchKind::WholeWord, false)];
+        assert_eq!(apply(&rules, "Cat, concatenate, cat."), "dog, concatenate, dog.");
+
+        let rules = [rule("c++", "C++", MatchKind::WholeWord, false)];
+        assert_eq!(apply(&rules, "I write c++ daily"), "I write C++ daily");
+    }
+
+    #[test]
+    fn test_regex() {
+        let rules = [rule(r"(\d+) percent", "$1%", MatchKind::Regex, false)];
+        assert_eq!(apply(&rules, "Up 20 Percent"), "Up 20%");
+
+        // Rules apply in order
+        let rules = [
+            rule("colour", "color", MatchKind::Literal, false),
+            rule("color", "hue", MatchKind::WholeWord, true),
+        ];
+        assert_eq!(apply(&rules, "colour"), "hue");
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(validate(&rule("x", "y", MatchKind::Literal, false)).is_ok());
+        assert!(validate(&rule("", "y", MatchKind::Literal, false)).is_err());
+        assert!(validate(&rule("(", "y", MatchKind::Regex, false)).unwrap_err().contains("Invalid pattern"));
+        assert!(validate(&rule("a*", "y", MatchKind::Regex, false)).unwrap_err().contains("empty"));
+    }
+}
@@ -0,0 +1,174 @@
+//! Automatic retry with exponential backoff for transient request failures
+//!
+//! Only errors whose kind is transient (rate limits, timeouts, network and
+//! server errors) are retried; a bad key or a rejected request fails at once.
+
+use crate::flow::AudioError;
+use std::future::Future;
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+
+/// Delays never grow beyond this, however many retries are configured
+const MAX_DELAY: Duration = Duration::from_secs(30);
+
+/// Upper bound for the retry count setting
+pub const MAX_RETRIES: u32 = 10;
+
+/// Check retry settings before they are saved
+pub fn validate_settings(max_retries: u32, initial_delay_ms: u64) -> Result<(), String> {
+    if max_retries > MAX_RETRIES {
+        return Err(format!("Retry count must be at most {}, got {}", MAX_RETRIES, max_retries));
+    }
+    if initial_delay_ms > MAX_DELAY.as_millis() as u64 {
+        return Err(format!(
+            "Retry delay must be at most {} ms, got {} ms",
+            MAX_DELAY.as_millis(),
+            initial_delay_ms
+        ));
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt; 0 disables retrying
+    pub max_retries: u32,
+    /// Delay before the first retry, doubled for each further one
+    pub initial_delay: Duration,
+}
+
+impl RetryPolicy {
+    /// Delay before retry number `retry` (starting at 1)
+    pub fn delay(&self, retry: u32) -> Duration {
+        let factor = 2u32.saturating_pow(retry.saturating_sub(1));
+        self.initial_delay.saturating_mul(factor).min(MAX_DELAY)
+    }
+
+    /// Run `operation` until it succeeds, fails permanently or runs out of retries
+    ///
+    /// `label` names the operation in log output. Waiting between attempts
+    /// stops early with a cancelled error if `cancellation_token` fires.
+    pub async fn run<T, F, Fut>(
+        &self,
+        label: &str,
+        cancellation_token: &CancellationToken,
+        mut operation: F,
+    ) -> Result<T, AudioError>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, AudioError>>,
+    {
+        let mut retry = 0;
+        loop {
+            match operation().await {
+                Ok(value) => return Ok(value),
+                Err(e) if e.is_transient() && retry < self.max_retries => {
+                    retry += 1;
+                    let delay = self.delay(retry);
+                    eprintln!(
+                        "{} failed ({}), retry {}/{} in {:?}",
+                        label, e.message, retry, self.max_retries, delay
+                    );
+                    tokio::select! {
+                        _ = tokio::time::sleep(delay) => {}
+                        _ = cancellation_token.cancelled() => {
+                            return Err(AudioError::cancelled(format!("{} cancelled", label)));
+                        }
+                    }
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::flow::ErrorKind;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    fn policy(max_retries: u32) -> RetryPolicy {
+        RetryPolicy {
+            max_retries,
+            initial_delay: Duration::from_millis(1),
+        }
+    }
+
+    #[test]
+    fn test_validate_settings() {
+        assert!(validate_settings(3, 1000).is_ok());
+        assert!(validate_settings(0, 0).is_ok());
+        assert!(validate_settings(MAX_RETRIES + 1, 1000).is_err());
+        assert!(validate_settings(3, 60_000).is_err());
+    }
+
+    #[test]
+    fn test_delay_doubles_up_to_cap() {
+        let policy = RetryPolicy {
+            max_retries: 10,
+            initial_delay: Duration::from_millis(500),
+        };
+        assert_eq!(policy.delay(1), Duration::from_millis(500));
+        assert_eq!(policy.delay(2), Duration::from_millis(1000));
+        assert_eq!(policy.delay(3), Duration::from_millis(2000));
+        assert_eq!(policy.delay(10), MAX_DELAY);
+        assert_eq!(policy.delay(100), MAX_DELAY);
+    }
+
+    #[tokio::test]
+    async fn test_retries_transient_errors() {
+        let attempts = AtomicU32::new(0);
+        let result = policy(3)
+            .run("test", &CancellationToken::new(), || async {
+                if attempts.fetch_add(1, Ordering::SeqCst) < 2 {
+                    Err(AudioError::new(ErrorKind::RateLimited, "slow down"))
+                } else {
+                    Ok("done")
+                }
+            })
+            .await;
+        assert_eq!(result.unwrap(), "done");
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_gives_up_after_max_retries() {
+        let attempts = AtomicU32::new(0);
+        let result: Result<(), _> = policy(2)
+            .run("test", &CancellationToken::new(), || async {
+                attempts.fetch_add(1, Ordering::SeqCst);
+                Err(AudioError::new(ErrorKind::Server, "500"))
+            })
+            .await;
+        assert_eq!(result.unwrap_err().kind, ErrorKind::Server);
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_permanent_errors_not_retried() {
+        let attempts = AtomicU32::new(0);
+        let result: Result<(), _> = policy(5)
+            .run("test", &CancellationToken::new(), || async {
+                attempts.fetch_add(1, Ordering::SeqCst);
+                Err(AudioError::new(ErrorKind::Auth, "bad key"))
+            })
+            .await;
+        assert_eq!(result.unwrap_err().kind, ErrorKind::Auth);
+        assert_eq!(attempts.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_cancel_during_backoff() {
+        let token = CancellationToken::new();
+        token.cancel();
+        let slow = RetryPolicy {
+            max_retries: 1,
+            initial_delay: Duration::from_secs(60),
+        };
+        let result: Result<(), _> = slow
+            .run("test", &token, || async { Err(AudioError::new(ErrorKind::Timeout, "timed out")) })
+            .await;
+        assert_eq!(result.unwrap_err().kind, ErrorKind::Cancelled);
+    }
+}
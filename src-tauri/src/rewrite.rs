@@ -0,0 +1,941 @@
+//! Prompts and providers for the rewrite step
+//!
+//! The rewrite step sends the transcript through a language model to clean up
+//! dictation with the selected prompt, to translate it, or both. A
+//! `RewriteProvider` talks to one of three API shapes: the OpenAI Responses
+//! API, Chat Completions (OpenAI and compatible servers such as Ollama), and
+//! a Messages-style API.
+
+use crate::flow::{AudioError, ErrorKind};
+use crate::template::{self, TemplateError, Variables};
+use crate::transcription::{self, OPENAI_BASE_URL};
+use crate::usage::TokenUsage;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Identifier of the OpenAI Responses API provider
+pub const PROVIDER_OPENAI_RESPONSES: &str = "openai_responses";
+
+/// Identifier of the provider for Chat Completions servers, including Ollama
+pub const PROVIDER_CHAT_COMPLETIONS: &str = "chat_completions";
+
+/// Identifier of the provider for Messages-style APIs
+pub const PROVIDER_MESSAGES: &str = "messages";
+
+/// All provider identifiers accepted in settings
+pub const AVAILABLE_PROVIDERS: &[&str] = &[PROVIDER_OPENAI_RESPONSES, PROVIDER_CHAT_COMPLETIONS, PROVIDER_MESSAGES];
+
+/// Accepted reasoning efforts; empty leaves the server default
+pub const REASONING_EFFORTS: &[&str] = &["", "minimal", "low", "medium", "high"];
+
+const MESSAGES_BASE_URL: &str = "https://api.anthropic.com/v1";
+const MESSAGES_API_VERSION: &str = "2023-06-01";
+
+/// Cap on generated tokens, including any reasoning
+const MAX_OUTPUT_TOKENS: u32 = 16384;
+
+/// Local models can take a while to load before the first request
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Longest accepted target language name
+const MAX_LANGUAGE_LEN: usize = 40;
+
+/// Template for translating without a rewrite prompt; `{target}` is replaced
+/// with the target language before rendering
+const TRANSLATE_PROMPT: &str = "Translate the following dictated text into {target}. \
+Keep the meaning, tone and formatting.\n\
+\n\
+Original text: {{transcript}}\n\
+\n\
+Return ONLY the translation, no explanations or formatting:";
+
+/// Start of the prompt for a voice command on selected text
+const COMMAND_PROMPT: &str = "Apply the following instruction to the text below. \
+Keep everything the instruction does not ask to change.";
+
+/// Which model rewrites the text, and how to reach it
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct RewriteSettings {
+    /// One of `AVAILABLE_PROVIDERS`
+    pub provider: String,
+    pub model: String,
+    /// API base URL, e.g. `http://localhost:11434/v1` for Ollama. Empty uses
+    /// OpenAI, or Anthropic for the Messages provider.
+    pub base_url: String,
+    /// API key for this provider. Empty uses the global OpenAI key when
+    /// talking to OpenAI, and no key for other servers.
+    pub api_key: String,
+    /// Sampling temperature, `None` for the server default. Reasoning models
+    /// may reject it.
+    pub temperature: Option<f32>,
+    /// One of `REASONING_EFFORTS`
+    pub reasoning_effort: String,
+    /// OpenAI service tier, e.g. "priority"; empty for the default tier
+    pub service_tier: String,
+}
+
+impl Default for RewriteSettings {
+    fn default() -> Self {
+        Self {
+            provider: PROVIDER_OPENAI_RESPONSES.to_string(),
+            model: "gpt-5.4".to_string(),
+            base_url: String::new(),
+            api_key: String::new(),
+            temperature: None,
+            reasoning_effort: "minimal".to_string(),
+            service_tier: "priority".to_string(),
+        }
+    }
+}
+
+/// Check rewrite settings before they are saved
+pub fn validate_settings(settings: &RewriteSettings) -> Result<(), String> {
+    if !AVAILABLE_PROVIDERS.contains(&settings.provider.as_str()) {
+        return Err(format!("Invalid rewrite provider: {}", settings.provider));
+    }
+    if settings.model.trim().is_empty() || settings.model.trim().chars().any(char::is_whitespace) {
+        return Err(format!("Invalid rewrite model name: '{}'", settings.model));
+    }
+    let base_url = settings.base_url.trim();
+    if !base_url.is_empty() {
+        let url = reqwest::Url::parse(base_url)
+            .map_err(|e| format!("Invalid rewrite base URL '{}': {}", base_url, e))?;
+        if url.scheme() != "http" && url.scheme() != "https" {
+            return Err(format!("Rewrite base URL must use http or https: {}", base_url));
+        }
+    }
+    if let Some(temperature) = settings.temperature
+        && !(0.0..=2.0).contains(&temperature)
+    {
+        return Err(format!("Temperature must be between 0 and 2, got {}", temperature));
+    }
+    if !REASONING_EFFORTS.contains(&settings.reasoning_effort.as_str()) {
+        return Err(format!("Invalid reasoning effort: {}", settings.reasoning_effort));
+    }
+    if !settings.service_tier.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        return Err(format!("Invalid service tier: '{}'", settings.service_tier));
+    }
+    Ok(())
+}
+
+/// Whether rewriting with these settings needs the global OpenAI key
+pub fn requires_openai_key(settings: &RewriteSettings) -> bool {
+    settings.provider != PROVIDER_MESSAGES && settings.base_url.trim().is_empty() && settings.api_key.trim().is_empty()
+}
+
+/// What a flow does when the rewrite step fails or returns no text
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum FailurePolicy {
+    /// Use the transcript as it is
+    FallbackSilent,
+    /// Use the transcript as it is and tell the user the rewrite was skipped
+    #[default]
+    FallbackWarn,
+    /// Fail the flow; the recording is kept so it can be retried
+    Fail,
+}
+
+/// Result of one rewrite request
+#[derive(Debug, Clone, PartialEq)]
+pub struct RewriteOutput {
+    pub text: String,
+    pub usage: TokenUsage,
+}
+
+/// Request and response shape of a provider
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ApiStyle {
+    Responses,
+    ChatCompletions,
+    Messages,
+}
+
+impl ApiStyle {
+    fn from_provider(provider: &str) -> Option<Self> {
+        match provider {
+            PROVIDER_OPENAI_RESPONSES => Some(ApiStyle::Responses),
+            PROVIDER_CHAT_COMPLETIONS => Some(ApiStyle::ChatCompletions),
+            PROVIDER_MESSAGES => Some(ApiStyle::Messages),
+            _ => None,
+        }
+    }
+
+    fn default_base_url(self) -> &'static str {
+        match self {
+            ApiStyle::Messages => MESSAGES_BASE_URL,
+            _ => OPENAI_BASE_URL,
+        }
+    }
+
+    fn path(self) -> &'static str {
+        match self {
+            ApiStyle::Responses => "responses",
+            ApiStyle::ChatCompletions => "chat/completions",
+            ApiStyle::Messages => "messages",
+        }
+    }
+
+    /// Whether requests go to OpenAI's own servers, the only ones that take
+    /// `service_tier` and accept every reasoning effort
+    fn is_openai(self, settings: &RewriteSettings) -> bool {
+        let base_url = settings.base_url.trim().trim_end_matches('/');
+        self != ApiStyle::Messages && (base_url.is_empty() || base_url == OPENAI_BASE_URL)
+    }
+
+    fn request_body(self, settings: &RewriteSettings, prompt: &str) -> serde_json::Value {
+        let effort = settings.reasoning_effort.as_str();
+        let openai = self.is_openai(settings);
+        let mut body = match self {
+            ApiStyle::Responses => serde_json::json!({
+                "model": settings.model,
+                "input": prompt,
+                "max_output_tokens": MAX_OUTPUT_TOKENS,
+            }),
+            ApiStyle::ChatCompletions => serde_json::json!({
+                "model": settings.model,
+                "messages": [{ "role": "user", "content": prompt }],
+                "stream": false,
+            }),
+            ApiStyle::Messages => serde_json::json!({
+                "model": settings.model,
+                "messages": [{ "role": "user", "content": prompt }],
+                "max_tokens": MAX_OUTPUT_TOKENS,
+            }),
+        };
+        match self {
+            ApiStyle::Responses if openai && !effort.is_empty() => body["reasoning"] = serde_json::json!({ "effort": effort }),
+            ApiStyle::ChatCompletions if openai && !effort.is_empty() => body["reasoning_effort"] = serde_json::json!(effort),
+            // Messages APIs take a thinking budget instead of an effort level
+            ApiStyle::Messages => {
+                let budget = match effort {
+                    "low" => 1024,
+                    "medium" => 4096,
+                    "high" => 8192,
+                    _ => 0,
+                };
+                if budget > 0 {
+                    body["thinking"] = serde_json::json!({ "type": "enabled", "budget_tokens": budget });
+                }
+            }
+            _ => {}
+        }
+        // Messages APIs reject a temperature while thinking is enabled
+        if let Some(temperature) = settings.temperature
+            && body.get("thinking").is_none()
+        {
+            body["temperature"] = serde_json::json!(temperature);
+        }
+        if openai && !settings.service_tier.is_empty() {
+            body["service_tier"] = serde_json::json!(settings.service_tier);
+        }
+        body
+    }
+
+    fn parse_response(self, response_text: &str) -> Result<RewriteOutput, AudioError> {
+        let invalid = |e: serde_json::Error| {
+            AudioError::new(
+                ErrorKind::InvalidResponse,
+                format!("Failed to parse rewrite response: {} | Raw response: {}", e, response_text),
+            )
+        };
+        let (text, usage) = match self {
+            ApiStyle::Responses => {
+                let response: ResponsesResponse = serde_json::from_str(response_text).map_err(invalid)?;
+                // Reasoning items come before the message and have no content
+                let text = response
+                    .output
+                    .into_iter()
+                    .find(|item| item.output_type == "message")
+                    .and_then(|item| item.content)
+                    .and_then(|content| content.into_iter().next())
+                    .map(|content| content.text);
+                (text, response.usage.unwrap_or_default())
+            }
+            ApiStyle::ChatCompletions => {
+                let response: ChatResponse = serde_json::from_str(response_text).map_err(invalid)?;
+                let text = response
+                    .choices
+                    .into_iter()
+                    .next()
+                    .and_then(|choice| choice.message.content)
+                    .map(|content| strip_thinking(&content).to_string());
+                let usage = response.usage.map(TokenUsage::from).unwrap_or_default();
+                (text, usage)
+            }
+            ApiStyle::Messages => {
+                let response: MessagesResponse = serde_json::from_str(response_text).map_err(invalid)?;
+                let text: String = response
+                    .content
+                    .into_iter()
+                    .filter(|block| block.block_type == "text")
+                    .filter_map(|block| block.text)
+                    .collect();
+                (Some(text).filter(|t| !t.is_empty()), response.usage.unwrap_or_default())
+            }
+        };
+        let text = text.filter(|t| !t.trim().is_empty()).ok_or_else(|| {
+            AudioError::new(ErrorKind::InvalidResponse, format!("Rewrite response contained no text: {}", response_text))
+        })?;
+        Ok(RewriteOutput { text, usage })
+    }
+}
+
+/// What one server-sent event of a streamed response carried
+#[derive(Debug, Default, PartialEq)]
+struct StreamEvent {
+    text: Option<String>,
+    input_tokens: Option<u64>,
+    output_tokens: Option<u64>,
+    /// The server will send nothing more
+    done: bool,
+}
+
+impl ApiStyle {
+    fn parse_stream_event(self, data: &str) -> Result<StreamEvent, AudioError> {
+        if self == ApiStyle::ChatCompletions && data.trim() == "[DONE]" {
+            return Ok(StreamEvent { done: true, ..Default::default() });
+        }
+        let value: serde_json::Value = serde_json::from_str(data).map_err(|e| {
+            AudioError::new(
+                ErrorKind::InvalidResponse,
+                format!("Failed to parse rewrite stream event: {} | Raw event: {}", e, data),
+            )
+        })?;
+        if let Some(error) = value.get("error").filter(|e| !e.is_null()) {
+            let message = error.get("message").and_then(|m| m.as_str()).unwrap_or("unknown error");
+            return Err(AudioError::new(ErrorKind::Server, format!("Rewrite stream failed: {}", message)));
+        }
+        let text = |v: &serde_json::Value| v.as_str().map(str::to_string);
+        let tokens = |v: &serde_json::Value| v.as_u64();
+        let event_type = value["type"].as_str().unwrap_or("");
+
+        Ok(match self {
+            ApiStyle::Responses => match event_type {
+                "response.output_text.delta" => StreamEvent { text: text(&value["delta"]), ..Default::default() },
+                "response.completed" => StreamEvent {
+                    input_tokens: tokens(&value["response"]["usage"]["input_tokens"]),
+                    output_tokens: tokens(&value["response"]["usage"]["output_tokens"]),
+                    done: true,
+                    ..Default::default()
+                },
+                "response.failed" | "response.incomplete" => {
+                    return Err(AudioError::new(
+                        ErrorKind::Server,
+                        format!("Rewrite stream ended early: {}", event_type),
+                    ));
+                }
+                _ => StreamEvent::default(),
+            },
+            ApiStyle::ChatCompletions => StreamEvent {
+                text: text(&value["choices"][0]["delta"]["content"]),
+                input_tokens: tokens(&value["usage"]["prompt_tokens"]),
+                output_tokens: tokens(&value["usage"]["completion_tokens"]),
+                done: false,
+            },
+            ApiStyle::Messages => match event_type {
+                "content_block_delta" if value["delta"]["type"] == "text_delta" => {
+                    StreamEvent { text: text(&value["delta"]["text"]), ..Default::default() }
+                }
+                "message_start" => StreamEvent {
+                    input_tokens: tokens(&value["message"]["usage"]["input_tokens"]),
+                    ..Default::default()
+                },
+                "message_delta" => StreamEvent {
+                    output_tokens: tokens(&value["usage"]["output_tokens"]),
+                    ..Default::default()
+                },
+                "message_stop" => StreamEvent { done: true, ..Default::default() },
+                _ => StreamEvent::default(),
+            },
+        })
+    }
+
+    /// Part of the streamed text that belongs to the answer
+    fn visible_text(self, raw: &str) -> &str {
+        if self != ApiStyle::ChatCompletions {
+            return raw;
+        }
+        // Hold back a thinking block until it is closed
+        let start = raw.trim_start();
+        if start.is_empty() || "<think>".starts_with(start) || (start.starts_with("<think>") && !start.contains("</think>")) {
+            return "";
+        }
+        strip_thinking(raw)
+    }
+}
+
+/// Splits a server-sent event stream into the data of each event
+#[derive(Default)]
+struct SseParser {
+    buffer: Vec<u8>,
+    data: String,
+}
+
+impl SseParser {
+    /// Add received bytes and return the data of every event they complete
+    fn push(&mut self, bytes: &[u8]) -> Vec<String> {
+        self.buffer.extend_from_slice(bytes);
+        let mut events = Vec::new();
+        while let Some(end) = self.buffer.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = self.buffer.drain(..=end).collect();
+            let line = String::from_utf8_lossy(&line);
+            let line = line.trim_end_matches(['\n', '\r']);
+            if line.is_empty() {
+                if !self.data.is_empty() {
+                    events.push(std::mem::take(&mut self.data));
+                }
+            } else if let Some(value) = line.strip_prefix("data:") {
+                if !self.data.is_empty() {
+                    self.data.push('\n');
+                }
+                self.data.push_str(value.strip_prefix(' ').unwrap_or(value));
+            }
+            // Event names, ids and comments are not needed
+        }
+        events
+    }
+
+    /// Data of an event left unterminated when the stream ended
+    fn finish(&mut self) -> Option<String> {
+        self.push(b"\n\n").pop()
+    }
+}
+
+#[derive(Deserialize)]
+struct ResponsesResponse {
+    output: Vec<ResponsesOutputItem>,
+    #[serde(default)]
+    usage: Option<TokenUsage>,
+}
+
+#[derive(Deserialize)]
+struct ResponsesOutputItem {
+    #[serde(rename = "type")]
+    output_type: String,
+    content: Option<Vec<ResponsesContentItem>>,
+}
+
+#[derive(Deserialize)]
+struct ResponsesContentItem {
+    text: String,
+}
+
+#[derive(Deserialize)]
+struct ChatResponse {
+    choices: Vec<ChatChoice>,
+    #[serde(default)]
+    usage: Option<ChatUsage>,
+}
+
+#[derive(Deserialize)]
+struct ChatChoice {
+    message: ChatMessage,
+}
+
+#[derive(Deserialize)]
+struct ChatMessage {
+    content: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ChatUsage {
+    #[serde(default)]
+    prompt_tokens: u64,
+    #[serde(default)]
+    completion_tokens: u64,
+}
+
+impl From<ChatUsage> for TokenUsage {
+    fn from(usage: ChatUsage) -> Self {
+        TokenUsage {
+            input_tokens: usage.prompt_tokens,
+            output_tokens: usage.completion_tokens,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct MessagesResponse {
+    content: Vec<MessagesContentBlock>,
+    #[serde(default)]
+    usage: Option<TokenUsage>,
+}
+
+#[derive(Deserialize)]
+struct MessagesContentBlock {
+    #[serde(rename = "type")]
+    block_type: String,
+    text: Option<String>,
+}
+
+/// Drop the `<think>` block local reasoning models put before their answer
+fn strip_thinking(text: &str) -> &str {
+    match text.trim_start().strip_prefix("<think>").and_then(|rest| rest.split_once("</think>")) {
+        Some((_, answer)) => answer.trim_start(),
+        None => text,
+    }
+}
+
+/// A configured model and server to send rewrite prompts to
+pub struct RewriteProvider {
+    style: ApiStyle,
+    settings: RewriteSettings,
+    base_url: String,
+    api_key: Option<String>,
+}
+
+impl RewriteProvider {
+    /// Create the provider described by `settings`
+    ///
+    /// `openai_api_key` is the global OpenAI key; it is only sent to OpenAI.
+    pub fn create(settings: &RewriteSettings, openai_api_key: &str) -> Result<Self, AudioError> {
+        validate_settings(settings).map_err(|message| AudioError::new(ErrorKind::Config, message))?;
+        let style = ApiStyle::from_provider(&settings.provider)
+            .ok_or_else(|| AudioError::new(ErrorKind::Config, format!("Invalid rewrite provider: {}", settings.provider)))?;
+        let base_url = match settings.base_url.trim() {
+            "" => style.default_base_url().to_string(),
+            url => url.trim_end_matches('/').to_string(),
+        };
+        let api_key = if requires_openai_key(settings) {
+            Some(transcription::resolve_api_key(openai_api_key)?)
+        } else {
+            Some(settings.api_key.trim().to_string()).filter(|key| !key.is_empty())
+        };
+        if style == ApiStyle::Messages && api_key.is_none() && settings.base_url.trim().is_empty() {
+            return Err(AudioError::new(ErrorKind::Config, "The Messages provider needs an API key"));
+        }
+        Ok(Self {
+            style,
+            settings: settings.clone(),
+            base_url,
+            api_key,
+        })
+    }
+
+    /// "provider/model" as recorded in usage; requests to OpenAI's servers
+    /// are labelled "openai" so they are priced
+    pub fn label(&self) -> String {
+        let provider = if self.base_url == OPENAI_BASE_URL { "openai" } else { self.settings.provider.as_str() };
+        format!("{}/{}", provider, self.settings.model)
+    }
+
+    /// Send `prompt` and return the model's answer. Cancellation is handled
+    /// by the caller dropping the returned future.
+    pub async fn rewrite(&self, prompt: &str) -> Result<RewriteOutput, AudioError> {
+        let response = self.send(prompt, false).await?;
+        let response_text = response
+            .text()
+            .await
+            .map_err(|e| AudioError::request("Failed to get response text", e))?;
+        self.style.parse_response(&response_text)
+    }
+
+    /// Like `rewrite`, but streams the answer and calls `on_text` with the
+    /// text received so far each time more arrives
+    pub async fn rewrite_streaming(
+        &self,
+        prompt: &str,
+        mut on_text: impl FnMut(&str) + Send,
+    ) -> Result<RewriteOutput, AudioError> {
+        let mut response = self.send(prompt, true).await?;
+
+        let mut parser = SseParser::default();
+        let mut raw = String::new();
+        let mut usage = TokenUsage::default();
+        let mut done = false;
+        while !done {
+            let chunk = response
+                .chunk()
+                .await
+                .map_err(|e| AudioError::request("Failed to read rewrite stream", e))?;
+            let events = match &chunk {
+                Some(bytes) => parser.push(bytes),
+                None => {
+                    done = true;
+                    parser.finish().into_iter().collect()
+                }
+            };
+            let received = raw.len();
+            for data in events {
+                let event = self.style.parse_stream_event(&data)?;
+                if let Some(text) = event.text {
+                    raw.push_str(&text);
+                }
+                usage.input_tokens = event.input_tokens.unwrap_or(usage.input_tokens);
+                usage.output_tokens = event.output_tokens.unwrap_or(usage.output_tokens);
+                done |= event.done;
+            }
+            if raw.len() > received {
+                on_text(self.style.visible_text(&raw));
+            }
+        }
+
+        let text = self.style.visible_text(&raw).to_string();
+        if text.trim().is_empty() {
+            return Err(AudioError::new(ErrorKind::InvalidResponse, "Rewrite stream contained no text"));
+        }
+        Ok(RewriteOutput { text, usage })
+    }
+
+    async fn send(&self, prompt: &str, stream: bool) -> Result<reqwest::Response, AudioError> {
+        let client = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .map_err(|e| AudioError::request("Failed to create HTTP client", e))?;
+
+        let url = format!("{}/{}", self.base_url, self.style.path());
+        println!("Sending rewrite request to {} ({})...", url, self.settings.model);
+
+        let mut body = self.style.request_body(&self.settings, prompt);
+        if stream {
+            body["stream"] = serde_json::json!(true);
+            if self.style == ApiStyle::ChatCompletions {
+                // Usage is only sent in a final chunk when asked for
+                body["stream_options"] = serde_json::json!({ "include_usage": true });
+            }
+        }
+
+        let mut request_builder = client.post(&url).json(&body);
+        match (&self.api_key, self.style) {
+            (Some(api_key), ApiStyle::Messages) => {
+                request_builder = request_builder.header("x-api-key", api_key);
+            }
+            (Some(api_key), _) => {
+                request_builder = request_builder.header("Authorization", format!("Bearer {}", api_key));
+            }
+            (None, _) => {}
+        }
+        if self.style == ApiStyle::Messages {
+            request_builder = request_builder.header("anthropic-version", MESSAGES_API_VERSION);
+        }
+
+        let response = request_builder
+            .send()
+            .await
+            .map_err(|e| AudioError::request("Failed to send rewrite request", e))?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response
+                .text()
+                .await
+                .unwrap_or_else(|_| "Unknown error".to_string());
+            return Err(AudioError::http_status(status, format!("Rewrite API error {}: {}", status, error_text)));
+        }
+        Ok(response)
+    }
+}
+
+/// Accepts an empty value (no translation) or a language such as "English" or "pt-BR"
+pub fn validate_target_language(language: &str) -> Result<(), String> {
+    let language = language.trim();
+    if language.len() > MAX_LANGUAGE_LEN {
+        return Err(format!("Target language must be at most {} characters", MAX_LANGUAGE_LEN));
+    }
+    if !language.chars().all(|c| c.is_alphabetic() || c == ' ' || c == '-' || c == '(' || c == ')') {
+        return Err(format!("Invalid target language: '{}'", language));
+    }
+    Ok(())
+}
+
+/// Target language to send for a target language setting; `None` means no translation
+pub fn target_language(language: &str) -> Option<String> {
+    Some(language.trim().to_string()).filter(|l| !l.is_empty())
+}
+
+/// Build the prompt for one rewrite
+///
+/// `template` is the selected rewrite prompt, or `None` to only translate.
+/// With a `target_language`, the result is asked for in that language.
+pub fn build_prompt(
+    template: Option<&str>,
+    variables: &Variables,
+    target_language: Option<&str>,
+) -> Result<String, TemplateError> {
+    Ok(match (template, target_language) {
+        (Some(template), None) => template::render(template, variables)?,
+        (Some(template), Some(language)) => format!(
+            "{}\n\nWrite the corrected text in {}, translating it if it is in another language.",
+            template::render(template, variables)?,
+            language
+        ),
+        (None, Some(language)) => template::render(&TRANSLATE_PROMPT.replace("{target}", language), variables)?,
+        (None, None) => variables.transcript.clone(),
+    })
+}
+
+/// Build the prompt for a spoken instruction on selected text, such as
+/// "make this more formal"
+pub fn build_command_prompt(instruction: &str, selection: &str) -> String {
+    format!(
+        "{}\n\nInstruction: {}\n\nText:\n{}\n\nReturn ONLY the resulting text, no explanations or formatting:",
+        COMMAND_PROMPT,
+        instruction.trim(),
+        selection
+    )
+}
+
+/// Put earlier dictation before a rewrite prompt, marked as context only
+pub fn with_prior_context(prompt: String, context: &[String]) -> String {
+    if context.is_empty() {
+        return prompt;
+    }
+    format!(
+        "Earlier dictation, for context only. Do not rewrite or repeat it:\n<context>\n{}\n</context>\n\n{}",
+        context.join("\n"),
+        prompt
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(provider: &str) -> RewriteSettings {
+        RewriteSettings {
+            provider: provider.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_validate_settings() {
+        assert!(validate_settings(&RewriteSettings::default()).is_ok());
+        assert!(validate_settings(&settings("gemini")).is_err());
+        assert!(validate_settings(&RewriteSettings { model: "gpt 5".to_string(), ..Default::default() }).is_err());
+        assert!(validate_settings(&RewriteSettings { temperature: Some(3.0), ..Default::default() }).is_err());
+        assert!(validate_settings(&RewriteSettings { reasoning_effort: "max".to_string(), ..Default::default() }).is_err());
+        assert!(validate_settings(&RewriteSettings { base_url: "ftp://host".to_string(), ..Default::default() }).is_err());
+    }
+
+    #[test]
+    fn test_requires_openai_key() {
+        assert!(requires_openai_key(&RewriteSettings::default()));
+        let ollama = RewriteSettings {
+            base_url: "http://localhost:11434/v1".to_string(),
+            ..settings(PROVIDER_CHAT_COMPLETIONS)
+        };
+        assert!(!requires_openai_key(&ollama));
+        assert!(!requires_openai_key(&settings(PROVIDER_MESSAGES)));
+    }
+
+    #[test]
+    fn test_request_bodies() {
+        let body = ApiStyle::Responses.request_body(&RewriteSettings::default(), "hi");
+        assert_eq!(body["input"], "hi");
+        assert_eq!(body["reasoning"]["effort"], "minimal");
+        assert_eq!(body["service_tier"], "priority");
+        assert!(body.get("temperature").is_none());
+
+        let chat = RewriteSettings {
+            temperature: Some(0.2),
+            reasoning_effort: String::new(),
+            service_tier: String::new(),
+            ..settings(PROVIDER_CHAT_COMPLETIONS)
+        };
+        let body = ApiStyle::ChatCompletions.request_body(&chat, "hi");
+        assert_eq!(body["messages"][0]["content"], "hi");
+        assert!((body["temperature"].as_f64().unwrap() - 0.2).abs() < 1e-6);
+        assert!(body.get("reasoning_effort").is_none());
+        assert!(body.get("service_tier").is_none());
+
+        // The defaults are only sent to OpenAI itself
+        let body = ApiStyle::ChatCompletions.request_body(&settings(PROVIDER_CHAT_COMPLETIONS), "hi");
+        assert_eq!(body["reasoning_effort"], "minimal");
+        let ollama = RewriteSettings {
+            base_url: "http://localhost:11434/v1".to_string(),
+            ..settings(PROVIDER_CHAT_COMPLETIONS)
+        };
+        let body = ApiStyle::ChatCompletions.request_body(&ollama, "hi");
+        assert!(body.get("reasoning_effort").is_none());
+        assert!(body.get("service_tier").is_none());
+
+        let messages = RewriteSettings {
+            temperature: Some(0.2),
+            reasoning_effort: "low".to_string(),
+            ..settings(PROVIDER_MESSAGES)
+        };
+        let body = ApiStyle::Messages.request_body(&messages, "hi");
+        assert_eq!(body["max_tokens"], MAX_OUTPUT_TOKENS);
+        assert_eq!(body["thinking"]["budget_tokens"], 1024);
+        assert!(body.get("temperature").is_none());
+        assert!(body.get("service_tier").is_none());
+        let messages = RewriteSettings { reasoning_effort: String::new(), ..messages };
+        let body = ApiStyle::Messages.request_body(&messages, "hi");
+        assert!(body.get("temperature").is_some());
+    }
+
+    #[test]
+    fn test_parse_responses() {
+        let json = r#"{
+            "output": [
+                {"type": "reasoning", "summary": []},
+                {"type": "message", "content": [{"type": "output_text", "text": "Hello."}]}
+            ],
+            "usage": {"input_tokens": 12, "output_tokens": 3, "total_tokens": 15}
+        }"#;
+        let output = ApiStyle::Responses.parse_response(json).unwrap();
+        assert_eq!(output.text, "Hello.");
+        assert_eq!(output.usage, TokenUsage { input_tokens: 12, output_tokens: 3 });
+
+        let no_message = ApiStyle::Responses.parse_response(r#"{"output": [{"type": "reasoning"}]}"#);
+        assert_eq!(no_message.unwrap_err().kind, ErrorKind::InvalidResponse);
+
+        let blank = r#"{"output": [{"type": "message", "content": [{"type": "output_text", "text": " \n"}]}]}"#;
+        assert_eq!(ApiStyle::Responses.parse_response(blank).unwrap_err().kind, ErrorKind::InvalidResponse);
+    }
+
+    #[test]
+    fn test_parse_chat_completions() {
+        let json = r#"{
+            "choices": [{"index": 0, "message": {"role": "assistant", "content": "<think>hmm</think>\n\nHello."}}],
+            "usage": {"prompt_tokens": 10, "completion_tokens": 4}
+        }"#;
+        let output = ApiStyle::ChatCompletions.parse_response(json).unwrap();
+        assert_eq!(output.text, "Hello.");
+        assert_eq!(output.usage, TokenUsage { input_tokens: 10, output_tokens: 4 });
+    }
+
+    #[test]
+    fn test_parse_messages() {
+        let json = r#"{
+            "content": [{"type": "thinking", "thinking": "..."}, {"type": "text", "text": "Hello."}],
+            "usage": {"input_tokens": 9, "output_tokens": 2}
+        }"#;
+        let output = ApiStyle::Messages.parse_response(json).unwrap();
+        assert_eq!(output.text, "Hello.");
+        assert_eq!(output.usage.output_tokens, 2);
+    }
+
+    #[test]
+    fn test_sse_parser() {
+        let mut parser = SseParser::default();
+        assert!(parser.push(b"event: message\ndata: {\"a\"").is_empty());
+        assert_eq!(parser.push(b": 1}\r\n\r\ndata: [DONE]\n\n"), vec!["{\"a\": 1}", "[DONE]"]);
+
+        // Multi-byte characters split across chunks survive
+        let bytes = "data: héllo\n\n".as_bytes();
+        assert!(parser.push(&bytes[..8]).is_empty());
+        assert_eq!(parser.push(&bytes[8..]), vec!["héllo"]);
+
+        assert!(parser.push(b"data: last").is_empty());
+        assert_eq!(parser.finish().as_deref(), Some("last"));
+        assert_eq!(parser.finish(), None);
+    }
+
+    #[test]
+    fn test_parse_stream_events() {
+        let delta = ApiStyle::Responses
+            .parse_stream_event(r#"{"type": "response.output_text.delta", "delta": "Hel"}"#)
+            .unwrap();
+        assert_eq!(delta.text.as_deref(), Some("Hel"));
+        let completed = ApiStyle::Responses
+            .parse_stream_event(r#"{"type": "response.completed", "response": {"usage": {"input_tokens": 5, "output_tokens": 2}}}"#)
+            .unwrap();
+        assert!(completed.done);
+        assert_eq!((completed.input_tokens, completed.output_tokens), (Some(5), Some(2)));
+
+        let chunk = ApiStyle::ChatCompletions
+            .parse_stream_event(r#"{"choices": [{"delta": {"content": "lo"}}]}"#)
+            .unwrap();
+        assert_eq!(chunk.text.as_deref(), Some("lo"));
+        let usage = ApiStyle::ChatCompletions
+            .parse_stream_event(r#"{"choices": [], "usage": {"prompt_tokens": 7, "completion_tokens": 3}}"#)
+            .unwrap();
+        assert_eq!(usage.output_tokens, Some(3));
+        assert!(ApiStyle::ChatCompletions.parse_stream_event("[DONE]").unwrap().done);
+
+        let text = ApiStyle::Messages
+            .parse_stream_event(r#"{"type": "content_block_delta", "index": 0, "delta": {"type": "text_delta", "text": "Hi"}}"#)
+            .unwrap();
+        assert_eq!(text.text.as_deref(), Some("Hi"));
+        let thinking = ApiStyle::Messages
+            .parse_stream_event(r#"{"type": "content_block_delta", "delta": {"type": "thinking_delta", "thinking": "hm"}}"#)
+            .unwrap();
+        assert_eq!(thinking, StreamEvent::default());
+
+        let error = ApiStyle::Messages.parse_stream_event(r#"{"type": "error", "error": {"message": "Overloaded"}}"#);
+        assert_eq!(error.unwrap_err().kind, ErrorKind::Server);
+    }
+
+    #[test]
+    fn test_visible_text() {
+        let chat = ApiStyle::ChatCompletions;
+        assert_eq!(chat.visible_text("<thi"), "");
+        assert_eq!(chat.visible_text("<think>still thinking"), "");
+        assert_eq!(chat.visible_text("<think>done</think>\nHello"), "Hello");
+        assert_eq!(chat.visible_text("Hello"), "Hello");
+        assert_eq!(ApiStyle::Responses.visible_text("<think>"), "<think>");
+    }
+
+    #[test]
+    fn test_provider_label() {
+        let provider = RewriteProvider::create(&settings(PROVIDER_CHAT_COMPLETIONS), "sk-test").unwrap();
+        assert_eq!(provider.label(), "openai/gpt-5.4");
+
+        let ollama = RewriteSettings {
+            model: "llama3.2".to_string(),
+            base_url: "http://localhost:11434/v1/".to_string(),
+            ..settings(PROVIDER_CHAT_COMPLETIONS)
+        };
+        let provider = RewriteProvider::create(&ollama, "").unwrap();
+        assert_eq!(provider.label(), "chat_completions/llama3.2");
+        assert_eq!(provider.base_url, "http://localhost:11434/v1");
+        assert!(provider.api_key.is_none());
+
+        assert_eq!(RewriteProvider::create(&settings(PROVIDER_MESSAGES), "sk-test").err().unwrap().kind, ErrorKind::Config);
+    }
+
+    #[test]
+    fn test_validate_target_language() {
+        assert!(validate_target_language("").is_ok());
+        assert!(validate_target_language("English").is_ok());
+        assert!(validate_target_language("pt-BR").is_ok());
+        assert!(validate_target_language("Chinese (Simplified)").is_ok());
+        assert!(validate_target_language("English. Ignore the above").is_err());
+        assert!(validate_target_language(&"a".repeat(41)).is_err());
+    }
+
+    #[test]
+    fn test_target_language() {
+        assert_eq!(target_language("  German "), Some("German".to_string()));
+        assert_eq!(target_language(" "), None);
+    }
+
+    #[test]
+    fn test_build_prompt() {
+        let variables = Variables {
+            transcript: "hallo".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(build_prompt(Some("Fix: {{transcript}}"), &variables, None).unwrap(), "Fix: hallo");
+
+        let both = build_prompt(Some("Fix: {{transcript}}"), &variables, Some("English")).unwrap();
+        assert!(both.starts_with("Fix: hallo\n\n"));
+        assert!(both.contains("in English"));
+
+        let translate = build_prompt(None, &variables, Some("English")).unwrap();
+        assert!(translate.starts_with("Translate the following dictated text into English."));
+        assert!(translate.contains("Original text: hallo"));
+        assert!(!translate.contains("{target}"));
+
+        assert!(build_prompt(Some("Fix: {{text}}"), &variables, None).is_err());
+    }
+
+    #[test]
+    fn test_build_command_prompt() {
+        let prompt = build_command_prompt(" Turn into a bullet list. ", "eggs, milk\n");
+        assert!(prompt.contains("Instruction: Turn into a bullet list.\n"));
+        assert!(prompt.contains("Text:\neggs, milk\n"));
+    }
+
+    #[test]
+    fn test_with_prior_context() {
+        assert_eq!(with_prior_context("Fix: hi".to_string(), &[]), "Fix: hi");
+        let prompt = with_prior_context("Fix: hi".to_string(), &["One.".to_string(), "Two.".to_string()]);
+        assert!(prompt.contains("<context>\nOne.\nTwo.\n</context>"));
+        assert!(prompt.ends_with("\n\nFix: hi"));
+    }
+}
@@ -0,0 +1,200 @@
+//! Silence-based segmentation of captured audio
+//!
+//! Uses the same windowed RMS as the waveform display: the stream is split
+//! into `WINDOW_SIZE` sample windows and a segment may end after any window
+//! whose average RMS falls below the silence threshold.
+
+/// Samples per RMS window
+pub const WINDOW_SIZE: usize = 2048;
+
+/// Samples per waveform bin within a window
+pub const BIN_SIZE: usize = 8;
+
+/// Average window RMS below which audio counts as silence (about -40 dBFS)
+pub const DEFAULT_SILENCE_RMS: f32 = 0.01;
+
+/// Compute per-bin RMS (`WINDOW_SIZE / BIN_SIZE` bins) and the average RMS of a window
+pub fn window_rms(window: &[f32]) -> (Vec<f32>, f32) {
+    let mut bins: Vec<f32> = Vec::with_capacity(window.len().div_ceil(BIN_SIZE));
+    let mut sum_sq_total: f32 = 0.0;
+    for chunk in window.chunks(BIN_SIZE) {
+        let mut sum_sq = 0.0f32;
+        for &s in chunk {
+            let ss = s * s;
+            sum_sq += ss;
+            sum_sq_total += ss;
+        }
+        bins.push((sum_sq / BIN_SIZE as f32).sqrt());
+    }
+    let avg_rms = if window.is_empty() {
+        0.0
+    } else {
+        (sum_sq_total / window.len() as f32).sqrt()
+    };
+    (bins, avg_rms)
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct SegmentConfig {
+    /// Segments shorter than this are never cut, even at silence
+    pub min_samples: usize,
+    /// Segments are cut here even if no silence was found
+    pub max_samples: usize,
+    /// Windows with average RMS below this count as silence
+    pub silence_rms: f32,
+}
+
+impl SegmentConfig {
+    /// Config for the given sample rate with segment bounds in seconds
+    pub fn new(sample_rate: u32, min_secs: f32, max_secs: f32, silence_rms: f32) -> Self {
+        Self {
+            min_samples: (sample_rate as f32 * min_secs) as usize,
+            max_samples: (sample_rate as f32 * max_secs) as usize,
+            silence_rms,
+        }
+    }
+}
+
+/// End of a segment within the samples passed to [`SilenceSegmenter::push`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SegmentCut {
+    pub offset: usize,
+    /// Whether any window of the finished segment was above the silence threshold
+    pub voiced: bool,
+}
+
+/// Finds segment boundaries in a stream of mono samples
+pub struct SilenceSegmenter {
+    config: SegmentConfig,
+    window: Vec<f32>,
+    segment_len: usize,
+    segment_voiced: bool,
+}
+
+impl SilenceSegmenter {
+    pub fn new(config: SegmentConfig) -> Self {
+        Self {
+            config,
+            window: Vec::with_capacity(WINDOW_SIZE),
+            segment_len: 0,
+            segment_voiced: false,
+        }
+    }
+
+    /// Feed samples and return the points in `samples` at which a segment ends
+    ///
+    /// Everything before the first cut belongs to the current segment, the
+    /// samples between two cuts form a complete segment, and the rest starts
+    /// the next one.
+    pub fn push(&mut self, samples: &[f32]) -> Vec<SegmentCut> {
+        let mut cuts = Vec::new();
+        let mut offset = 0;
+
+        while offset < samples.len() {
+            let take = (WINDOW_SIZE - self.window.len()).min(samples.len() - offset);
+            self.window.extend_from_slice(&samples[offset..offset + take]);
+            offset += take;
+            self.segment_len += take;
+
+            if self.window.len() == WINDOW_SIZE {
+                let (_, avg_rms) = window_rms(&self.window);
+                self.window.clear();
+
+                let silent = avg_rms < self.config.silence_rms;
+                self.segment_voiced |= !silent;
+                if (silent && self.segment_len >= self.config.min_samples)
+                    || self.segment_len >= self.config.max_samples
+                {
+                    cuts.push(SegmentCut { offset, voiced: self.segment_voiced });
+                    self.segment_len = 0;
+                    self.segment_voiced = false;
+                }
+            }
+        }
+
+        cuts
+    }
+
+    /// Number of samples in the current, unfinished segment
+    pub fn segment_len(&self) -> usize {
+        self.segment_len
+    }
+
+    /// Whether the current segment has had any window above the silence threshold
+    pub fn segment_voiced(&self) -> bool {
+        self.segment_voiced
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tone(len: usize) -> Vec<f32> {
+        (0..len).map(|i| (i as f32 * 0.05).sin() * 0.5).collect()
+    }
+
+    fn test_config() -> SegmentConfig {
+        SegmentConfig {
+            min_samples: WINDOW_SIZE * 4,
+            max_samples: WINDOW_SIZE * 16,
+            silence_rms: DEFAULT_SILENCE_RMS,
+        }
+    }
+
+    #[test]
+    fn test_window_rms() {
+        let (bins, avg) = window_rms(&vec![0.5f32; WINDOW_SIZE]);
+        assert_eq!(bins.len(), WINDOW_SIZE / BIN_SIZE);
+        assert!(bins.iter().all(|&b| (b - 0.5).abs() < 1e-6));
+        assert!((avg - 0.5).abs() < 1e-6);
+
+        let (bins, avg) = window_rms(&[]);
+        assert!(bins.is_empty());
+        assert_eq!(avg, 0.0);
+    }
+
+    #[test]
+    fn test_cut_at_silence_after_min_length() {
+        let mut segmenter = SilenceSegmenter::new(test_config());
+
+        // Silence before the minimum length never cuts
+        assert!(segmenter.push(&vec![0.0; WINDOW_SIZE * 2]).is_empty());
+
+        let mut samples = tone(WINDOW_SIZE * 3);
+        samples.extend(vec![0.0; WINDOW_SIZE * 2]);
+        let cuts = segmenter.push(&samples);
+
+        // First silent window after reaching the minimum ends the segment
+        assert_eq!(cuts, vec![SegmentCut { offset: WINDOW_SIZE * 4, voiced: true }]);
+        assert_eq!(segmenter.segment_len(), WINDOW_SIZE);
+        assert!(!segmenter.segment_voiced());
+    }
+
+    #[test]
+    fn test_cut_at_max_length() {
+        let mut segmenter = SilenceSegmenter::new(test_config());
+        let cuts = segmenter.push(&tone(WINDOW_SIZE * 40));
+        let offsets: Vec<usize> = cuts.iter().map(|c| c.offset).collect();
+        assert_eq!(offsets, vec![WINDOW_SIZE * 16, WINDOW_SIZE * 32]);
+        assert_eq!(segmenter.segment_len(), WINDOW_SIZE * 8);
+    }
+
+    #[test]
+    fn test_windows_span_pushes() {
+        let mut segmenter = SilenceSegmenter::new(test_config());
+        assert!(segmenter.push(&tone(WINDOW_SIZE * 4 + 100)).is_empty());
+
+        // The window straddling both pushes still holds speech, so the cut
+        // lands after the first fully silent window
+        let cuts = segmenter.push(&vec![0.0; WINDOW_SIZE * 2]);
+        assert_eq!(cuts, vec![SegmentCut { offset: WINDOW_SIZE * 2 - 100, voiced: true }]);
+    }
+
+    #[test]
+    fn test_silent_segment_not_voiced() {
+        let mut segmenter = SilenceSegmenter::new(test_config());
+        let cuts = segmenter.push(&vec![0.0; WINDOW_SIZE * 5]);
+        assert_eq!(cuts, vec![SegmentCut { offset: WINDOW_SIZE * 4, voiced: false }]);
+    }
+}
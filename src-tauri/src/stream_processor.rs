@@ -4,6 +4,14 @@ use rubato::{
 };
 use std::error::Error;
 
+/// A finished recording
+pub struct EncodedAudio {
+    /// Complete WebM file
+    pub data: Vec<u8>,
+    /// Length of the audio, from the samples written to the WebM encoder
+    pub duration_secs: f64,
+}
+
 /// Streaming audio processor that resamples and encodes audio incrementally
 ///
 /// This processor accepts audio samples in chunks (as they arrive from the audio device),
@@ -148,8 +156,9 @@ impl AudioStreamProcessor {
     /// Finalize the encoder and return the complete WebM file
     ///
     /// Processes any remaining buffered samples (padding if necessary),
-    /// finalizes the WebM container, and returns the complete file data.
-    pub fn finalize(mut self) -> Result<Vec<u8>, Box<dyn Error>> {
+    /// finalizes the WebM container, and returns the complete file data
+    /// together with its duration.
+    pub fn finalize(mut self) -> Result<EncodedAudio, Box<dyn Error>> {
         println!("[AudioStreamProcessor] Finalizing...");
         println!("[AudioStreamProcessor] Summary before final chunk:");
         println!("  - Total samples received: {}", self.samples_received);
@@ -235,11 +244,15 @@ impl AudioStreamProcessor {
             writer_ts_ms, duration_by_samples_ms
         );
         // Finalize WebM
+        let duration_secs = self.webm_writer.duration_secs();
         let webm_data = self.webm_writer.finalize()?;
         
-        println!("[AudioStreamProcessor] WebM finalized: {} bytes", webm_data.len());
+        println!("[AudioStreamProcessor] WebM finalized: {} bytes, {:.2} s", webm_data.len(), duration_secs);
 
-        Ok(webm_data)
+        Ok(EncodedAudio {
+            data: webm_data,
+            duration_secs,
+        })
     }
 
     /// Get processing statistics
@@ -0,0 +1,260 @@
+//! Rewrite prompt templates
+//!
+//! A template marks where values go with `{{name}}`, e.g.
+//! `Fix this dictation: {{transcript}}`. Single braces are plain text, so
+//! prompts may contain JSON or code. Values are inserted as-is and never
+//! parsed again, so a transcript containing braces cannot inject variables.
+
+use std::fmt;
+
+pub const TRANSCRIPT: &str = "transcript";
+pub const DATE: &str = "date";
+pub const PREVIOUS_TRANSCRIPT: &str = "previous_transcript";
+pub const CLIPBOARD: &str = "clipboard";
+pub const LANGUAGE: &str = "language";
+
+/// All variable names a template may use
+pub const VARIABLES: &[&str] = &[TRANSCRIPT, DATE, PREVIOUS_TRANSCRIPT, CLIPBOARD, LANGUAGE];
+
+/// Placeholder of the pre-template prompt format, replaced by `{{transcript}}`
+const LEGACY_PLACEHOLDER: &str = "{}";
+
+/// Why a template was rejected, and where
+#[derive(Debug, Clone, PartialEq)]
+pub struct TemplateError {
+    /// 1-based line and column of the offending placeholder, if there is one
+    pub location: Option<(usize, usize)>,
+    pub message: String,
+}
+
+impl TemplateError {
+    fn at(template: &str, offset: usize, message: String) -> Self {
+        let before = &template[..offset];
+        let line = before.matches('\n').count() + 1;
+        let column = before.rsplit('\n').next().unwrap_or("").chars().count() + 1;
+        TemplateError {
+            location: Some((line, column)),
+            message,
+        }
+    }
+}
+
+impl fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.location {
+            Some((line, column)) => write!(f, "line {}, column {}: {}", line, column, self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+/// Values for one rendering; each is empty when unknown
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Variables {
+    pub transcript: String,
+    /// Today's date as YYYY-MM-DD, in local time
+    pub date: String,
+    /// Text of the most recent history entry
+    pub previous_transcript: String,
+    /// Clipboard text when the recording started
+    pub clipboard: String,
+    /// Configured dictation language code, empty when auto-detected
+    pub language: String,
+}
+
+impl Variables {
+    fn get(&self, name: &str) -> &str {
+        match name {
+            TRANSCRIPT => &self.transcript,
+            DATE => &self.date,
+            PREVIOUS_TRANSCRIPT => &self.previous_transcript,
+            CLIPBOARD => &self.clipboard,
+            LANGUAGE => &self.language,
+            _ => "",
+        }
+    }
+}
+
+enum Piece<'a> {
+    Text(&'a str),
+    Variable(&'static str),
+}
+
+fn parse(template: &str) -> Result<Vec<Piece<'_>>, TemplateError> {
+    let mut pieces = Vec::new();
+    let mut rest = 0;
+    while let Some(found) = template[rest..].find("{{") {
+        let open = rest + found;
+        let inner_start = open + 2;
+        let close = template[inner_start..]
+            .find("}}")
+            .map(|i| inner_start + i)
+            .filter(|&close| !template[inner_start..close].contains("{{"))
+            .ok_or_else(|| TemplateError::at(template, open, "'{{' is never closed with '}}'".to_string()))?;
+        let name = template[inner_start..close].trim();
+        let variable = VARIABLES.iter().find(|v| **v == name).ok_or_else(|| {
+            let message = if name.is_empty() {
+                "empty placeholder '{{}}'".to_string()
+            } else {
+                format!("unknown variable '{}', expected one of: {}", name, VARIABLES.join(", "))
+            };
+            TemplateError::at(template, open, message)
+        })?;
+        if open > rest {
+            pieces.push(Piece::Text(&template[rest..open]));
+        }
+        pieces.push(Piece::Variable(variable));
+        rest = close + 2;
+    }
+    if rest < template.len() {
+        pieces.push(Piece::Text(&template[rest..]));
+    }
+    Ok(pieces)
+}
+
+/// Check a rewrite prompt before it is saved
+///
+/// Every placeholder must name a known variable, and the transcript must be
+/// used somewhere.
+pub fn validate(template: &str) -> Result<(), TemplateError> {
+    if !uses_checked(template, TRANSCRIPT)? {
+        return Err(TemplateError {
+            location: None,
+            message: "the prompt never uses {{transcript}}".to_string(),
+        });
+    }
+    Ok(())
+}
+
+fn uses_checked(template: &str, variable: &str) -> Result<bool, TemplateError> {
+    Ok(parse(template)?
+        .iter()
+        .any(|piece| matches!(piece, Piece::Variable(name) if *name == variable)))
+}
+
+/// Whether a valid template uses `variable`, so costly values such as the
+/// clipboard are only read when needed
+pub fn uses(template: &str, variable: &str) -> bool {
+    uses_checked(template, variable).unwrap_or(false)
+}
+
+/// Convert a prompt in the old format, where `{}` marked the transcript
+///
+/// Returns `None` if the prompt already uses template variables or has no
+/// `{}` to convert.
+pub fn migrate_legacy(template: &str) -> Option<String> {
+    let has_variables = parse(template).map_or(true, |pieces| {
+        pieces.iter().any(|piece| matches!(piece, Piece::Variable(_)))
+    });
+    if has_variables || !template.contains(LEGACY_PLACEHOLDER) {
+        return None;
+    }
+    Some(template.replace(LEGACY_PLACEHOLDER, &format!("{{{{{}}}}}", TRANSCRIPT)))
+}
+
+/// Fill in a template's variables
+pub fn render(template: &str, variables: &Variables) -> Result<String, TemplateError> {
+    let mut rendered = String::with_capacity(template.len() + variables.transcript.len());
+    for piece in parse(template)? {
+        match piece {
+            Piece::Text(text) => rendered.push_str(text),
+            Piece::Variable(name) => rendered.push_str(variables.get(name)),
+        }
+    }
+    Ok(rendered)
+}
+
+/// Value of `date` right now, in the system time zone
+pub fn today() -> String {
+    format_date(chrono::Local::now())
+}
+
+fn format_date<Tz: chrono::TimeZone>(time: chrono::DateTime<Tz>) -> String
+where
+    Tz::Offset: fmt::Display,
+{
+    time.format("%Y-%m-%d").to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn variables() -> Variables {
+        Variables {
+            transcript: "hello {{date}} {}".to_string(),
+            date: "2024-05-01".to_string(),
+            previous_transcript: "earlier".to_string(),
+            clipboard: "copied".to_string(),
+            language: "en".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_render() {
+        let rendered = render("On {{date}} ({{ language }}): {{transcript}}", &variables()).unwrap();
+        // The transcript's braces are inserted literally
+        assert_eq!(rendered, "On 2024-05-01 (en): hello {{date}} {}");
+
+        let json = render(r#"Reply as {"text": "..."}}: {{transcript}}"#, &variables()).unwrap();
+        assert_eq!(json, r#"Reply as {"text": "..."}}: hello {{date}} {}"#);
+
+        let context = render("{{previous_transcript}}|{{clipboard}}|{{transcript}}", &Variables::default()).unwrap();
+        assert_eq!(context, "||");
+    }
+
+    #[test]
+    fn test_format_date() {
+        use chrono::{FixedOffset, TimeZone};
+        let date = |ts, offset| format_date(FixedOffset::east_opt(offset).unwrap().timestamp_opt(ts, 0).unwrap());
+        // 2024-02-29 23:30 UTC is already March 1st an hour east of UTC
+        let ts = 1_709_249_400;
+        assert_eq!(date(ts, 0), "2024-02-29");
+        assert_eq!(date(ts, 3600), "2024-03-01");
+        // and 00:30 UTC is still the previous day two hours west of it
+        assert_eq!(date(ts + 3600, -7200), "2024-02-29");
+    }
+
+    #[test]
+    fn test_validate_errors() {
+        assert!(validate("Fix: {{transcript}}").is_ok());
+
+        let unknown = validate("Fix:\n  {{transcrpt}}").unwrap_err();
+        assert_eq!(unknown.location, Some((2, 3)));
+        assert!(unknown.message.contains("unknown variable 'transcrpt'"));
+        assert_eq!(
+            unknown.to_string(),
+            format!("line 2, column 3: unknown variable 'transcrpt', expected one of: {}", VARIABLES.join(", "))
+        );
+
+        let unclosed = validate("{{transcript}} and {{date").unwrap_err();
+        assert_eq!(unclosed.location, Some((1, 20)));
+        assert!(unclosed.message.contains("never closed"));
+
+        let nested = validate("{{ {{transcript}}").unwrap_err();
+        assert_eq!(nested.location, Some((1, 1)));
+
+        assert!(validate("{{}} {{transcript}}").unwrap_err().message.contains("empty placeholder"));
+
+        let missing = validate("Fix: {}").unwrap_err();
+        assert_eq!(missing.location, None);
+        assert!(missing.message.contains("{{transcript}}"));
+    }
+
+    #[test]
+    fn test_uses() {
+        assert!(uses("{{clipboard}} {{transcript}}", CLIPBOARD));
+        assert!(!uses("{{transcript}}", CLIPBOARD));
+        assert!(!uses("{{clipboard", CLIPBOARD));
+    }
+
+    #[test]
+    fn test_migrate_legacy() {
+        assert_eq!(
+            migrate_legacy("Original text: {}\nReturn it."),
+            Some("Original text: {{transcript}}\nReturn it.".to_string())
+        );
+        assert_eq!(migrate_legacy("Original text: {{transcript}} {}"), None);
+        assert_eq!(migrate_legacy("No placeholder"), None);
+    }
+}
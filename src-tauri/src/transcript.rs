@@ -0,0 +1,277 @@
+//! Transcription results with optional segment and word timing
+//!
+//! Times are in seconds from the start of the recording. Backends that do not
+//! report timing return a transcript with only `text` set. Diarized transcripts
+//! also carry speaker turns, and their text has one labelled line per turn.
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct TranscriptSegment {
+    pub start: f64,
+    pub end: f64,
+    pub text: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct TranscriptWord {
+    pub start: f64,
+    pub end: f64,
+    pub word: String,
+}
+
+/// Consecutive speech by one speaker
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct SpeakerTurn {
+    pub speaker: String,
+    pub start: f64,
+    pub end: f64,
+    pub text: String,
+}
+
+/// The rewrite that produced a transcript's final text
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct RewriteRecord {
+    /// Prompt used, `None` for a translation without a rewrite prompt
+    pub prompt_id: Option<String>,
+    /// "provider/model" label of the rewrite
+    pub model: String,
+    /// Text the rewrite returned
+    pub text: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct Transcript {
+    pub text: String,
+    #[serde(default)]
+    pub segments: Vec<TranscriptSegment>,
+    #[serde(default)]
+    pub words: Vec<TranscriptWord>,
+    #[serde(default)]
+    pub turns: Vec<SpeakerTurn>,
+    /// Backend and model that produced the text, set by the flow. Pieces
+    /// transcribed by different models list each of them.
+    #[serde(default)]
+    pub model: String,
+    /// Text as transcribed, before dictation commands and rewriting; set by
+    /// the flow
+    #[serde(default)]
+    pub raw_text: String,
+    /// Rewrite applied to the text, set by the flow
+    #[serde(default)]
+    pub rewrite: Option<RewriteRecord>,
+}
+
+impl Transcript {
+    /// Transcript of speaker turns, merging consecutive turns of the same speaker
+    pub fn from_turns(turns: impl IntoIterator<Item = SpeakerTurn>) -> Self {
+        let mut merged: Vec<SpeakerTurn> = Vec::new();
+        for turn in turns {
+            let text = turn.text.trim();
+            if text.is_empty() {
+                continue;
+            }
+            match merged.last_mut() {
+                Some(last) if last.speaker == turn.speaker => {
+                    last.text.push(' ');
+                    last.text.push_str(text);
+                    last.end = turn.end;
+                }
+                _ => merged.push(SpeakerTurn {
+                    text: text.to_string(),
+                    ..turn
+                }),
+            }
+        }
+        let text = merged
+            .iter()
+            .map(|turn| format!("{}: {}", turn.speaker, turn.text))
+            .collect::<Vec<_>>()
+            .join("\n");
+        Transcript {
+            text,
+            turns: merged,
+            ..Default::default()
+        }
+    }
+
+    /// Shift all times by `secs`, for audio that was cut from a longer recording
+    pub fn offset(mut self, secs: f64) -> Self {
+        for segment in &mut self.segments {
+            segment.start += secs;
+            segment.end += secs;
+        }
+        for word in &mut self.words {
+            word.start += secs;
+            word.end += secs;
+        }
+        for turn in &mut self.turns {
+            turn.start += secs;
+            turn.end += secs;
+        }
+        self
+    }
+
+    /// Join transcripts of consecutive pieces of a recording, in order
+    ///
+    /// Texts are trimmed and joined with single spaces; empty pieces are skipped.
+    /// Each distinct model is listed once. Speaker turns are joined as turns;
+    /// pieces are diarized separately, so labels may not match across them.
+    pub fn join(parts: impl IntoIterator<Item = Transcript>) -> Self {
+        let parts: Vec<Transcript> = parts.into_iter().collect();
+        if parts.iter().any(|part| !part.turns.is_empty()) {
+            let mut joined = Transcript::from_turns(parts.iter().flat_map(|part| part.turns.clone()));
+            for part in parts {
+                joined.segments.extend(part.segments);
+                joined.words.extend(part.words);
+                joined.add_model(&part.model);
+            }
+            return joined;
+        }
+
+        let mut joined = Transcript::default();
+        for part in parts {
+            let text = part.text.trim();
+            if !text.is_empty() {
+                if !joined.text.is_empty() {
+                    joined.text.push(' ');
+                }
+                joined.text.push_str(text);
+            }
+            joined.segments.extend(part.segments);
+            joined.words.extend(part.words);
+            joined.add_model(&part.model);
+        }
+        joined
+    }
+
+    fn add_model(&mut self, model: &str) {
+        if !model.is_empty() && !self.model.split(", ").any(|m| m == model) {
+            if !self.model.is_empty() {
+                self.model.push_str(", ");
+            }
+            self.model.push_str(model);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plain(text: &str) -> Transcript {
+        Transcript {
+            text: text.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn timed(text: &str, start: f64, end: f64) -> Transcript {
+        Transcript {
+            text: text.to_string(),
+            segments: vec![TranscriptSegment { start, end, text: text.to_string() }],
+            words: text
+                .split_whitespace()
+                .enumerate()
+                .map(|(i, word)| TranscriptWord {
+                    start: start + i as f64 * 0.5,
+                    end: start + i as f64 * 0.5 + 0.4,
+                    word: word.to_string(),
+                })
+                .collect(),
+            turns: Vec::new(),
+            model: String::new(),
+            raw_text: String::new(),
+            rewrite: None,
+        }
+    }
+
+    fn turn(speaker: &str, start: f64, text: &str) -> SpeakerTurn {
+        SpeakerTurn {
+            speaker: speaker.to_string(),
+            start,
+            end: start + 1.0,
+            text: text.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_offset() {
+        let transcript = timed("hello world", 0.0, 1.0).offset(10.0);
+        assert_eq!(transcript.segments[0].start, 10.0);
+        assert_eq!(transcript.segments[0].end, 11.0);
+        assert_eq!(transcript.words[1].start, 10.5);
+    }
+
+    #[test]
+    fn test_join() {
+        let joined = Transcript::join(vec![
+            timed(" hello world ", 0.0, 1.0),
+            plain("   "),
+            timed("again", 5.0, 6.0),
+        ]);
+        assert_eq!(joined.text, "hello world again");
+        assert_eq!(joined.segments.len(), 2);
+        assert_eq!(joined.words.len(), 3);
+
+        let joined = Transcript::join(vec![plain("a"), plain("b")]);
+        assert_eq!(joined.text, "a b");
+        assert!(joined.segments.is_empty() && joined.words.is_empty());
+    }
+
+    #[test]
+    fn test_from_turns() {
+        let transcript = Transcript::from_turns(vec![
+            turn("A", 0.0, " Hi."),
+            turn("A", 1.0, "How are you?"),
+            turn("B", 2.0, "Fine."),
+            turn("A", 3.0, "  "),
+        ]);
+        assert_eq!(transcript.text, "A: Hi. How are you?\nB: Fine.");
+        assert_eq!(transcript.turns.len(), 2);
+        assert_eq!(transcript.turns[0].end, 2.0);
+    }
+
+    #[test]
+    fn test_join_turns() {
+        let first = Transcript::from_turns(vec![turn("A", 0.0, "one"), turn("B", 1.0, "two")]);
+        let second = Transcript::from_turns(vec![turn("B", 0.0, "three")]).offset(10.0);
+        let joined = Transcript::join(vec![first, second]);
+        assert_eq!(joined.text, "A: one\nB: two three");
+        assert_eq!(joined.turns[1].end, 11.0);
+    }
+
+    #[test]
+    fn test_join_models() {
+        let by = |text: &str, model: &str| Transcript {
+            model: model.to_string(),
+            ..plain(text)
+        };
+        let joined = Transcript::join(vec![
+            by("a", "openai/gpt-4o-transcribe"),
+            by("b", "openai/whisper-1"),
+            by("c", "openai/gpt-4o-transcribe"),
+        ]);
+        assert_eq!(joined.model, "openai/gpt-4o-transcribe, openai/whisper-1");
+    }
+
+    #[test]
+    fn test_parse_verbose_json() {
+        // Shape of an OpenAI verbose_json response, extra fields ignored
+        let json = r#"{
+            "task": "transcribe",
+            "language": "english",
+            "duration": 1.2,
+            "text": "Hi there.",
+            "segments": [{"id": 0, "seek": 0, "start": 0.0, "end": 1.2, "text": " Hi there.", "tokens": [1, 2]}],
+            "words": [{"word": "Hi", "start": 0.1, "end": 0.4}, {"word": "there", "start": 0.5, "end": 1.0}]
+        }"#;
+        let transcript: Transcript = serde_json::from_str(json).unwrap();
+        assert_eq!(transcript.text, "Hi there.");
+        assert_eq!(transcript.segments.len(), 1);
+        assert_eq!(transcript.words[1].word, "there");
+
+        let text_only: Transcript = serde_json::from_str(r#"{"text": "Hi"}"#).unwrap();
+        assert_eq!(text_only, plain("Hi"));
+    }
+}
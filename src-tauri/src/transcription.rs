@@ -0,0 +1,677 @@
+//! Transcription backends
+//!
+//! A `TranscriptionBackend` turns a finished recording (WebM/Opus) into text.
+//! `Flow` only talks to the trait, so new providers can be added here without
+//! touching the recording pipeline, and tests can substitute a fake backend.
+
+use crate::flow::{AudioError, ErrorKind};
+use crate::transcript::{SpeakerTurn, Transcript};
+use crate::webm::WebmWriter;
+use reqwest::header::{HeaderName, HeaderValue};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::env;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Identifier of the OpenAI transcription backend
+pub const BACKEND_OPENAI: &str = "openai";
+
+/// Identifier of the backend for self-hosted servers exposing the OpenAI API shape
+pub const BACKEND_OPENAI_COMPATIBLE: &str = "openai_compatible";
+
+/// Identifier of the offline whisper.cpp backend
+pub const BACKEND_LOCAL: &str = "local";
+
+/// All backend identifiers accepted in settings
+pub const AVAILABLE_BACKENDS: &[&str] = &[BACKEND_OPENAI, BACKEND_OPENAI_COMPATIBLE, BACKEND_LOCAL];
+
+pub const OPENAI_BASE_URL: &str = "https://api.openai.com/v1";
+
+/// Per-backend connection settings
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct BackendSettings {
+    /// API base URL, e.g. `http://localhost:8000/v1`. Empty uses the backend default.
+    #[serde(default)]
+    pub base_url: String,
+    /// API key for this backend. The OpenAI backend falls back to the global key.
+    #[serde(default)]
+    pub api_key: String,
+    /// Additional HTTP headers sent with every request
+    #[serde(default)]
+    pub extra_headers: BTreeMap<String, String>,
+}
+
+/// Check that a backend's settings are well-formed before they are saved
+pub fn validate_backend_settings(settings: &BackendSettings) -> Result<(), String> {
+    let base_url = settings.base_url.trim();
+    if !base_url.is_empty() {
+        let url = reqwest::Url::parse(base_url)
+            .map_err(|e| format!("Invalid base URL '{}': {}", base_url, e))?;
+        if url.scheme() != "http" && url.scheme() != "https" {
+            return Err(format!("Base URL must use http or https: {}", base_url));
+        }
+    }
+
+    for (name, value) in &settings.extra_headers {
+        HeaderName::from_bytes(name.as_bytes())
+            .map_err(|_| format!("Invalid header name: '{}'", name))?;
+        HeaderValue::from_str(value)
+            .map_err(|_| format!("Invalid value for header '{}'", name))?;
+    }
+
+    Ok(())
+}
+
+/// A short silent WebM clip used to probe a backend without recording
+pub fn silent_test_clip() -> Result<Vec<u8>, String> {
+    let mut writer = WebmWriter::new(64000).map_err(|e| format!("Failed to create test clip: {}", e))?;
+    writer
+        .add_samples_f32(&[0.0; 24000]) // 0.5 s at 48 kHz
+        .map_err(|e| format!("Failed to encode test clip: {}", e))?;
+    writer.finalize().map_err(|e| format!("Failed to finalize test clip: {}", e))
+}
+
+/// Language setting value that leaves detection to the backend
+pub const LANGUAGE_AUTO: &str = "auto";
+
+/// Accepts "auto" or an ISO-639-1 code such as "en"
+pub fn validate_language(language: &str) -> Result<(), String> {
+    if language == LANGUAGE_AUTO
+        || (language.len() == 2 && language.chars().all(|c| c.is_ascii_lowercase()))
+    {
+        Ok(())
+    } else {
+        Err(format!(
+            "Invalid language: '{}'. Use \"{}\" or a two-letter ISO-639-1 code such as \"en\"",
+            language, LANGUAGE_AUTO
+        ))
+    }
+}
+
+/// Language hint to send for a language setting; `None` means auto-detect
+pub fn language_hint(language: &str) -> Option<String> {
+    Some(language.to_string()).filter(|l| l != LANGUAGE_AUTO && !l.is_empty())
+}
+
+/// Transcription mode that returns plain text
+pub const TRANSCRIPTION_MODE_TEXT: &str = "text";
+
+/// Transcription mode that also returns segment and word timestamps
+pub const TRANSCRIPTION_MODE_TIMESTAMPS: &str = "timestamps";
+
+/// Transcription mode that labels who is speaking; needs a diarizing model
+/// such as gpt-4o-transcribe-diarize
+pub const TRANSCRIPTION_MODE_DIARIZED: &str = "diarized";
+
+/// All transcription modes accepted in settings
+pub const AVAILABLE_TRANSCRIPTION_MODES: &[&str] =
+    &[TRANSCRIPTION_MODE_TEXT, TRANSCRIPTION_MODE_TIMESTAMPS, TRANSCRIPTION_MODE_DIARIZED];
+
+/// Whether `model` returns `verbose_json` with timestamps. The gpt-4o
+/// transcription models only answer with plain `json`.
+pub fn supports_timestamps(model: &str) -> bool {
+    !model.starts_with("gpt-4o")
+}
+
+/// Whether `model` labels speakers, e.g. gpt-4o-transcribe-diarize; other
+/// models reject `diarized_json`
+pub fn supports_diarization(model: &str) -> bool {
+    model.contains("diarize")
+}
+
+/// Whether `model` is served by `/audio/translations`, which only has
+/// whisper models; local transcription always can translate
+pub fn supports_translation(backend: &str, model: &str) -> bool {
+    backend == BACKEND_LOCAL || !model.starts_with("gpt-4o")
+}
+
+/// Check that `backend` and `model` support the transcription `mode`, and
+/// translating into English if `translate` is set
+pub fn validate_mode(backend: &str, model: &str, mode: &str, translate: bool) -> Result<(), String> {
+    if !AVAILABLE_TRANSCRIPTION_MODES.contains(&mode) {
+        return Err(format!("Invalid transcription mode: {}", mode));
+    }
+    if mode == TRANSCRIPTION_MODE_DIARIZED && backend == BACKEND_LOCAL {
+        return Err("The local backend cannot label speakers".to_string());
+    }
+    if mode == TRANSCRIPTION_MODE_DIARIZED && !supports_diarization(model) {
+        return Err(format!("Model '{}' cannot label speakers", model));
+    }
+    if translate && mode == TRANSCRIPTION_MODE_DIARIZED {
+        return Err("Speaker labels cannot be combined with translation".to_string());
+    }
+    if translate && !supports_translation(backend, model) {
+        return Err(format!("Model '{}' cannot translate; use whisper-1", model));
+    }
+    if mode == TRANSCRIPTION_MODE_TIMESTAMPS && !supports_timestamps(model) {
+        return Err(format!("Model '{}' does not return timestamps", model));
+    }
+    Ok(())
+}
+
+/// A single transcription request
+#[derive(Debug, Clone)]
+pub struct TranscriptionRequest {
+    /// Audio data (WebM format)
+    pub audio_data: Vec<u8>,
+    /// Model name understood by the backend
+    pub model: String,
+    /// ISO-639-1 language of the audio, or `None` to auto-detect
+    pub language: Option<String>,
+    /// Text that guides spelling and style, e.g. names and jargon
+    pub prompt: Option<String>,
+    /// Request segment and word timestamps along with the text
+    pub timestamps: bool,
+    /// Translate the speech into English instead of transcribing it
+    pub translate: bool,
+    /// Split the text into speaker turns
+    pub diarize: bool,
+}
+
+pub type TranscriptionFuture<'a> = Pin<Box<dyn Future<Output = Result<Transcript, AudioError>> + Send + 'a>>;
+
+/// A service that converts recorded audio into text
+pub trait TranscriptionBackend: Send + Sync {
+    /// Identifier used in settings and log output
+    fn name(&self) -> &str;
+
+    /// Returns an error if the backend cannot use the given model
+    fn validate_model(&self, model: &str) -> Result<(), String>;
+
+    /// Transcribe the audio in `request`. Cancellation is handled by the caller
+    /// dropping the returned future.
+    fn transcribe(&self, request: TranscriptionRequest) -> TranscriptionFuture<'_>;
+
+    /// How many chunks of a long recording may be transcribed at once
+    fn max_concurrent_requests(&self) -> usize {
+        4
+    }
+
+    /// Check that the backend is reachable and accepts `model` by transcribing
+    /// a short silent clip
+    fn test_connection(&self, model: String) -> TranscriptionFuture<'_> {
+        Box::pin(async move {
+            let audio_data = silent_test_clip().map_err(|message| AudioError::new(ErrorKind::Audio, message))?;
+            self.transcribe(TranscriptionRequest {
+                audio_data,
+                model,
+                language: None,
+                prompt: None,
+                timestamps: false,
+                translate: false,
+                diarize: false,
+            })
+            .await
+        })
+    }
+}
+
+/// Whether `base_url` is empty or the OpenAI API, i.e. the global key may be sent there
+fn is_official_url(base_url: &str) -> bool {
+    let url = base_url.trim().trim_end_matches('/');
+    url.is_empty() || url == OPENAI_BASE_URL
+}
+
+/// Create the backend registered under `name`
+///
+/// `openai_api_key` is the global OpenAI key; it is never sent to other servers.
+pub fn create_backend(
+    name: &str,
+    openai_api_key: String,
+    settings: &BackendSettings,
+) -> Result<Arc<dyn TranscriptionBackend>, String> {
+    validate_backend_settings(settings)?;
+    match name {
+        BACKEND_OPENAI => {
+            let api_key = if !settings.api_key.trim().is_empty() {
+                settings.api_key.clone()
+            } else if is_official_url(&settings.base_url) {
+                openai_api_key
+            } else {
+                return Err(format!("Backend '{}' requires its own API key for {}", name, settings.base_url.trim()));
+            };
+            Ok(Arc::new(OpenAiBackend::new(BACKEND_OPENAI, api_key, true, settings)))
+        }
+        BACKEND_OPENAI_COMPATIBLE if settings.base_url.trim().is_empty() => {
+            Err(format!("Backend '{}' requires a base URL", name))
+        }
+        // Self-hosted servers often run without authentication
+        BACKEND_OPENAI_COMPATIBLE => Ok(Arc::new(OpenAiBackend::new(
+            BACKEND_OPENAI_COMPATIBLE,
+            settings.api_key.clone(),
+            false,
+            settings,
+        ))),
+        BACKEND_LOCAL => create_local_backend(),
+        _ => Err(format!("Unknown transcription backend: {}", name)),
+    }
+}
+
+/// A backend together with the model to request from it
+#[derive(Clone)]
+pub struct TranscriptionTarget {
+    pub backend: Arc<dyn TranscriptionBackend>,
+    pub model: String,
+}
+
+impl TranscriptionTarget {
+    /// Create the backend registered under `name` and check that it accepts `model`
+    pub fn create(
+        name: &str,
+        model: &str,
+        openai_api_key: String,
+        settings: &BackendSettings,
+    ) -> Result<Self, String> {
+        let backend = create_backend(name, openai_api_key, settings)?;
+        backend.validate_model(model)?;
+        Ok(Self {
+            backend,
+            model: model.to_string(),
+        })
+    }
+
+    /// "backend/model", as reported with results and stored in history
+    pub fn label(&self) -> String {
+        format!("{}/{}", self.backend.name(), self.model)
+    }
+}
+
+/// A backend and model to try, in order, when the selected ones fail
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct FallbackModel {
+    pub backend: String,
+    pub model: String,
+}
+
+/// Check that `fallback` can stand in for the `primary` backend in `mode`.
+/// A local primary keeps audio on this device, so it only falls back to
+/// servers when `allow_remote` is set.
+pub fn validate_fallback(
+    fallback: &FallbackModel,
+    primary: &str,
+    mode: &str,
+    translate: bool,
+    allow_remote: bool,
+) -> Result<(), String> {
+    if primary == BACKEND_LOCAL && fallback.backend != BACKEND_LOCAL && !allow_remote {
+        return Err("would upload audio that the local backend keeps on this device".to_string());
+    }
+    validate_mode(&fallback.backend, &fallback.model, mode, translate)
+}
+
+#[cfg(feature = "local-whisper")]
+fn create_local_backend() -> Result<Arc<dyn TranscriptionBackend>, String> {
+    let models_dir = crate::flow_manager::FlowManager::get_config_dir()
+        .ok_or_else(|| "Could not determine config directory".to_string())?
+        .join("models");
+    Ok(Arc::new(crate::local_whisper::LocalWhisperBackend::new(models_dir)))
+}
+
+#[cfg(not(feature = "local-whisper"))]
+fn create_local_backend() -> Result<Arc<dyn TranscriptionBackend>, String> {
+    Err("This build does not include local transcription (enable the `local-whisper` feature)".to_string())
+}
+
+/// Whether the backend cannot work without an API key
+pub fn requires_api_key(name: &str) -> bool {
+    name == BACKEND_OPENAI
+}
+
+/// Resolve the API key to use: the configured one, or `OPENAI_API_KEY` if empty
+pub fn resolve_api_key(api_key: &str) -> Result<String, AudioError> {
+    let api_key = if api_key.trim().is_empty() {
+        env::var("OPENAI_API_KEY")
+            .map_err(|_| AudioError::new(ErrorKind::Config, "OPENAI_API_KEY environment variable not set"))?
+    } else {
+        api_key.to_string()
+    };
+
+    if api_key.trim().is_empty() {
+        return Err(AudioError::new(ErrorKind::Config, "OpenAI API key is empty"));
+    }
+
+    Ok(api_key)
+}
+
+/// Backend for the OpenAI `/audio/transcriptions` endpoint and servers mimicking it
+pub struct OpenAiBackend {
+    name: &'static str,
+    api_key: String,
+    api_key_required: bool,
+    base_url: String,
+    extra_headers: BTreeMap<String, String>,
+}
+
+impl OpenAiBackend {
+    pub fn new(name: &'static str, api_key: String, api_key_required: bool, settings: &BackendSettings) -> Self {
+        let base_url = match settings.base_url.trim() {
+            "" => OPENAI_BASE_URL.to_string(),
+            url => url.trim_end_matches('/').to_string(),
+        };
+        Self {
+            name,
+            api_key,
+            api_key_required,
+            base_url,
+            extra_headers: settings.extra_headers.clone(),
+        }
+    }
+
+    async fn transcribe_impl(&self, request: TranscriptionRequest) -> Result<Transcript, AudioError> {
+        let api_key = if self.api_key_required {
+            Some(resolve_api_key(&self.api_key)?)
+        } else {
+            Some(self.api_key.clone()).filter(|key| !key.trim().is_empty())
+        };
+
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(60))
+            .build()
+            .map_err(|e| AudioError::request("Failed to create HTTP client", e))?;
+
+        // Fallback models without timestamps still return the text
+        let timestamps = request.timestamps && supports_timestamps(&request.model);
+        let mut form = reqwest::multipart::Form::new()
+            .part(
+                "file",
+                reqwest::multipart::Part::bytes(request.audio_data)
+                    .file_name("audio.webm")
+                    .mime_str("audio/webm")
+                    .map_err(|e| AudioError::request("Failed to create file part", e))?,
+            )
+            .text("model", request.model);
+        // Translations are always into English and have no language parameter
+        if let Some(language) = request.language.filter(|_| !request.translate) {
+            form = form.text("language", language);
+        }
+        // Diarizing models reject prompts
+        if let Some(prompt) = request.prompt.filter(|_| !request.diarize) {
+            form = form.text("prompt", prompt);
+        }
+        if request.diarize {
+            // Recordings over 30 s must be split server-side
+            form = form
+                .text("response_format", "diarized_json")
+                .text("chunking_strategy", "auto");
+        } else if timestamps {
+            form = form.text("response_format", "verbose_json");
+            // Translations return segments but do not support word timing
+            if !request.translate {
+                form = form
+                    .text("timestamp_granularities[]", "segment")
+                    .text("timestamp_granularities[]", "word");
+            }
+        }
+
+        let endpoint = if request.translate { "translations" } else { "transcriptions" };
+        let url = format!("{}/audio/{}", self.base_url, endpoint);
+        println!("Sending transcription request to {}...", url);
+
+        let mut request_builder = client.post(&url);
+        if let Some(api_key) = api_key {
+            request_builder = request_builder.header("Authorization", format!("Bearer {}", api_key));
+        }
+        for (name, value) in &self.extra_headers {
+            request_builder = request_builder.header(name.as_str(), value.as_str());
+        }
+
+        let response = request_builder
+            .multipart(form)
+            .send()
+            .await
+            .map_err(|e| AudioError::request("Failed to send request", e))?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response
+                .text()
+                .await
+                .unwrap_or_else(|_| "Unknown error".to_string());
+            return Err(AudioError::http_status(
+                status,
+                format!("Transcription API error {}: {}", status, error_text),
+            ));
+        }
+
+        if request.diarize {
+            let diarized: DiarizedResponse = response
+                .json()
+                .await
+                .map_err(|e| AudioError::request("Failed to parse response", e))?;
+            return Ok(diarized.into());
+        }
+
+        // Plain `json` responses only carry `text`; `verbose_json` adds segments and words
+        let mut transcript: Transcript = response
+            .json()
+            .await
+            .map_err(|e| AudioError::request("Failed to parse response", e))?;
+        for segment in &mut transcript.segments {
+            segment.text = segment.text.trim().to_string();
+        }
+
+        Ok(transcript)
+    }
+}
+
+impl TranscriptionBackend for OpenAiBackend {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn validate_model(&self, model: &str) -> Result<(), String> {
+        // Model names are free-form; the server reports unknown ones
+        if model.trim().is_empty() || model.chars().any(char::is_whitespace) {
+            Err(format!("Invalid model name: '{}'", model))
+        } else {
+            Ok(())
+        }
+    }
+
+    fn transcribe(&self, request: TranscriptionRequest) -> TranscriptionFuture<'_> {
+        Box::pin(self.transcribe_impl(request))
+    }
+}
+
+/// Body of a `diarized_json` response
+#[derive(Deserialize)]
+struct DiarizedResponse {
+    segments: Vec<SpeakerTurn>,
+}
+
+impl From<DiarizedResponse> for Transcript {
+    fn from(response: DiarizedResponse) -> Self {
+        Transcript::from_turns(response.segments)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct FakeBackend;
+
+    impl TranscriptionBackend for FakeBackend {
+        fn name(&self) -> &str {
+            "fake"
+        }
+
+        fn validate_model(&self, _model: &str) -> Result<(), String> {
+            Ok(())
+        }
+
+        fn transcribe(&self, request: TranscriptionRequest) -> TranscriptionFuture<'_> {
+            Box::pin(async move {
+                Ok(Transcript {
+                    text: format!("{} bytes with {}", request.audio_data.len(), request.model),
+                    ..Default::default()
+                })
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fake_backend_through_trait_object() {
+        let backend: Arc<dyn TranscriptionBackend> = Arc::new(FakeBackend);
+        let transcript = backend
+            .transcribe(TranscriptionRequest {
+                audio_data: vec![0; 4],
+                model: "m".to_string(),
+                language: None,
+                prompt: None,
+                timestamps: false,
+                translate: false,
+                diarize: false,
+            })
+            .await
+            .unwrap();
+        assert_eq!(transcript.text, "4 bytes with m");
+    }
+
+    #[test]
+    fn test_create_backend() {
+        let settings = BackendSettings::default();
+        let backend = create_backend(BACKEND_OPENAI, String::new(), &settings).unwrap();
+        assert_eq!(backend.name(), BACKEND_OPENAI);
+        assert!(create_backend("nope", String::new(), &settings).is_err());
+        // A compatible server has no default URL
+        assert!(create_backend(BACKEND_OPENAI_COMPATIBLE, String::new(), &settings).is_err());
+    }
+
+    #[test]
+    fn test_global_key_stays_with_openai() {
+        let official = BackendSettings {
+            base_url: "https://api.openai.com/v1/".to_string(),
+            ..Default::default()
+        };
+        assert!(create_backend(BACKEND_OPENAI, "sk-global".to_string(), &official).is_ok());
+
+        // A custom server needs its own key rather than the global one
+        let mut custom = BackendSettings {
+            base_url: "https://proxy.example.com/v1".to_string(),
+            ..Default::default()
+        };
+        assert!(create_backend(BACKEND_OPENAI, "sk-global".to_string(), &custom).is_err());
+        custom.api_key = "sk-proxy".to_string();
+        assert!(create_backend(BACKEND_OPENAI, "sk-global".to_string(), &custom).is_ok());
+    }
+
+    #[test]
+    fn test_parse_diarized_json() {
+        let json = r#"{
+            "task": "transcribe",
+            "duration": 4.0,
+            "text": "Hi. Hello there.",
+            "segments": [
+                {"type": "transcript.text.segment", "id": "seg_0", "start": 0.0, "end": 1.0, "text": " Hi.", "speaker": "A"},
+                {"type": "transcript.text.segment", "id": "seg_1", "start": 1.5, "end": 4.0, "text": "Hello there.", "speaker": "B"}
+            ]
+        }"#;
+        let transcript: Transcript = serde_json::from_str::<DiarizedResponse>(json).unwrap().into();
+        assert_eq!(transcript.text, "A: Hi.\nB: Hello there.");
+        assert_eq!(transcript.turns[1].start, 1.5);
+    }
+
+    #[test]
+    fn test_create_target() {
+        let settings = BackendSettings::default();
+        let target = TranscriptionTarget::create(BACKEND_OPENAI, "whisper-1", String::new(), &settings).unwrap();
+        assert_eq!(target.label(), "openai/whisper-1");
+        assert!(TranscriptionTarget::create(BACKEND_OPENAI, "", String::new(), &settings).is_err());
+        assert!(TranscriptionTarget::create("nope", "whisper-1", String::new(), &settings).is_err());
+    }
+
+    #[test]
+    fn test_model_validation() {
+        let backend = OpenAiBackend::new(BACKEND_OPENAI, String::new(), true, &BackendSettings::default());
+        assert!(backend.validate_model("whisper-1").is_ok());
+        assert!(backend.validate_model("Systran/faster-whisper-large-v3").is_ok());
+        assert!(backend.validate_model("").is_err());
+        assert!(backend.validate_model("two words").is_err());
+    }
+
+    #[test]
+    fn test_validate_backend_settings() {
+        let mut settings = BackendSettings {
+            base_url: "http://localhost:8000/v1/".to_string(),
+            ..Default::default()
+        };
+        assert!(validate_backend_settings(&settings).is_ok());
+
+        settings.extra_headers.insert("X-Team".to_string(), "muse".to_string());
+        assert!(validate_backend_settings(&settings).is_ok());
+
+        settings.extra_headers.insert("Bad Header".to_string(), "x".to_string());
+        assert!(validate_backend_settings(&settings).is_err());
+
+        settings.extra_headers.clear();
+        settings.base_url = "ftp://example.com".to_string();
+        assert!(validate_backend_settings(&settings).is_err());
+
+        settings.base_url = "not a url".to_string();
+        assert!(validate_backend_settings(&settings).is_err());
+    }
+
+    #[test]
+    fn test_base_url_trailing_slash() {
+        let settings = BackendSettings {
+            base_url: "http://localhost:8000/v1/".to_string(),
+            ..Default::default()
+        };
+        let backend = OpenAiBackend::new(BACKEND_OPENAI_COMPATIBLE, String::new(), false, &settings);
+        assert_eq!(backend.base_url, "http://localhost:8000/v1");
+    }
+
+    #[test]
+    fn test_language() {
+        assert!(validate_language(LANGUAGE_AUTO).is_ok());
+        assert!(validate_language("en").is_ok());
+        assert!(validate_language("EN").is_err());
+        assert!(validate_language("english").is_err());
+        assert!(validate_language("").is_err());
+
+        assert_eq!(language_hint(LANGUAGE_AUTO), None);
+        assert_eq!(language_hint("de"), Some("de".to_string()));
+    }
+
+    #[test]
+    fn test_validate_mode() {
+        assert!(validate_mode(BACKEND_OPENAI, "gpt-4o-transcribe-diarize", TRANSCRIPTION_MODE_DIARIZED, false).is_ok());
+        assert!(validate_mode(BACKEND_LOCAL, "whisper-1", TRANSCRIPTION_MODE_TIMESTAMPS, false).is_ok());
+        assert!(validate_mode(BACKEND_LOCAL, "whisper-1", TRANSCRIPTION_MODE_DIARIZED, false).is_err());
+        assert!(validate_mode(BACKEND_OPENAI, "whisper-1", "subtitles", false).is_err());
+        assert!(validate_mode(BACKEND_OPENAI, "gpt-4o-transcribe", TRANSCRIPTION_MODE_TIMESTAMPS, false).is_err());
+        assert!(validate_mode(BACKEND_OPENAI, "gpt-4o-transcribe", TRANSCRIPTION_MODE_TEXT, false).is_ok());
+        assert!(validate_mode(BACKEND_OPENAI, "whisper-1", TRANSCRIPTION_MODE_DIARIZED, false).is_err());
+        assert!(validate_mode(BACKEND_OPENAI_COMPATIBLE, "gpt-4o-transcribe", TRANSCRIPTION_MODE_DIARIZED, false).is_err());
+    }
+
+    #[test]
+    fn test_validate_fallback() {
+        let fallback = |backend: &str, model: &str| FallbackModel {
+            backend: backend.to_string(),
+            model: model.to_string(),
+        };
+        let whisper = fallback(BACKEND_OPENAI, "whisper-1");
+        assert!(validate_fallback(&whisper, BACKEND_OPENAI, TRANSCRIPTION_MODE_TEXT, true, false).is_ok());
+        assert!(validate_fallback(&whisper, BACKEND_OPENAI, TRANSCRIPTION_MODE_DIARIZED, false, false).is_err());
+        // Audio only leaves the device from a local primary when allowed
+        assert!(validate_fallback(&whisper, BACKEND_LOCAL, TRANSCRIPTION_MODE_TEXT, false, false).is_err());
+        assert!(validate_fallback(&whisper, BACKEND_LOCAL, TRANSCRIPTION_MODE_TEXT, false, true).is_ok());
+        let local = fallback(BACKEND_LOCAL, "ggml-base");
+        assert!(validate_fallback(&local, BACKEND_LOCAL, TRANSCRIPTION_MODE_TEXT, false, false).is_ok());
+    }
+
+    #[test]
+    fn test_validate_translation() {
+        assert!(validate_mode(BACKEND_OPENAI, "whisper-1", TRANSCRIPTION_MODE_TEXT, true).is_ok());
+        assert!(validate_mode(BACKEND_LOCAL, "ggml-base", TRANSCRIPTION_MODE_TIMESTAMPS, true).is_ok());
+        assert!(validate_mode(BACKEND_OPENAI, "gpt-4o-transcribe", TRANSCRIPTION_MODE_TEXT, true).is_err());
+        assert!(
+            validate_mode(BACKEND_OPENAI, "gpt-4o-transcribe-diarize", TRANSCRIPTION_MODE_DIARIZED, true).is_err()
+        );
+    }
+}
@@ -0,0 +1,150 @@
+//! Progressive typing of streamed text
+//!
+//! Streamed rewrite output is typed as it arrives. A partial text can still
+//! change before the stream ends (a held-back `<think>` block, or a final
+//! text that differs from the streamed one), so each update is turned into
+//! the backspaces and keystrokes that take the typed text to the new one.
+//!
+//! Backspaces assume the cursor is still at the end of the typed text. That
+//! cannot be checked, but focus can: text is only deleted in the window it
+//! was typed into, and once focus moves the typed text is left alone.
+
+/// Keystrokes that turn one typed text into another
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TypingEdit {
+    /// Characters to delete from the end of the typed text
+    pub backspaces: usize,
+    /// Text to type after deleting
+    pub text: String,
+}
+
+impl TypingEdit {
+    pub fn is_empty(&self) -> bool {
+        self.backspaces == 0 && self.text.is_empty()
+    }
+}
+
+/// Edit from `typed` to `target`, keeping their common prefix
+pub fn edit(typed: &str, target: &str) -> TypingEdit {
+    let common = typed
+        .char_indices()
+        .zip(target.chars())
+        .find(|((_, a), b)| a != b)
+        .map(|((i, _), _)| i)
+        .unwrap_or_else(|| typed.len().min(target.len()));
+    TypingEdit {
+        backspaces: typed[common..].chars().count(),
+        text: target[common..].to_string(),
+    }
+}
+
+/// Text typed live for one flow
+#[derive(Debug, Default)]
+pub struct LiveTyping {
+    typed: String,
+    /// Window the text was typed into, `None` if unknown
+    window: Option<String>,
+    /// Focus moved or typing failed; nothing more is typed
+    stopped: bool,
+}
+
+impl LiveTyping {
+    pub fn is_stopped(&self) -> bool {
+        self.stopped
+    }
+
+    pub fn has_typed(&self) -> bool {
+        !self.typed.is_empty()
+    }
+
+    /// Keystrokes that take the typed text to `target`, `None` once stopped
+    pub fn plan(&self, target: &str) -> Option<TypingEdit> {
+        (!self.stopped).then(|| edit(&self.typed, target))
+    }
+
+    /// Whether `edit` may be sent while `focused` has focus; deleting text
+    /// elsewhere than where it was typed stops live typing
+    ///
+    /// An unknown window on both sides counts as the same one.
+    pub fn allows(&mut self, edit: &TypingEdit, focused: Option<&str>) -> bool {
+        if edit.backspaces > 0 && self.window.as_deref() != focused {
+            self.stopped = true;
+        }
+        !self.stopped
+    }
+
+    /// Record that the typed text is now `target`, typed into `focused`
+    pub fn typed(&mut self, target: &str, focused: Option<String>) {
+        if self.typed.is_empty() {
+            self.window = focused;
+        }
+        self.typed = target.to_string();
+    }
+
+    pub fn stop(&mut self) {
+        self.stopped = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_edit() {
+        assert_eq!(edit("", "Hello"), TypingEdit { backspaces: 0, text: "Hello".to_string() });
+        assert_eq!(edit("Hello", "Hello, world"), TypingEdit { backspaces: 0, text: ", world".to_string() });
+        assert!(edit("Same", "Same").is_empty());
+        assert_eq!(edit("Hello wrld", "Hello world"), TypingEdit { backspaces: 3, text: "orld".to_string() });
+        assert_eq!(edit("Hello there", "Hello"), TypingEdit { backspaces: 6, text: String::new() });
+    }
+
+    #[test]
+    fn test_edit_multibyte() {
+        // Backspaces count characters, not bytes
+        assert_eq!(edit("café au", "café noir"), TypingEdit { backspaces: 2, text: "noir".to_string() });
+        assert_eq!(edit("naïve", "naive"), TypingEdit { backspaces: 3, text: "ive".to_string() });
+    }
+
+    #[test]
+    fn test_abandon_erases_only_in_the_same_window() {
+        let mut live = LiveTyping::default();
+        let first = live.plan("Hello wor").unwrap();
+        assert!(live.allows(&first, Some("0x1")));
+        live.typed("Hello wor", Some("0x1".to_string()));
+
+        // A failed flow erases its partial text where it was typed
+        let erase = live.plan("").unwrap();
+        assert_eq!(erase.backspaces, 9);
+        assert!(live.allows(&erase, Some("0x1")));
+
+        // After focus moved the text is left in place, and nothing more is typed
+        assert!(!live.allows(&erase, Some("0x2")));
+        assert!(live.is_stopped());
+        assert_eq!(live.plan("Hello world"), None);
+    }
+
+    #[test]
+    fn test_appending_needs_no_focus() {
+        let mut live = LiveTyping::default();
+        live.typed("Hello", None);
+        let append = live.plan("Hello there").unwrap();
+        assert!(live.allows(&append, Some("0x2")));
+        // Unknown on both sides, e.g. without X11
+        assert!(live.allows(&live.plan("Help").unwrap(), None));
+    }
+
+    #[test]
+    fn test_edits_reach_target() {
+        let steps = ["I", "I think", "I think so", "I thought so."];
+        let mut typed = String::new();
+        for target in steps {
+            let e = edit(&typed, target);
+            for _ in 0..e.backspaces {
+                typed.pop();
+            }
+            typed.push_str(&e.text);
+            assert_eq!(typed, target);
+        }
+    }
+}
@@ -0,0 +1,262 @@
+//! Cost and usage accounting
+//!
+//! Each completed flow records how much audio was transcribed and how many
+//! tokens the rewrite used. Costs are estimated from OpenAI list prices;
+//! self-hosted and local backends, and models missing from the price table,
+//! count as free.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// USD per minute of audio for OpenAI transcription models
+const AUDIO_PRICES: &[(&str, f64)] = &[
+    ("whisper-1", 0.006),
+    ("gpt-4o-transcribe", 0.006),
+    ("gpt-4o-transcribe-diarize", 0.006),
+    ("gpt-4o-mini-transcribe", 0.003),
+];
+
+/// USD per million input and output tokens for OpenAI text models. The first
+/// matching prefix wins, so more specific names come first.
+const TOKEN_PRICES: &[(&str, f64, f64)] = &[
+    ("gpt-5-nano", 0.05, 0.40),
+    ("gpt-5-mini", 0.25, 2.00),
+    ("gpt-5", 1.25, 10.00),
+    ("gpt-4.1-nano", 0.10, 0.40),
+    ("gpt-4.1-mini", 0.40, 1.60),
+    ("gpt-4.1", 2.00, 8.00),
+    ("gpt-4o-mini", 0.15, 0.60),
+    ("gpt-4o", 2.50, 10.00),
+];
+
+/// Provider prefix of model labels that are billed
+const BILLED_PROVIDER: &str = "openai";
+
+/// Tokens used by one rewrite request
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub struct TokenUsage {
+    #[serde(default)]
+    pub input_tokens: u64,
+    #[serde(default)]
+    pub output_tokens: u64,
+}
+
+/// What one flow consumed
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct FlowUsage {
+    /// Length of the transcribed recording
+    pub audio_secs: f64,
+    /// "backend/model" labels that transcribed it, comma-separated
+    pub transcription_model: String,
+    /// "provider/model" label of the rewrite, if one ran
+    #[serde(default)]
+    pub rewrite_model: Option<String>,
+    #[serde(default)]
+    pub rewrite_tokens: TokenUsage,
+}
+
+/// A flow's usage as stored in the usage log
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct UsageRecord {
+    /// Unix time the flow completed
+    pub timestamp: u64,
+    #[serde(flatten)]
+    pub usage: FlowUsage,
+    pub cost_usd: f64,
+}
+
+/// Summed usage of one day or month
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct UsageTotal {
+    /// "YYYY-MM-DD" or "YYYY-MM"
+    pub period: String,
+    pub flows: u32,
+    pub audio_secs: f64,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cost_usd: f64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Period {
+    Day,
+    Month,
+}
+
+/// Model name of a "provider/model" label if the provider is billed
+fn billed_model(label: &str) -> Option<&str> {
+    let (provider, model) = label.trim().split_once('/')?;
+    (provider == BILLED_PROVIDER).then_some(model)
+}
+
+/// USD per minute of audio for a transcription model label
+///
+/// Labels listing several models, from chunks that fell back, are priced at
+/// the most expensive of them.
+pub fn audio_price_per_minute(labels: &str) -> f64 {
+    labels
+        .split(',')
+        .filter_map(billed_model)
+        .filter_map(|model| AUDIO_PRICES.iter().find(|(name, _)| *name == model))
+        .map(|(_, price)| *price)
+        .fold(0.0, f64::max)
+}
+
+/// USD per million input and output tokens for a rewrite model label
+pub fn token_prices(label: &str) -> (f64, f64) {
+    billed_model(label)
+        .and_then(|model| TOKEN_PRICES.iter().find(|(prefix, _, _)| model.starts_with(prefix)))
+        .map(|(_, input, output)| (*input, *output))
+        .unwrap_or((0.0, 0.0))
+}
+
+/// Estimated cost of a flow in USD
+pub fn cost_usd(usage: &FlowUsage) -> f64 {
+    let audio = usage.audio_secs / 60.0 * audio_price_per_minute(&usage.transcription_model);
+    let rewrite = usage.rewrite_model.as_deref().map_or(0.0, |model| {
+        let (input, output) = token_prices(model);
+        (usage.rewrite_tokens.input_tokens as f64 * input + usage.rewrite_tokens.output_tokens as f64 * output)
+            / 1_000_000.0
+    });
+    audio + rewrite
+}
+
+/// Check the monthly budget setting before it is saved; 0 disables the cap
+pub fn validate_budget(budget_usd: f64) -> Result<(), String> {
+    if budget_usd.is_finite() && budget_usd >= 0.0 {
+        Ok(())
+    } else {
+        Err(format!("Monthly budget must be a positive amount or 0, got {}", budget_usd))
+    }
+}
+
+/// Current offset of the system time zone east of UTC, in seconds
+pub fn local_utc_offset_secs() -> i64 {
+    i64::from(chrono::Local::now().offset().local_minus_utc())
+}
+
+/// Label of the day or month a Unix time falls in, `utc_offset_secs` east of UTC
+pub fn period_key(timestamp: u64, period: Period, utc_offset_secs: i64) -> String {
+    let local = chrono::DateTime::from_timestamp(timestamp as i64 + utc_offset_secs, 0).unwrap_or_default();
+    let format = match period {
+        Period::Day => "%Y-%m-%d",
+        Period::Month => "%Y-%m",
+    };
+    local.format(format).to_string()
+}
+
+/// Usage summed per day or month, oldest first
+pub fn totals(records: &[UsageRecord], period: Period, utc_offset_secs: i64) -> Vec<UsageTotal> {
+    let mut totals: BTreeMap<String, UsageTotal> = BTreeMap::new();
+    for record in records {
+        let key = period_key(record.timestamp, period, utc_offset_secs);
+        let total = totals.entry(key.clone()).or_insert_with(|| UsageTotal {
+            period: key,
+            ..Default::default()
+        });
+        total.flows += 1;
+        total.audio_secs += record.usage.audio_secs;
+        total.input_tokens += record.usage.rewrite_tokens.input_tokens;
+        total.output_tokens += record.usage.rewrite_tokens.output_tokens;
+        total.cost_usd += record.cost_usd;
+    }
+    totals.into_values().collect()
+}
+
+/// Cost of the flows in the same month as `now`
+pub fn month_cost(records: &[UsageRecord], now: u64, utc_offset_secs: i64) -> f64 {
+    let month = period_key(now, Period::Month, utc_offset_secs);
+    records
+        .iter()
+        .filter(|record| period_key(record.timestamp, Period::Month, utc_offset_secs) == month)
+        .map(|record| record.cost_usd)
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(timestamp: u64, cost_usd: f64) -> UsageRecord {
+        UsageRecord {
+            timestamp,
+            usage: FlowUsage {
+                audio_secs: 30.0,
+                transcription_model: "openai/whisper-1".to_string(),
+                rewrite_model: None,
+                rewrite_tokens: TokenUsage::default(),
+            },
+            cost_usd,
+        }
+    }
+
+    #[test]
+    fn test_cost() {
+        let mut usage = FlowUsage {
+            audio_secs: 120.0,
+            transcription_model: "openai/gpt-4o-mini-transcribe".to_string(),
+            ..Default::default()
+        };
+        assert!((cost_usd(&usage) - 0.006).abs() < 1e-12);
+
+        usage.rewrite_model = Some("openai/gpt-5-mini".to_string());
+        usage.rewrite_tokens = TokenUsage { input_tokens: 1_000_000, output_tokens: 100_000 };
+        assert!((cost_usd(&usage) - (0.006 + 0.25 + 0.2)).abs() < 1e-12);
+
+        // Self-hosted and unknown models are free
+        usage.transcription_model = "openai_compatible/whisper-1".to_string();
+        usage.rewrite_model = Some("openai/unknown".to_string());
+        assert_eq!(cost_usd(&usage), 0.0);
+    }
+
+    #[test]
+    fn test_fallback_models_priced_at_most_expensive() {
+        assert_eq!(audio_price_per_minute("openai/gpt-4o-mini-transcribe, openai/whisper-1"), 0.006);
+        assert_eq!(audio_price_per_minute("local/ggml-base.en"), 0.0);
+    }
+
+    #[test]
+    fn test_period_key() {
+        // 2024-02-29 23:30 UTC
+        let ts = 1_709_249_400;
+        assert_eq!(period_key(ts, Period::Day, 0), "2024-02-29");
+        assert_eq!(period_key(ts, Period::Month, 0), "2024-02");
+        assert_eq!(period_key(ts, Period::Day, 3600), "2024-03-01");
+        assert_eq!(period_key(0, Period::Day, -3600), "1969-12-31");
+    }
+
+    #[test]
+    fn test_totals() {
+        let day = 86_400;
+        let records = vec![record(0, 0.5), record(100, 0.25), record(day, 1.0), record(40 * day, 2.0)];
+
+        let daily = totals(&records, Period::Day, 0);
+        assert_eq!(daily.len(), 3);
+        assert_eq!(daily[0].period, "1970-01-01");
+        assert_eq!(daily[0].flows, 2);
+        assert_eq!(daily[0].cost_usd, 0.75);
+        assert_eq!(daily[0].audio_secs, 60.0);
+
+        let monthly = totals(&records, Period::Month, 0);
+        assert_eq!(monthly.len(), 2);
+        assert_eq!(monthly[0].cost_usd, 1.75);
+        assert_eq!(month_cost(&records, 5 * day, 0), 1.75);
+    }
+
+    #[test]
+    fn test_validate_budget() {
+        assert!(validate_budget(0.0).is_ok());
+        assert!(validate_budget(20.0).is_ok());
+        assert!(validate_budget(-1.0).is_err());
+        assert!(validate_budget(f64::NAN).is_err());
+    }
+
+    #[test]
+    fn test_record_roundtrip() {
+        let json = serde_json::to_string(&record(5, 0.1)).unwrap();
+        assert!(json.contains("\"audio_secs\":30.0"));
+        let parsed: UsageRecord = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, record(5, 0.1));
+    }
+}
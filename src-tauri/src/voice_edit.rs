@@ -0,0 +1,297 @@
+//! Spoken edit commands for typed output
+//!
+//! In type mode a recording that starts with an edit command changes the
+//! text typed by earlier recordings instead of being typed itself:
+//! "scratch that" or "undo" deletes the last insertion, "replace X with Y"
+//! swaps the last whole-word "X" for "Y", and "capitalize that" capitalizes
+//! the last word. Edits assume the cursor is still where the last insertion
+//! ended, since that is all the app can know.
+
+use crate::typing::{self, TypingEdit};
+
+/// Insertions remembered for editing; older ones are forgotten
+pub const MAX_INSERTIONS: usize = 20;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum EditCommand {
+    DeleteLast,
+    Replace { find: String, replacement: String },
+    CapitalizeLastWord,
+}
+
+const DELETE_PHRASES: &[&[&str]] = &[&["scratch", "that"], &["delete", "that"], &["undo", "that"], &["undo"]];
+
+const CAPITALIZE_PHRASES: &[&[&str]] = &[
+    &["capitalize", "that"],
+    &["capitalise", "that"],
+    &["capitalize", "the", "last", "word"],
+    &["capitalise", "the", "last", "word"],
+    &["capitalize", "last", "word"],
+    &["capitalise", "last", "word"],
+];
+
+/// Punctuation that may end a command followed by more dictation
+const COMMAND_END: &[char] = &['.', ',', '!', '?', ';', ':'];
+
+/// Lowercase letters and digits of a word, used for matching
+fn key(word: &str) -> String {
+    word.chars().filter(|c| c.is_alphanumeric()).flat_map(char::to_lowercase).collect()
+}
+
+/// Text without surrounding punctuation and whitespace
+fn strip(text: &str) -> &str {
+    text.trim_matches(|c: char| !c.is_alphanumeric())
+}
+
+/// Words of `text` with their byte offsets
+fn words(text: &str) -> Vec<(usize, &str)> {
+    text.split_whitespace()
+        .map(|word| (word.as_ptr() as usize - text.as_ptr() as usize, word))
+        .collect()
+}
+
+/// Text after a command phrase of `length` words, or `None` if the phrase
+/// is not a command here
+///
+/// A command must be the whole transcript or be followed by punctuation, so
+/// "undo the last change" is dictated as text rather than run.
+fn after_phrase<'a>(text: &'a str, words: &[(usize, &str)], length: usize) -> Option<&'a str> {
+    let (start, last) = words[length - 1];
+    let end = start + last.len();
+    let rest = &text[end..];
+    if strip(rest).is_empty() {
+        return Some("");
+    }
+    last.ends_with(COMMAND_END).then(|| rest.trim_start())
+}
+
+fn match_phrase<'a>(text: &'a str, words: &[(usize, &str)], phrases: &[&[&str]]) -> Option<&'a str> {
+    let mut phrases: Vec<&&[&str]> = phrases.iter().collect();
+    // Longest first, so "undo that" is not read as "undo"
+    phrases.sort_by_key(|phrase| std::cmp::Reverse(phrase.len()));
+    phrases.into_iter().find_map(|phrase| {
+        let matches = phrase.len() <= words.len()
+            && phrase.iter().zip(words).all(|(spoken, (_, word))| *spoken == key(word));
+        if matches { after_phrase(text, words, phrase.len()) } else { None }
+    })
+}
+
+/// The edit command at the start of a transcript and the text after it
+pub fn parse(transcript: &str) -> Option<(EditCommand, String)> {
+    let words = words(transcript);
+    if words.is_empty() {
+        return None;
+    }
+    if let Some(rest) = match_phrase(transcript, &words, DELETE_PHRASES) {
+        return Some((EditCommand::DeleteLast, rest.to_string()));
+    }
+    if let Some(rest) = match_phrase(transcript, &words, CAPITALIZE_PHRASES) {
+        return Some((EditCommand::CapitalizeLastWord, rest.to_string()));
+    }
+    if key(words[0].1) == "replace" {
+        // "replace X with Y" takes the whole transcript; X ends at the first "with"
+        let with = words.iter().skip(2).position(|(_, word)| key(word) == "with")? + 2;
+        let find = strip(&transcript[words[1].0..words[with].0]);
+        let replacement = strip(&transcript[words[with].0 + words[with].1.len()..]);
+        if find.is_empty() || replacement.is_empty() {
+            return None;
+        }
+        return Some((
+            EditCommand::Replace {
+                find: find.to_string(),
+                replacement: replacement.to_string(),
+            },
+            String::new(),
+        ));
+    }
+    None
+}
+
+/// Byte range of the last whole-word occurrence of `needle`, ignoring case
+fn rfind_word(haystack: &str, needle: &str) -> Option<(usize, usize)> {
+    let needle: Vec<char> = needle.chars().flat_map(char::to_lowercase).collect();
+    let starts: Vec<usize> = haystack.char_indices().map(|(i, _)| i).collect();
+    starts.iter().rev().find_map(|&start| {
+        let mut chars = haystack[start..].char_indices();
+        let mut matched = 0;
+        let mut end = start;
+        while matched < needle.len() {
+            let (offset, c) = chars.next()?;
+            for lower in c.to_lowercase() {
+                if needle.get(matched) != Some(&lower) {
+                    return None;
+                }
+                matched += 1;
+            }
+            end = start + offset + c.len_utf8();
+        }
+        let before_ok = !haystack[..start].chars().next_back().is_some_and(char::is_alphanumeric);
+        let after_ok = !haystack[end..].chars().next().is_some_and(char::is_alphanumeric);
+        (before_ok && after_ok).then_some((start, end))
+    })
+}
+
+/// Replace the insertions from the one containing byte `from` of their
+/// joined text onwards with `new_tail`, the new joined text
+fn rewrite_tail(insertions: &mut Vec<String>, from: usize, new_tail: &str) {
+    let mut start = 0;
+    let mut index = 0;
+    for (i, insertion) in insertions.iter().enumerate() {
+        if start + insertion.len() > from {
+            index = i;
+            break;
+        }
+        start += insertion.len();
+    }
+    insertions.truncate(index);
+    insertions.push(new_tail[start..].to_string());
+}
+
+/// Keystrokes that apply `command` to the recent insertions, updating them
+///
+/// Returns `None`, leaving the insertions as they are, if there is nothing
+/// to edit.
+pub fn apply(command: &EditCommand, insertions: &mut Vec<String>) -> Option<TypingEdit> {
+    let typed = insertions.concat();
+    match command {
+        EditCommand::DeleteLast => {
+            let last = insertions.pop()?;
+            Some(TypingEdit {
+                backspaces: last.chars().count(),
+                text: String::new(),
+            })
+        }
+        EditCommand::Replace { find, replacement } => {
+            let (start, end) = rfind_word(&typed, find)?;
+            let new_text = format!("{}{}{}", &typed[..start], replacement, &typed[end..]);
+            rewrite_tail(insertions, start, &new_text);
+            Some(typing::edit(&typed, &new_text))
+        }
+        EditCommand::CapitalizeLastWord => {
+            let end = typed.rfind(char::is_alphanumeric)?;
+            let start = typed[..end]
+                .char_indices()
+                .rev()
+                .find(|(_, c)| !c.is_alphanumeric())
+                .map_or(0, |(i, c)| i + c.len_utf8());
+            let mut chars = typed[start..].chars();
+            let first = chars.next()?;
+            let new_text = format!("{}{}{}", &typed[..start], first.to_uppercase(), chars.as_str());
+            if new_text == typed {
+                return None;
+            }
+            rewrite_tail(insertions, start, &new_text);
+            Some(typing::edit(&typed, &new_text))
+        }
+    }
+}
+
+/// Remember text that was typed, forgetting the oldest insertions
+pub fn record(insertions: &mut Vec<String>, text: &str) {
+    if text.is_empty() {
+        return;
+    }
+    insertions.push(text.to_string());
+    if insertions.len() > MAX_INSERTIONS {
+        insertions.drain(..insertions.len() - MAX_INSERTIONS);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn insertions(texts: &[&str]) -> Vec<String> {
+        texts.iter().map(|t| t.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(parse("Scratch that."), Some((EditCommand::DeleteLast, String::new())));
+        assert_eq!(parse("undo"), Some((EditCommand::DeleteLast, String::new())));
+        assert_eq!(
+            parse("Scratch that, I meant Friday."),
+            Some((EditCommand::DeleteLast, "I meant Friday.".to_string()))
+        );
+        assert_eq!(parse("Capitalize the last word!"), Some((EditCommand::CapitalizeLastWord, String::new())));
+        assert_eq!(
+            parse("Replace Tuesday with Wednesday."),
+            Some((
+                EditCommand::Replace {
+                    find: "Tuesday".to_string(),
+                    replacement: "Wednesday".to_string()
+                },
+                String::new()
+            ))
+        );
+        assert_eq!(
+            parse("replace with with without"),
+            Some((
+                EditCommand::Replace {
+                    find: "with".to_string(),
+                    replacement: "without".to_string()
+                },
+                String::new()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_parse_ignores_dictation() {
+        // Commands must stand alone or be followed by punctuation
+        assert_eq!(parse("Undo the last change to the file."), None);
+        assert_eq!(parse("I said scratch that."), None);
+        assert_eq!(parse("Replace it."), None);
+        assert_eq!(parse(""), None);
+    }
+
+    #[test]
+    fn test_delete_last() {
+        let mut typed = insertions(&["Hello. ", "Sée you"]);
+        let edit = apply(&EditCommand::DeleteLast, &mut typed).unwrap();
+        assert_eq!(edit, TypingEdit { backspaces: 7, text: String::new() });
+        assert_eq!(typed, insertions(&["Hello. "]));
+
+        assert_eq!(apply(&EditCommand::DeleteLast, &mut Vec::new()), None);
+    }
+
+    #[test]
+    fn test_replace() {
+        let mut typed = insertions(&["Meet on Tuesday ", "at the cat cafe"]);
+        let replace = |find: &str, replacement: &str| EditCommand::Replace {
+            find: find.to_string(),
+            replacement: replacement.to_string(),
+        };
+
+        let edit = apply(&replace("tuesday", "Friday"), &mut typed).unwrap();
+        assert_eq!(edit.backspaces, "Tuesday at the cat cafe".len());
+        assert_eq!(edit.text, "Friday at the cat cafe");
+        assert_eq!(typed.concat(), "Meet on Friday at the cat cafe");
+        assert_eq!(typed.len(), 1);
+
+        // Whole words only
+        assert_eq!(apply(&replace("ca", "x"), &mut typed), None);
+        let edit = apply(&replace("cat cafe", "tea room"), &mut typed).unwrap();
+        assert_eq!(edit, TypingEdit { backspaces: 8, text: "tea room".to_string() });
+    }
+
+    #[test]
+    fn test_capitalize_last_word() {
+        let mut typed = insertions(&["see you in paris."]);
+        let edit = apply(&EditCommand::CapitalizeLastWord, &mut typed).unwrap();
+        assert_eq!(edit, TypingEdit { backspaces: 6, text: "Paris.".to_string() });
+        assert_eq!(typed, insertions(&["see you in Paris."]));
+        assert_eq!(apply(&EditCommand::CapitalizeLastWord, &mut typed), None);
+    }
+
+    #[test]
+    fn test_record_limit() {
+        let mut typed = Vec::new();
+        for i in 0..MAX_INSERTIONS + 5 {
+            record(&mut typed, &i.to_string());
+        }
+        record(&mut typed, "");
+        assert_eq!(typed.len(), MAX_INSERTIONS);
+        assert_eq!(typed[0], "5");
+    }
+}
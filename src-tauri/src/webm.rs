@@ -42,9 +42,13 @@
 //!     Ok(webm_data)
 //! }
 //! ```
+//!
+//! `read_opus_track` does the reverse for files produced by `WebmWriter`,
+//! returning the raw Opus packets so they can be decoded again, and
+//! `decode_opus_track` decodes them straight to PCM.
 
 use crate::ebml::EbmlBuilder;
-use crate::opus::{BufferedOpusEncoder, OpusError};
+use crate::opus::{BufferedOpusEncoder, OpusError, OpusFrameDecoder};
 
 /// Sample rate for audio (48kHz - Opus native rate)
 const SAMPLE_RATE: u32 = 48000;
@@ -104,6 +108,115 @@ mod ids {
     pub const SIMPLE_BLOCK: u8 = 0xA3;
 }
 
+/// Opus packets and codec setup read back from a WebM file
+#[derive(Debug, Clone)]
+pub struct WebmOpusTrack {
+    /// Samples to discard from the start of the decoded audio
+    pub preskip: u16,
+    /// Opus packets in playback order
+    pub packets: Vec<Vec<u8>>,
+}
+
+/// Read the Opus packets of the first audio track in a WebM file
+///
+/// Supports the subset of Matroska written by `WebmWriter`: a single track,
+/// SimpleBlocks without lacing, and OpusHead in CodecPrivate.
+pub fn read_opus_track(data: &[u8]) -> Result<WebmOpusTrack, OpusError> {
+    let mut track = WebmOpusTrack {
+        preskip: 0,
+        packets: Vec::new(),
+    };
+    read_elements(data, &mut track)?;
+    if track.packets.is_empty() {
+        return Err(OpusError::WebmError("No audio blocks found".to_string()));
+    }
+    Ok(track)
+}
+
+/// Decode a WebM file written by `WebmWriter` to 48 kHz mono PCM
+///
+/// The encoder lookahead (preskip) is dropped, so sample 0 is the start of the recording.
+pub fn decode_opus_track(data: &[u8]) -> Result<Vec<f32>, OpusError> {
+    let track = read_opus_track(data)?;
+    let mut decoder = OpusFrameDecoder::new()?;
+    let mut pcm = Vec::with_capacity(track.packets.len() * (SAMPLE_RATE * FRAME_DURATION_MS / 1000) as usize);
+    for packet in &track.packets {
+        pcm.extend_from_slice(&decoder.decode_f32(packet)?);
+    }
+    pcm.drain(..(track.preskip as usize).min(pcm.len()));
+    Ok(pcm)
+}
+
+/// Read an EBML element ID (marker bits kept). Returns (id, length).
+fn read_element_id(data: &[u8]) -> Result<(u32, usize), OpusError> {
+    let first = *data.first().ok_or_else(|| OpusError::WebmError("Truncated element ID".to_string()))?;
+    let len = first.leading_zeros() as usize + 1;
+    if len > 4 || data.len() < len {
+        return Err(OpusError::WebmError("Invalid element ID".to_string()));
+    }
+    let id = data[..len].iter().fold(0u32, |acc, &b| (acc << 8) | b as u32);
+    Ok((id, len))
+}
+
+/// Read an EBML data size (marker bit removed). Returns (size, length);
+/// size is None for the reserved "unknown size" value.
+fn read_element_size(data: &[u8]) -> Result<(Option<u64>, usize), OpusError> {
+    let first = *data.first().ok_or_else(|| OpusError::WebmError("Truncated element size".to_string()))?;
+    let len = first.leading_zeros() as usize + 1;
+    if len > 8 || data.len() < len {
+        return Err(OpusError::WebmError("Invalid element size".to_string()));
+    }
+    let mask = if len == 8 { 0 } else { 0xFFu8 >> len };
+    let value = data[1..len]
+        .iter()
+        .fold((first & mask) as u64, |acc, &b| (acc << 8) | b as u64);
+    let all_ones = (1u64 << (7 * len)) - 1;
+    Ok((if value == all_ones { None } else { Some(value) }, len))
+}
+
+/// Walk sibling elements, descending into the containers that lead to audio data
+fn read_elements(mut data: &[u8], track: &mut WebmOpusTrack) -> Result<(), OpusError> {
+    while !data.is_empty() {
+        let (id, id_len) = read_element_id(data)?;
+        let (size, size_len) = read_element_size(&data[id_len..])?;
+        let header_len = id_len + size_len;
+        let body_len = match size {
+            Some(size) => usize::try_from(size)
+                .ok()
+                .filter(|&size| size <= data.len() - header_len)
+                .ok_or_else(|| OpusError::WebmError("Element extends past end of data".to_string()))?,
+            // Unknown size: the element runs to the end of its parent
+            None => data.len() - header_len,
+        };
+        let body = &data[header_len..header_len + body_len];
+
+        match id {
+            ids::SEGMENT | ids::TRACKS | ids::CLUSTER => read_elements(body, track)?,
+            id if id == ids::TRACK_ENTRY as u32 => read_elements(body, track)?,
+            // OpusHead: magic(8) version(1) channels(1) preskip(2, LE) ...
+            id if id == ids::CODEC_PRIVATE as u32 && body.len() >= 12 && &body[..8] == b"OpusHead" => {
+                track.preskip = u16::from_le_bytes([body[10], body[11]]);
+            }
+            id if id == ids::SIMPLE_BLOCK as u32 => {
+                // Track number (vint), timestamp offset (2 bytes), flags (1 byte), frame
+                let (_, track_len) = read_element_size(body)?;
+                let frame_start = track_len + 3;
+                if body.len() < frame_start {
+                    return Err(OpusError::WebmError("Truncated SimpleBlock".to_string()));
+                }
+                if body[track_len + 2] & 0x06 != 0 {
+                    return Err(OpusError::WebmError("Laced blocks are not supported".to_string()));
+                }
+                track.packets.push(body[frame_start..].to_vec());
+            }
+            _ => {}
+        }
+
+        data = &data[header_len + body_len..];
+    }
+    Ok(())
+}
+
 /// WebM writer that encodes audio to Opus and packages it in WebM container
 pub struct WebmWriter {
     /// Opus encoder
@@ -454,6 +567,11 @@ impl WebmWriter {
         completed_size + self.current_cluster_blocks.len()
     }
     
+    /// Duration of all samples added so far, including any not yet encoded
+    pub fn duration_secs(&self) -> f64 {
+        (self.total_samples_encoded + self.encoder.buffered_samples() as u64) as f64 / SAMPLE_RATE as f64
+    }
+
     /// Get the current timestamp in milliseconds
     pub fn current_timestamp_ms(&self) -> u32 {
         self.current_timestamp_ms
@@ -495,6 +613,13 @@ mod tests {
         assert_eq!(writer.current_timestamp_ms(), 20);
     }
     
+    #[test]
+    fn test_duration_secs() {
+        let mut writer = WebmWriter::new(64000).unwrap();
+        writer.add_samples_f32(&vec![0.0f32; 48000 + 480]).unwrap();
+        assert!((writer.duration_secs() - 1.01).abs() < 1e-9);
+    }
+    
     #[test]
     fn test_clustering() {
         let mut writer = WebmWriter::new(64000).unwrap();
@@ -531,6 +656,47 @@ mod tests {
         // This test just ensures finalize works
     }
     
+    #[test]
+    fn test_read_opus_track_roundtrip() {
+        let mut writer = WebmWriter::new(64000).unwrap();
+        // ~2.5 seconds of a quiet tone so frames span several clusters
+        let samples: Vec<f32> = (0..120000).map(|i| (i as f32 * 0.05).sin() * 0.2).collect();
+        writer.add_samples_f32(&samples).unwrap();
+        let preskip = writer.encoder.get_preskip().unwrap() as u16;
+        let data = writer.finalize().unwrap();
+
+        let track = read_opus_track(&data).unwrap();
+        assert_eq!(track.preskip, preskip);
+        // 125 frames of input + padding/flush frames
+        assert!(track.packets.len() >= 125);
+    }
+
+    #[test]
+    fn test_decode_opus_track() {
+        let mut writer = WebmWriter::new(64000).unwrap();
+        let samples: Vec<f32> = (0..48000).map(|i| (i as f32 * 0.05).sin() * 0.2).collect();
+        writer.add_samples_f32(&samples).unwrap();
+        let data = writer.finalize().unwrap();
+
+        // Preskip removed; the flush pads the end to a whole frame
+        let pcm = decode_opus_track(&data).unwrap();
+        assert!(pcm.len() >= samples.len());
+        assert!(pcm.len() < samples.len() + 2 * 960);
+    }
+
+    #[test]
+    fn test_read_opus_track_rejects_garbage() {
+        assert!(read_opus_track(&[]).is_err());
+        assert!(read_opus_track(&[0x1A, 0x45, 0xDF, 0xA3, 0xFF]).is_err());
+    }
+
+    #[test]
+    fn test_element_size_decoding() {
+        assert_eq!(read_element_size(&[0x82]).unwrap(), (Some(2), 1));
+        assert_eq!(read_element_size(&[0x40, 0x7F]).unwrap(), (Some(127), 2));
+        assert_eq!(read_element_size(&[0xFF]).unwrap(), (None, 1));
+    }
+
     #[test]
     fn test_opus_head_structure() {
         let preskip = 312u16;
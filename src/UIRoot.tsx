@@ -2,7 +2,7 @@ import { useCallback, useEffect, useRef, useState } from "react";
 import { invoke } from "@tauri-apps/api/core";
 import { getCurrentWindow } from "@tauri-apps/api/window";
 
-import { useBackendListeners } from "./hooks/useBackendListeners";
+import { DeviceEvent, FlowError, useBackendListeners } from "./hooks/useBackendListeners";
 
 type FrontendStatus = "loading" | "ready" | "recording" | "processing";
 
@@ -39,6 +39,35 @@ function useDpr() {
   return dpr;
 }
 
+// Characters of live transcript shown while recording (the most recent ones)
+const LIVE_TRANSCRIPT_TAIL = 120;
+
+// Short labels for the error pill; the full message is shown on hover
+const ERROR_LABELS: Record<string, string> = {
+  auth: "Check API key",
+  rate_limited: "Rate limited",
+  timeout: "Timed out",
+  network: "Offline",
+  server: "Server error",
+  bad_request: "Request rejected",
+  invalid_response: "Bad response",
+  device: "Mic error",
+  audio: "Audio error",
+  config: "Check settings",
+};
+
+// Hover text for the microphone pill
+function describeDeviceEvent(event: DeviceEvent): string {
+  switch (event.kind) {
+    case "lost":
+      return `Microphone lost: ${event.message}`;
+    case "reconnected":
+      return `Microphone lost, recording continued on ${event.device}`;
+    case "unavailable":
+      return "Microphone lost and no other one was available; recording ended early";
+  }
+}
+
 const clamp = (n: number, min: number, max: number) => Math.max(min, Math.min(max, n));
 
 export default function UIRoot() {
@@ -47,6 +76,11 @@ export default function UIRoot() {
   const [waveformBins, setWaveformBins] = useState<number[]>([]);
   const [waveformAvgRms, setWaveformAvgRms] = useState<number>(0);
   const [retryVisible, setRetryVisible] = useState<boolean>(false);
+  const [partialTranscripts, setPartialTranscripts] = useState<string[]>([]);
+  const [partialRewrite, setPartialRewrite] = useState<string>("");
+  const [flowError, setFlowError] = useState<FlowError | null>(null);
+  const [rewriteFailure, setRewriteFailure] = useState<FlowError | null>(null);
+  const [deviceEvent, setDeviceEvent] = useState<DeviceEvent | null>(null);
   const waveformUpdateCountRef = useRef<number>(0);
 
   const dpr = useDpr();
@@ -118,6 +152,13 @@ export default function UIRoot() {
     setStatus(newStatus);
     if (newStatus === "recording") {
       waveformUpdateCountRef.current = 0;
+      setFlowError(null);
+      setRewriteFailure(null);
+      setDeviceEvent(null);
+    }
+    if (newStatus === "recording" || newStatus === "ready") {
+      setPartialTranscripts([]);
+      setPartialRewrite("");
     }
   }, []);
 
@@ -134,6 +175,11 @@ export default function UIRoot() {
     setTranscriptionText: noopSetText,
     setLayoutMode: noopSetLayout,
     setRetryVisible,
+    setFlowError,
+    setRewriteFailure,
+    setDeviceEvent,
+    setPartialTranscripts,
+    setPartialRewrite,
     copyToClipboard,
     textareaRef,
     addSmartSpacing,
@@ -291,9 +337,12 @@ export default function UIRoot() {
   const onRetry = useCallback(async () => {
     try {
       setRetryVisible(false);
+      setFlowError(null);
+      setRewriteFailure(null);
       await invoke<string>("retry_transcription");
     } catch (e) {
       console.error("Failed to retry transcription:", e);
+      setFlowError(e as FlowError);
     }
   }, []);
 
@@ -333,6 +382,7 @@ export default function UIRoot() {
           }
         } catch (err) {
           console.error("Failed to toggle recording via canvas click:", err);
+          setFlowError(err as FlowError);
         }
       }
 
@@ -371,8 +421,33 @@ export default function UIRoot() {
         />
       )}
 
+      {status === "processing" && partialRewrite.trim() && (
+        <div className="live-transcript">{partialRewrite.slice(-LIVE_TRANSCRIPT_TAIL)}</div>
+      )}
+
+      {(status === "recording" || (status === "processing" && !partialRewrite.trim())) && partialTranscripts.length > 0 && (
+        <div className="live-transcript">
+          {partialTranscripts.filter((t) => t && t.trim()).join(" ").slice(-LIVE_TRANSCRIPT_TAIL)}
+        </div>
+      )}
+
       <div className="ui-controls">
         <div className="bottom-right">
+          {flowError && (
+            <span className="ctrl pill flow-error no-drag" title={flowError.message}>
+              {ERROR_LABELS[flowError.kind] ?? "Error"}
+            </span>
+          )}
+          {!flowError && deviceEvent && (
+            <span className="ctrl pill flow-warning no-drag" title={describeDeviceEvent(deviceEvent)}>
+              {deviceEvent.kind === "reconnected" ? "Mic switched" : "Mic lost"}
+            </span>
+          )}
+          {!flowError && rewriteFailure && (
+            <span className="ctrl pill flow-warning no-drag" title={`Rewrite skipped: ${rewriteFailure.message}`}>
+              Not rewritten
+            </span>
+          )}
           {retryVisible && (
             <button className="ctrl pill ghost no-drag" onClick={onRetry} title="Retry last">
               Retry
@@ -2,15 +2,46 @@ import { useEffect, useState, useCallback } from "react";
 import { invoke } from "@tauri-apps/api/core";
 import { listen } from "@tauri-apps/api/event";
 
+interface TranscriptSegment {
+  start: number;
+  end: number;
+  text: string;
+}
+
+interface TranscriptWord {
+  start: number;
+  end: number;
+  word: string;
+}
+
+interface SpeakerTurn {
+  speaker: string;
+  start: number;
+  end: number;
+  text: string;
+}
+
 interface TranscriptionHistoryEntry {
   text: string;
   timestamp: number;
+  segments: TranscriptSegment[];
+  words: TranscriptWord[];
+  turns: SpeakerTurn[];
+  audio_path: string | null;
+  model: string;
+  raw_text: string;
+  rewritten_text: string | null;
+  prompt_id: string | null;
+  rewrite_model: string | null;
 }
 
+type TextVersion = "final" | "raw" | "rewritten";
+
 export default function History() {
   const [entries, setEntries] = useState<TranscriptionHistoryEntry[]>([]);
   const [loading, setLoading] = useState(true);
   const [copiedIndex, setCopiedIndex] = useState<number | null>(null);
+  const [copiedVersion, setCopiedVersion] = useState<TextVersion>("final");
 
   const loadHistory = useCallback(async () => {
     try {
@@ -43,16 +74,25 @@ export default function History() {
     };
   }, [loadHistory]);
 
-  const handleCopy = async (index: number) => {
+  const handleCopy = async (index: number, version: TextVersion = "final") => {
     try {
-      await invoke("copy_history_entry", { index });
+      await invoke("copy_history_entry", { index, version });
       setCopiedIndex(index);
+      setCopiedVersion(version);
       setTimeout(() => setCopiedIndex(null), 1500);
     } catch (e) {
       console.error("Failed to copy history entry:", e);
     }
   };
 
+  const handlePlay = async (index: number, start: number) => {
+    try {
+      await invoke("play_history_audio", { index, start });
+    } catch (e) {
+      console.error("Failed to play recording:", e);
+    }
+  };
+
   const handleCopyAll = async () => {
     if (entries.length === 0) return;
     const allText = entries.map(e => e.text).join("\n\n");
@@ -78,6 +118,95 @@ export default function History() {
     return date.toLocaleTimeString([], { hour: '2-digit', minute: '2-digit' });
   };
 
+  const formatOffset = (secs: number) => {
+    const minutes = Math.floor(secs / 60);
+    const seconds = Math.floor(secs % 60);
+    return `${minutes}:${seconds.toString().padStart(2, "0")}`;
+  };
+
+  // Diarized entries show one labelled line per turn instead of the flat text
+  const renderText = (entry: TranscriptionHistoryEntry, index: number) => {
+    if (entry.turns.length === 0) {
+      return <p className="history-entry-text">{entry.text}</p>;
+    }
+    return (
+      <div className="history-entry-turns">
+        {entry.turns.map((turn, i) => (
+          <p
+            key={i}
+            className={`history-entry-turn ${entry.audio_path ? "history-entry-turn-playable" : ""}`}
+            title={formatOffset(turn.start)}
+            onClick={() => entry.audio_path && handlePlay(index, turn.start)}
+          >
+            <span className="history-entry-speaker">{turn.speaker}</span>
+            {turn.text}
+          </p>
+        ))}
+      </div>
+    );
+  };
+
+  // The transcription before rewriting, for when the rewrite mangled it
+  const renderOriginal = (entry: TranscriptionHistoryEntry, index: number) => {
+    if (entry.rewritten_text === null || !entry.raw_text || entry.raw_text === entry.text) return null;
+    return (
+      <details className="history-entry-timing">
+        <summary>Original transcription</summary>
+        <p className="history-entry-text">{entry.raw_text}</p>
+        <button className="history-entry-copy-btn" onClick={() => handleCopy(index, "raw")}>
+          {copiedIndex === index && copiedVersion === "raw" ? "Copied!" : "Copy Original"}
+        </button>
+        {entry.rewritten_text !== entry.text && (
+          <button className="history-entry-copy-btn" onClick={() => handleCopy(index, "rewritten")}>
+            {copiedIndex === index && copiedVersion === "rewritten" ? "Copied!" : "Copy Rewrite"}
+          </button>
+        )}
+      </details>
+    );
+  };
+
+  // Timings refer to the raw transcription; playback needs the saved recording
+  const renderTimedText = (entry: TranscriptionHistoryEntry, index: number) => {
+    if (!entry.audio_path) return null;
+    if (entry.words.length > 0) {
+      return (
+        <details className="history-entry-timing">
+          <summary>Play from a word</summary>
+          <p className="history-entry-words">
+            {entry.words.map((word, i) => (
+              <span
+                key={i}
+                className="history-entry-word"
+                title={formatOffset(word.start)}
+                onClick={() => handlePlay(index, word.start)}
+              >
+                {word.word}{" "}
+              </span>
+            ))}
+          </p>
+        </details>
+      );
+    }
+    if (entry.segments.length > 0) {
+      return (
+        <details className="history-entry-timing">
+          <summary>Play from a segment</summary>
+          {entry.segments.map((segment, i) => (
+            <p
+              key={i}
+              className="history-entry-segment"
+              onClick={() => handlePlay(index, segment.start)}
+            >
+              <span className="history-entry-offset">{formatOffset(segment.start)}</span>
+              {segment.text}
+            </p>
+          ))}
+        </details>
+      );
+    }
+    return null;
+  };
+
   if (loading) {
     return (
       <div className="history-container">
@@ -112,15 +241,26 @@ export default function History() {
             return (
               <div key={originalIndex} className="history-entry">
                 <div className="history-entry-header">
-                  <span className="history-entry-time">{formatTimestamp(entry.timestamp)}</span>
+                  <span className="history-entry-time">
+                    {formatTimestamp(entry.timestamp)}
+                    {entry.model && <span className="history-entry-model"> · {entry.model}</span>}
+                    {entry.rewrite_model && (
+                      <span className="history-entry-model">
+                        {" "}→ {entry.rewrite_model}
+                        {entry.prompt_id && ` (${entry.prompt_id})`}
+                      </span>
+                    )}
+                  </span>
                   <button
                     className="history-entry-copy-btn"
                     onClick={() => handleCopy(originalIndex)}
                   >
-                    {copiedIndex === originalIndex ? "Copied!" : "Copy"}
+                    {copiedIndex === originalIndex && copiedVersion === "final" ? "Copied!" : "Copy"}
                   </button>
                 </div>
-                <p className="history-entry-text">{entry.text}</p>
+                {renderText(entry, originalIndex)}
+                {renderOriginal(entry, originalIndex)}
+                {renderTimedText(entry, originalIndex)}
               </div>
             );
           })}
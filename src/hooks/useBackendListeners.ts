@@ -4,6 +4,12 @@ import { listen, UnlistenFn } from "@tauri-apps/api/event";
 type FrontendStatus = "loading" | "ready" | "recording" | "processing";
 type FlowState = "idle" | "recording" | "processing" | "completed" | "error" | "cancelled";
 type WaveformChunkPayload = { bins: number[]; avgRms?: number; avg_rms?: number };
+type PartialTranscriptPayload = { index: number; text: string };
+export type FlowError = { kind: string; message: string };
+export type DeviceEvent =
+  | { kind: "lost"; message: string }
+  | { kind: "reconnected"; device: string }
+  | { kind: "unavailable" };
 
 interface UseBackendListenersProps {
   insertMode: boolean;
@@ -15,6 +21,11 @@ interface UseBackendListenersProps {
   setTranscriptionText: (text: string) => void;
   setLayoutMode: (mode: "expanded" | "collapsed" | "h-collapsed") => void;
   setRetryVisible: (visible: boolean) => void;
+  setFlowError?: (error: FlowError | null) => void;
+  setRewriteFailure?: (error: FlowError | null) => void;
+  setDeviceEvent?: (event: DeviceEvent | null) => void;
+  setPartialTranscripts?: (update: (prev: string[]) => string[]) => void;
+  setPartialRewrite?: (text: string) => void;
   copyToClipboard: (text: string) => Promise<void>;
   textareaRef: React.RefObject<HTMLTextAreaElement | null>;
   addSmartSpacing: (text: string, insertPosition: number, fullText: string) => { text: string; adjustedPosition: number };
@@ -34,6 +45,11 @@ export function useBackendListeners({
   setTranscriptionText,
   setLayoutMode,
   setRetryVisible,
+  setFlowError,
+  setRewriteFailure,
+  setDeviceEvent,
+  setPartialTranscripts,
+  setPartialRewrite,
   copyToClipboard,
   textareaRef,
   addSmartSpacing,
@@ -87,6 +103,43 @@ export function useBackendListeners({
           })
         );
 
+        // Live transcript of a recorded segment
+        unsubs.push(
+          await listen<PartialTranscriptPayload>("partial-transcript", (event) => {
+            if (!mounted || !setPartialTranscripts) return;
+            const { index, text } = event.payload;
+            setPartialTranscripts((prev) => {
+              const next = [...prev];
+              next[index] = text;
+              return next;
+            });
+          })
+        );
+
+        // Rewritten text so far while the rewrite streams
+        unsubs.push(
+          await listen<string>("partial-rewrite", (event) => {
+            if (!mounted || !setPartialRewrite) return;
+            setPartialRewrite(event.payload || "");
+          })
+        );
+
+        // Rewrite failed; the raw transcript is used unless the flow fails too
+        unsubs.push(
+          await listen<FlowError>("rewrite-failed", (event) => {
+            if (!mounted || !setRewriteFailure) return;
+            setRewriteFailure(event.payload);
+          })
+        );
+
+        // Microphone lost while recording, and where recording continued
+        unsubs.push(
+          await listen<DeviceEvent>("input-device", (event) => {
+            if (!mounted || !setDeviceEvent) return;
+            setDeviceEvent(event.payload);
+          })
+        );
+
         // Transcription result
         unsubs.push(
           await listen<string>("transcription-result", async (event) => {
@@ -141,9 +194,13 @@ export function useBackendListeners({
 
         // Flow error
         unsubs.push(
-          await listen<any>("flow-error", () => {
+          await listen<FlowError>("flow-error", (event) => {
             if (!mounted) return;
             setStatus("ready");
+            // Cancelling is reported as an error kind but is not a failure
+            if (setFlowError && event.payload?.kind !== "cancelled") {
+              setFlowError(event.payload);
+            }
           })
         );
 
@@ -180,6 +237,11 @@ export function useBackendListeners({
     setTranscriptionText,
     setLayoutMode,
     setRetryVisible,
+    setFlowError,
+    setRewriteFailure,
+    setDeviceEvent,
+    setPartialTranscripts,
+    setPartialRewrite,
     copyToClipboard,
     textareaRef,
     addSmartSpacing,
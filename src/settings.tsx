@@ -1,6 +1,7 @@
 import { useEffect, useState } from "react";
 import { invoke } from "@tauri-apps/api/core";
 import { listen } from "@tauri-apps/api/event";
+import { FlowError } from "./hooks/useBackendListeners";
 
 interface RewritePrompt {
   id: string;
@@ -8,33 +9,318 @@ interface RewritePrompt {
   text: string;
 }
 
+interface BackendSettings {
+  base_url: string;
+  api_key: string;
+  extra_headers: Record<string, string>;
+}
+
+interface FallbackModel {
+  backend: string;
+  model: string;
+}
+
+interface RewriteSettings {
+  provider: string;
+  model: string;
+  base_url: string;
+  api_key: string;
+  temperature: number | null;
+  reasoning_effort: string;
+  service_tier: string;
+}
+
+interface DictationRules {
+  comma: boolean;
+  period: boolean;
+  question_mark: boolean;
+  exclamation_mark: boolean;
+  colon: boolean;
+  semicolon: boolean;
+  new_line: boolean;
+  new_paragraph: boolean;
+  spelling: boolean;
+  camel_case: boolean;
+  title_case: boolean;
+  no_space: boolean;
+}
+
+const DICTATION_RULES: { key: keyof DictationRules; label: string; example: string }[] = [
+  { key: "comma", label: "Comma", example: "\"comma\" → ," },
+  { key: "period", label: "Period", example: "\"period\", \"full stop\" → ." },
+  { key: "question_mark", label: "Question Mark", example: "\"question mark\" → ?" },
+  { key: "exclamation_mark", label: "Exclamation Mark", example: "\"exclamation mark\" → !" },
+  { key: "colon", label: "Colon", example: "\"colon\" → :" },
+  { key: "semicolon", label: "Semicolon", example: "\"semicolon\" → ;" },
+  { key: "new_line", label: "New Line", example: "\"new line\"" },
+  { key: "new_paragraph", label: "New Paragraph", example: "\"new paragraph\"" },
+  { key: "spelling", label: "Phonetic Spelling", example: "\"alpha bravo charlie\" → ABC" },
+  { key: "camel_case", label: "Camel Case", example: "\"camel case user name\" → userName" },
+  { key: "title_case", label: "Title Case", example: "\"title case user name\" → UserName" },
+  { key: "no_space", label: "No Space", example: "\"no space hello there\" → hellothere" },
+];
+
+type MatchKind = "literal" | "whole_word" | "regex";
+
+interface ReplacementRule {
+  id: string;
+  find: string;
+  replace: string;
+  kind: MatchKind;
+  case_sensitive: boolean;
+}
+
+const EMPTY_RULE: ReplacementRule = { id: "", find: "", replace: "", kind: "whole_word", case_sensitive: false };
+
+interface AppProfile {
+  app: string;
+  prompt_id: string;
+  rewrite_enabled: boolean;
+  output_mode: string;
+}
+
+interface FocusedApp {
+  classes: string[];
+  process: string | null;
+}
+
+interface InputConfig {
+  channels: number;
+  min_sample_rate: number;
+  max_sample_rate: number;
+  sample_format: string;
+}
+
+interface InputDevice {
+  name: string;
+  is_default: boolean;
+  configs: InputConfig[];
+}
+
+const describeConfig = (config: InputConfig) => {
+  const rate =
+    config.min_sample_rate === config.max_sample_rate
+      ? `${config.min_sample_rate} Hz`
+      : `${config.min_sample_rate}–${config.max_sample_rate} Hz`;
+  return `${config.channels} ch, ${rate}, ${config.sample_format}`;
+};
+
+interface UsageTotal {
+  period: string;
+  flows: number;
+  audio_secs: number;
+  input_tokens: number;
+  output_tokens: number;
+  cost_usd: number;
+}
+
 interface Options {
+  transcription_backend: string;
+  backend_settings: Record<string, BackendSettings>;
   model: string;
+  fallback_models: FallbackModel[];
+  allow_remote_fallback: boolean;
+  language: string;
+  transcription_prompt: string;
+  context_entries: number;
+  context_window_minutes: number;
+  transcription_mode: string;
+  max_retries: number;
+  retry_initial_delay_ms: number;
+  monthly_budget_usd: number;
   rewrite_enabled: boolean;
+  rewrite_settings: RewriteSettings;
+  stream_rewrite: boolean;
+  rewrite_failure_policy: string;
+  translate_to_english: boolean;
+  rewrite_target_language: string;
   omit_final_punctuation: boolean;
+  dictation_rules: DictationRules;
+  live_transcription: boolean;
+  input_device: string;
+  input_device_fallbacks: string[];
+  disconnect_policy: string;
   selected_prompt_id: string;
   custom_prompts: RewritePrompt[];
   api_key: string;
   api_key_from_env: boolean;
   shortcuts: string;
+  command_shortcut: string;
   output_mode: string;
+  voice_edits: boolean;
+  app_profiles: AppProfile[];
+}
+
+function formatUsage(total: UsageTotal | null): string {
+  if (!total) return "$0.00";
+  const minutes = (total.audio_secs / 60).toFixed(1);
+  return `$${total.cost_usd.toFixed(2)} (${total.flows} recordings, ${minutes} min)`;
 }
 
 export default function Settings() {
   const [options, setOptions] = useState<Options>({
+    transcription_backend: "openai",
+    backend_settings: {},
     model: "whisper-1",
+    fallback_models: [],
+    allow_remote_fallback: false,
+    language: "auto",
+    transcription_prompt: "",
+    context_entries: 0,
+    context_window_minutes: 5,
+    transcription_mode: "text",
+    max_retries: 3,
+    retry_initial_delay_ms: 1000,
+    monthly_budget_usd: 0,
     rewrite_enabled: false,
+    rewrite_settings: {
+      provider: "openai_responses",
+      model: "gpt-5.4",
+      base_url: "",
+      api_key: "",
+      temperature: null,
+      reasoning_effort: "minimal",
+      service_tier: "priority",
+    },
+    translate_to_english: false,
+    rewrite_target_language: "",
+    stream_rewrite: false,
+    rewrite_failure_policy: "fallback_warn",
     omit_final_punctuation: false,
+    dictation_rules: {
+      comma: false,
+      period: false,
+      question_mark: false,
+      exclamation_mark: false,
+      colon: false,
+      semicolon: false,
+      new_line: false,
+      new_paragraph: false,
+      spelling: false,
+      camel_case: false,
+      title_case: false,
+      no_space: false,
+    },
+    live_transcription: false,
+    input_device: "",
+    input_device_fallbacks: [],
+    disconnect_policy: "reconnect",
     selected_prompt_id: "default",
     custom_prompts: [],
     api_key: "",
     api_key_from_env: false,
     shortcuts: "Alt+Slash",
+    command_shortcut: "Alt+Shift+Slash",
     output_mode: "clipboard",
+    voice_edits: false,
+    app_profiles: [],
   });
   const [loading, setLoading] = useState(true);
   const [saving, setSaving] = useState(false);
+  const [saveError, setSaveError] = useState<string | null>(null);
   const [expandedPromptId, setExpandedPromptId] = useState<string | null>(null);
+  const [connectionStatus, setConnectionStatus] = useState<string | null>(null);
+  const [usageToday, setUsageToday] = useState<UsageTotal | null>(null);
+  const [usageMonth, setUsageMonth] = useState<UsageTotal | null>(null);
+  const [lastFocusedApp, setLastFocusedApp] = useState<FocusedApp | null>(null);
+  const [inputDevices, setInputDevices] = useState<InputDevice[]>([]);
+  const [replacementRules, setReplacementRules] = useState<ReplacementRule[]>([]);
+  const [newRule, setNewRule] = useState<ReplacementRule>(EMPTY_RULE);
+  const [ruleError, setRuleError] = useState<string | null>(null);
+  const [ruleSample, setRuleSample] = useState("");
+  const [ruleSampleResult, setRuleSampleResult] = useState<string | null>(null);
+
+  const currentBackendSettings: BackendSettings =
+    options.backend_settings[options.transcription_backend] ?? { base_url: "", api_key: "", extra_headers: {} };
+
+  const updateBackendSettings = (patch: Partial<BackendSettings>) => {
+    setOptions({
+      ...options,
+      backend_settings: {
+        ...options.backend_settings,
+        [options.transcription_backend]: { ...currentBackendSettings, ...patch },
+      },
+    });
+  };
+
+  const updateRewriteSettings = (patch: Partial<RewriteSettings>) => {
+    setOptions({ ...options, rewrite_settings: { ...options.rewrite_settings, ...patch } });
+  };
+
+  const updateFallback = (index: number, patch: Partial<FallbackModel>) => {
+    setOptions({
+      ...options,
+      fallback_models: options.fallback_models.map((f, i) => (i === index ? { ...f, ...patch } : f)),
+    });
+  };
+
+  const updateProfile = (index: number, patch: Partial<AppProfile>) => {
+    setOptions({
+      ...options,
+      app_profiles: options.app_profiles.map((p, i) => (i === index ? { ...p, ...patch } : p)),
+    });
+  };
+
+  // Replacement rules are saved as soon as they are added or removed
+  const handleAddRule = async () => {
+    try {
+      const rule = await invoke<ReplacementRule>("add_replacement_rule", { rule: newRule });
+      setReplacementRules([...replacementRules, rule]);
+      setNewRule(EMPTY_RULE);
+      setRuleError(null);
+    } catch (e) {
+      setRuleError(String(e));
+    }
+  };
+
+  const handleRemoveRule = async (id: string) => {
+    try {
+      await invoke("remove_replacement_rule", { id });
+      setReplacementRules(replacementRules.filter((r) => r.id !== id));
+    } catch (e) {
+      setRuleError(String(e));
+    }
+  };
+
+  const handleTestRules = async () => {
+    try {
+      // Tests the rule being written if there is one, otherwise the saved rules
+      const rule = newRule.find ? newRule : null;
+      setRuleSampleResult(await invoke<string>("test_replacement_rules", { text: ruleSample, rule }));
+      setRuleError(null);
+    } catch (e) {
+      setRuleSampleResult(null);
+      setRuleError(String(e));
+    }
+  };
+
+  const headersToText = (headers: Record<string, string>) =>
+    Object.entries(headers).map(([name, value]) => `${name}: ${value}`).join("\n");
+
+  const textToHeaders = (text: string) => {
+    const headers: Record<string, string> = {};
+    for (const line of text.split("\n")) {
+      const idx = line.indexOf(":");
+      if (idx > 0) {
+        headers[line.slice(0, idx).trim()] = line.slice(idx + 1).trim();
+      }
+    }
+    return headers;
+  };
+
+  const handleTestConnection = async () => {
+    setConnectionStatus("Testing...");
+    try {
+      const result = await invoke<string>("test_transcription_backend", {
+        backend: options.transcription_backend,
+        settings: currentBackendSettings,
+        model: options.model,
+      });
+      setConnectionStatus(result);
+    } catch (e) {
+      setConnectionStatus(`Failed: ${(e as FlowError).message}`);
+    }
+  };
 
   const allPrompts = options.custom_prompts.length > 0 
     ? options.custom_prompts 
@@ -46,6 +332,21 @@ export default function Settings() {
         }
       ];
 
+  const loadInputDevices = () => {
+    invoke<InputDevice[]>("list_input_devices")
+      .then(setInputDevices)
+      .catch((e) => console.error("Failed to list input devices:", e));
+  };
+
+  const updateInputFallback = (index: number, name: string) => {
+    const fallbacks = [...options.input_device_fallbacks];
+    fallbacks[index] = name;
+    setOptions({ ...options, input_device_fallbacks: fallbacks });
+  };
+
+  const selectedInputDevice = inputDevices.find((d) => d.name === options.input_device);
+  const isConnected = (name: string) => inputDevices.some((d) => d.name.toLowerCase() === name.trim().toLowerCase());
+
   useEffect(() => {
     const loadOptions = async () => {
       try {
@@ -60,6 +361,36 @@ export default function Settings() {
 
     loadOptions();
 
+    const loadUsage = async () => {
+      // Group by the local calendar rather than UTC
+      const utcOffsetMinutes = -new Date().getTimezoneOffset();
+      try {
+        const [days, months] = await Promise.all([
+          invoke<UsageTotal[]>("get_usage_totals", { period: "day", utcOffsetMinutes }),
+          invoke<UsageTotal[]>("get_usage_totals", { period: "month", utcOffsetMinutes }),
+        ]);
+        const now = new Date();
+        const month = `${now.getFullYear()}-${String(now.getMonth() + 1).padStart(2, "0")}`;
+        const today = `${month}-${String(now.getDate()).padStart(2, "0")}`;
+        setUsageToday(days.find((t) => t.period === today) ?? null);
+        setUsageMonth(months.find((t) => t.period === month) ?? null);
+      } catch (e) {
+        console.error("Failed to load usage:", e);
+      }
+    };
+
+    loadUsage();
+
+    invoke<ReplacementRule[]>("list_replacement_rules")
+      .then(setReplacementRules)
+      .catch((e) => console.error("Failed to load replacement rules:", e));
+
+    loadInputDevices();
+
+    invoke<FocusedApp | null>("get_last_focused_app")
+      .then(setLastFocusedApp)
+      .catch((e) => console.error("Failed to load last focused app:", e));
+
     const setupListener = async () => {
       const unlisten = await listen<{ full: Options }>("options-changed", (event) => {
         setOptions(event.payload.full);
@@ -79,25 +410,52 @@ export default function Settings() {
 
   const handleSave = async () => {
     setSaving(true);
+    setSaveError(null);
     try {
       const customPromptsOnly = options.custom_prompts.filter(p => p.id !== "default");
       
       await invoke("update_options", {
         patch: {
+          backend_settings: options.backend_settings,
+          transcription_backend: options.transcription_backend,
           model: options.model,
+          fallback_models: options.fallback_models,
+          allow_remote_fallback: options.allow_remote_fallback,
+          language: options.language,
+          transcription_prompt: options.transcription_prompt,
+          context_entries: options.context_entries,
+          context_window_minutes: options.context_window_minutes,
+          transcription_mode: options.transcription_mode,
+          max_retries: options.max_retries,
+          retry_initial_delay_ms: options.retry_initial_delay_ms,
+          monthly_budget_usd: options.monthly_budget_usd,
           rewrite_enabled: options.rewrite_enabled,
+          rewrite_settings: options.rewrite_settings,
+          stream_rewrite: options.stream_rewrite,
+          rewrite_failure_policy: options.rewrite_failure_policy,
+          translate_to_english: options.translate_to_english,
+          rewrite_target_language: options.rewrite_target_language,
           omit_final_punctuation: options.omit_final_punctuation,
+          dictation_rules: options.dictation_rules,
+          live_transcription: options.live_transcription,
+          input_device: options.input_device,
+          input_device_fallbacks: options.input_device_fallbacks,
+          disconnect_policy: options.disconnect_policy,
           selected_prompt_id: options.selected_prompt_id,
           custom_prompts: customPromptsOnly,
           api_key: options.api_key,
           shortcuts: options.shortcuts,
+          command_shortcut: options.command_shortcut,
           output_mode: options.output_mode,
+          voice_edits: options.voice_edits,
+          app_profiles: options.app_profiles,
         },
       });
 
       await invoke("close_settings_window");
     } catch (e) {
       console.error("Failed to save options:", e);
+      setSaveError((e as FlowError).message);
       setSaving(false);
     }
   };
@@ -123,7 +481,7 @@ export default function Settings() {
     const newPrompt: RewritePrompt = {
       id: newId,
       name: "New Prompt",
-      text: "Enter your custom rewrite prompt here. Use {} as a placeholder for the transcribed text.",
+      text: "Enter your custom rewrite prompt here.\n\nOriginal text: {{transcript}}",
     };
     setOptions({
       ...options,
@@ -199,16 +557,373 @@ export default function Settings() {
 
       <div className="settings-section">
         <label className="settings-label">
-          Transcription Model
+          Voice Command Shortcut
+          <input
+            type="text"
+            className="settings-input"
+            value={options.command_shortcut}
+            onChange={(e) => setOptions({ ...options, command_shortcut: e.target.value })}
+            placeholder="Alt+Shift+Slash"
+          />
+        </label>
+        <p className="settings-hint">Rewrites the selected text by voice; takes effect after a restart</p>
+      </div>
+
+      <div className="settings-section">
+        <label className="settings-label">
+          Microphone
           <select
             className="settings-select"
+            value={options.input_device}
+            onChange={(e) => setOptions({ ...options, input_device: e.target.value })}
+          >
+            <option value="">System Default</option>
+            {inputDevices.map((device) => (
+              <option key={device.name} value={device.name}>
+                {device.is_default ? `${device.name} (default)` : device.name}
+              </option>
+            ))}
+            {options.input_device && !isConnected(options.input_device) && (
+              <option value={options.input_device}>{options.input_device} (not connected)</option>
+            )}
+          </select>
+        </label>
+        {selectedInputDevice && selectedInputDevice.configs.length > 0 && (
+          <p className="settings-hint">Supports {selectedInputDevice.configs.map(describeConfig).join("; ")}</p>
+        )}
+        <span className="settings-label">Fallback Microphones</span>
+        {options.input_device_fallbacks.map((name, index) => (
+          <div key={index} className="fallback-row">
+            <input
+              type="text"
+              className="settings-input"
+              list="input-devices"
+              value={name}
+              onChange={(e) => updateInputFallback(index, e.target.value)}
+            />
+            {name.trim() && !isConnected(name) && <span className="settings-hint">Not connected</span>}
+            <button
+              className="prompt-delete-btn"
+              onClick={() =>
+                setOptions({
+                  ...options,
+                  input_device_fallbacks: options.input_device_fallbacks.filter((_, i) => i !== index),
+                })
+              }
+            >
+              Remove
+            </button>
+          </div>
+        ))}
+        <datalist id="input-devices">
+          {inputDevices.map((device) => (
+            <option key={device.name} value={device.name} />
+          ))}
+        </datalist>
+        <button
+          className="add-prompt-btn"
+          onClick={() => setOptions({ ...options, input_device_fallbacks: [...options.input_device_fallbacks, ""] })}
+        >
+          + Add Fallback
+        </button>
+        <button className="add-prompt-btn" onClick={loadInputDevices}>
+          Refresh Devices
+        </button>
+        <p className="settings-hint">
+          Fallbacks are tried in order when the microphone above is not connected, then the system default
+        </p>
+        <label className="settings-label">
+          When the Microphone Disconnects
+          <select
+            className="settings-select"
+            value={options.disconnect_policy}
+            onChange={(e) => setOptions({ ...options, disconnect_policy: e.target.value })}
+          >
+            <option value="reconnect">Continue on the next available microphone</option>
+            <option value="stop">Stop and transcribe what was recorded</option>
+            <option value="fail">Fail the recording</option>
+          </select>
+        </label>
+      </div>
+
+      <div className="settings-section">
+        <label className="settings-label">
+          Transcription Backend
+          <select
+            className="settings-select"
+            value={options.transcription_backend}
+            onChange={(e) => setOptions({ ...options, transcription_backend: e.target.value })}
+          >
+            <option value="openai">OpenAI</option>
+            <option value="openai_compatible">OpenAI-compatible Server</option>
+            <option value="local">Local (whisper.cpp)</option>
+          </select>
+        </label>
+        {options.transcription_backend === "local" && (
+          <p className="settings-hint">
+            Place ggml model files (e.g. ggml-base.en.bin) in the models folder next to settings.json
+            and enter the file name as the model. Requires a build with the local-whisper feature.
+          </p>
+        )}
+      </div>
+
+      {options.transcription_backend === "openai_compatible" && (
+        <div className="settings-section">
+          <label className="settings-label">
+            Base URL
+            <input
+              type="text"
+              className="settings-input"
+              value={currentBackendSettings.base_url}
+              onChange={(e) => updateBackendSettings({ base_url: e.target.value })}
+              placeholder="http://localhost:8000/v1"
+            />
+          </label>
+          <label className="settings-label">
+            Server API Key
+            <input
+              type="password"
+              className="settings-input"
+              value={currentBackendSettings.api_key}
+              onChange={(e) => updateBackendSettings({ api_key: e.target.value })}
+              placeholder="Optional"
+            />
+          </label>
+          <label className="settings-label">
+            Extra Headers
+            <textarea
+              className="prompt-text-input"
+              value={headersToText(currentBackendSettings.extra_headers)}
+              onChange={(e) => updateBackendSettings({ extra_headers: textToHeaders(e.target.value) })}
+              placeholder="X-Header-Name: value"
+              rows={3}
+            />
+          </label>
+        </div>
+      )}
+
+      <div className="settings-section">
+        <label className="settings-label">
+          Transcription Model
+          <input
+            type="text"
+            className="settings-input"
+            list="transcription-models"
             value={options.model}
             onChange={(e) => setOptions({ ...options, model: e.target.value })}
-          >
+          />
+          <datalist id="transcription-models">
             <option value="whisper-1">Whisper</option>
             <option value="gpt-4o-transcribe">GPT-4o Transcribe</option>
+            <option value="gpt-4o-transcribe-diarize">GPT-4o Transcribe Diarize</option>
+          </datalist>
+        </label>
+        <button className="settings-btn settings-btn-secondary" onClick={handleTestConnection}>
+          Test Connection
+        </button>
+        {connectionStatus && <p className="settings-hint">{connectionStatus}</p>}
+      </div>
+
+      <div className="settings-section">
+        <span className="settings-label">Fallback Models</span>
+        {options.fallback_models.map((fallback, index) => (
+          <div key={index} className="fallback-row">
+            <select
+              className="settings-select"
+              value={fallback.backend}
+              onChange={(e) => updateFallback(index, { backend: e.target.value })}
+            >
+              <option value="openai">OpenAI</option>
+              <option value="openai_compatible">OpenAI-compatible Server</option>
+              <option value="local">Local (whisper.cpp)</option>
+            </select>
+            <input
+              type="text"
+              className="settings-input"
+              list="transcription-models"
+              value={fallback.model}
+              onChange={(e) => updateFallback(index, { model: e.target.value })}
+            />
+            <button
+              className="prompt-delete-btn"
+              onClick={() =>
+                setOptions({ ...options, fallback_models: options.fallback_models.filter((_, i) => i !== index) })
+              }
+            >
+              Remove
+            </button>
+          </div>
+        ))}
+        <button
+          className="add-prompt-btn"
+          onClick={() =>
+            setOptions({
+              ...options,
+              fallback_models: [...options.fallback_models, { backend: "openai", model: "whisper-1" }],
+            })
+          }
+        >
+          + Add Fallback
+        </button>
+        <p className="settings-hint">
+          Tried in order on the same recording when the model above fails. Server backends use the URL and key
+          saved for them above.
+        </p>
+        {options.transcription_backend === "local" && (
+          <>
+            <label className="settings-checkbox-label">
+              <input
+                type="checkbox"
+                className="settings-checkbox"
+                checked={options.allow_remote_fallback}
+                onChange={(e) => setOptions({ ...options, allow_remote_fallback: e.target.checked })}
+              />
+              <span>Allow Server Fallbacks</span>
+            </label>
+            <p className="settings-hint">Uploads the recording when local transcription fails</p>
+          </>
+        )}
+      </div>
+
+      <div className="settings-section">
+        <label className="settings-label">
+          Language
+          <input
+            type="text"
+            className="settings-input"
+            list="transcription-languages"
+            value={options.language}
+            onChange={(e) => setOptions({ ...options, language: e.target.value.trim().toLowerCase() })}
+          />
+          <datalist id="transcription-languages">
+            <option value="auto">Auto-detect</option>
+            <option value="en">English</option>
+            <option value="de">German</option>
+            <option value="fr">French</option>
+            <option value="es">Spanish</option>
+            <option value="it">Italian</option>
+            <option value="pt">Portuguese</option>
+            <option value="nl">Dutch</option>
+            <option value="ja">Japanese</option>
+            <option value="zh">Chinese</option>
+          </datalist>
+        </label>
+        <p className="settings-hint">"auto" or a two-letter ISO-639-1 code</p>
+        <label className="settings-label">
+          Vocabulary
+          <textarea
+            className="prompt-text-input"
+            value={options.transcription_prompt}
+            onChange={(e) => setOptions({ ...options, transcription_prompt: e.target.value })}
+            placeholder="Product names, colleagues' names, jargon..."
+            rows={3}
+          />
+        </label>
+        <p className="settings-hint">Sent with every transcription to help spell names and terms correctly</p>
+      </div>
+
+      <div className="settings-section">
+        <label className="settings-label">
+          Context Entries
+          <input
+            type="number"
+            className="settings-input"
+            min={0}
+            max={10}
+            value={options.context_entries}
+            onChange={(e) => setOptions({ ...options, context_entries: Math.max(0, Number(e.target.value) || 0) })}
+          />
+        </label>
+        <label className="settings-label">
+          Context Expiry (minutes)
+          <input
+            type="number"
+            className="settings-input"
+            min={1}
+            max={120}
+            value={options.context_window_minutes}
+            onChange={(e) =>
+              setOptions({ ...options, context_window_minutes: Math.max(1, Number(e.target.value) || 1) })
+            }
+          />
+        </label>
+        <p className="settings-hint">
+          Recent transcriptions sent with the next one so sentences continue naturally; 0 turns this off.
+          Older transcriptions are never used.
+        </p>
+      </div>
+
+      <div className="settings-section">
+        <label className="settings-label">
+          Transcription Mode
+          <select
+            className="settings-select"
+            value={options.transcription_mode}
+            onChange={(e) => setOptions({ ...options, transcription_mode: e.target.value })}
+          >
+            <option value="text">Text Only</option>
+            <option value="timestamps">Text with Timestamps</option>
+            <option value="diarized">Speaker Labels</option>
           </select>
         </label>
+        <p className="settings-hint">
+          {options.transcription_mode === "timestamps"
+            ? "Keeps word timings so history can play the recording from any word (OpenAI: whisper-1 only)"
+            : options.transcription_mode === "diarized"
+            ? "Labels who is speaking, one line per turn (needs gpt-4o-transcribe-diarize; vocabulary is not sent)"
+            : "Returns plain text"}
+        </p>
+      </div>
+
+      <div className="settings-section">
+        <label className="settings-label">
+          Retries
+          <input
+            type="number"
+            className="settings-input"
+            min={0}
+            max={10}
+            value={options.max_retries}
+            onChange={(e) => setOptions({ ...options, max_retries: Math.max(0, Number(e.target.value) || 0) })}
+          />
+        </label>
+        <label className="settings-label">
+          First Retry Delay (ms)
+          <input
+            type="number"
+            className="settings-input"
+            min={0}
+            max={30000}
+            step={250}
+            value={options.retry_initial_delay_ms}
+            onChange={(e) =>
+              setOptions({ ...options, retry_initial_delay_ms: Math.max(0, Number(e.target.value) || 0) })
+            }
+          />
+        </label>
+        <p className="settings-hint">
+          Timeouts, rate limits, network and server errors are retried, doubling the delay each time
+        </p>
+      </div>
+
+      <div className="settings-section">
+        <label className="settings-label">
+          Monthly Budget (USD)
+          <input
+            type="number"
+            className="settings-input"
+            min={0}
+            step={1}
+            value={options.monthly_budget_usd}
+            onChange={(e) =>
+              setOptions({ ...options, monthly_budget_usd: Math.max(0, Number(e.target.value) || 0) })
+            }
+          />
+        </label>
+        <p className="settings-hint">
+          Recording is blocked once this month's estimated cost reaches the budget; 0 means no limit.
+          Today: {formatUsage(usageToday)}. This month: {formatUsage(usageMonth)}.
+        </p>
       </div>
 
       <div className="settings-section">
@@ -228,6 +943,99 @@ export default function Settings() {
             ? "Simulates keyboard input into the focused window (requires Accessibility permission)"
             : "Copies transcription to clipboard"}
         </p>
+        {options.output_mode === "type" && (
+          <>
+            <label className="settings-checkbox-label">
+              <input
+                type="checkbox"
+                className="settings-checkbox"
+                checked={options.voice_edits}
+                onChange={(e) => setOptions({ ...options, voice_edits: e.target.checked })}
+              />
+              <span>Voice Edit Commands</span>
+            </label>
+            <p className="settings-hint">
+              Start a recording with "scratch that", "replace X with Y" or "capitalize that" to edit the text
+              typed before it
+            </p>
+          </>
+        )}
+      </div>
+
+      <div className="settings-section">
+        <span className="settings-label">App Profiles</span>
+        {options.app_profiles.map((profile, index) => (
+          <div key={index} className="fallback-row">
+            <input
+              type="text"
+              className="settings-input"
+              placeholder="Application"
+              value={profile.app}
+              onChange={(e) => updateProfile(index, { app: e.target.value })}
+            />
+            <select
+              className="settings-select"
+              value={profile.prompt_id}
+              onChange={(e) => updateProfile(index, { prompt_id: e.target.value })}
+            >
+              {allPrompts.map((prompt) => (
+                <option key={prompt.id} value={prompt.id}>
+                  {prompt.name}
+                </option>
+              ))}
+            </select>
+            <label className="settings-checkbox-label">
+              <input
+                type="checkbox"
+                className="settings-checkbox"
+                checked={profile.rewrite_enabled}
+                onChange={(e) => updateProfile(index, { rewrite_enabled: e.target.checked })}
+              />
+              <span>Rewrite</span>
+            </label>
+            <select
+              className="settings-select"
+              value={profile.output_mode}
+              onChange={(e) => updateProfile(index, { output_mode: e.target.value })}
+            >
+              <option value="clipboard">Clipboard</option>
+              <option value="type">Type</option>
+            </select>
+            <button
+              className="prompt-delete-btn"
+              onClick={() =>
+                setOptions({ ...options, app_profiles: options.app_profiles.filter((_, i) => i !== index) })
+              }
+            >
+              Remove
+            </button>
+          </div>
+        ))}
+        <button
+          className="add-prompt-btn"
+          onClick={() =>
+            setOptions({
+              ...options,
+              app_profiles: [
+                ...options.app_profiles,
+                {
+                  app: lastFocusedApp?.classes[lastFocusedApp.classes.length - 1] ?? lastFocusedApp?.process ?? "",
+                  prompt_id: options.selected_prompt_id,
+                  rewrite_enabled: options.rewrite_enabled,
+                  output_mode: options.output_mode,
+                },
+              ],
+            })
+          }
+        >
+          + Add Profile
+        </button>
+        <p className="settings-hint">
+          Used instead of the prompt, rewrite and output settings while dictating into the named application
+          (window class or process name; Linux/X11 only for now).
+          {lastFocusedApp &&
+            ` Last recording: ${[...lastFocusedApp.classes, lastFocusedApp.process].filter(Boolean).join(", ")}.`}
+        </p>
       </div>
 
       <div className="settings-section">
@@ -245,6 +1053,117 @@ export default function Settings() {
         <p className="settings-hint">Remove trailing punctuation from transcriptions</p>
       </div>
 
+      <div className="settings-section">
+        <span className="settings-label">Dictation Commands</span>
+        {DICTATION_RULES.map(({ key, label, example }) => (
+          <label key={key} className="settings-checkbox-label">
+            <input
+              type="checkbox"
+              className="settings-checkbox"
+              checked={options.dictation_rules[key]}
+              onChange={(e) =>
+                setOptions({
+                  ...options,
+                  dictation_rules: { ...options.dictation_rules, [key]: e.target.checked },
+                })
+              }
+            />
+            <span>
+              {label}: {example}
+            </span>
+          </label>
+        ))}
+        <p className="settings-hint">
+          Applied instantly on this device before any rewrite, so simple dictation can skip the rewrite model. "Period" and "colon" only count at the end of a phrase
+        </p>
+      </div>
+
+      <div className="settings-section">
+        <span className="settings-label">Replacements</span>
+        {replacementRules.map((rule) => (
+          <div key={rule.id} className="fallback-row">
+            <span className="settings-hint">
+              {rule.find} → {rule.replace} ({rule.kind.replace("_", " ")}
+              {rule.case_sensitive ? ", match case" : ""})
+            </span>
+            <button className="prompt-delete-btn" onClick={() => handleRemoveRule(rule.id)}>
+              Remove
+            </button>
+          </div>
+        ))}
+        <div className="fallback-row">
+          <input
+            type="text"
+            className="settings-input"
+            placeholder="Find"
+            value={newRule.find}
+            onChange={(e) => setNewRule({ ...newRule, find: e.target.value })}
+          />
+          <input
+            type="text"
+            className="settings-input"
+            placeholder="Replace with"
+            value={newRule.replace}
+            onChange={(e) => setNewRule({ ...newRule, replace: e.target.value })}
+          />
+          <select
+            className="settings-select"
+            value={newRule.kind}
+            onChange={(e) => setNewRule({ ...newRule, kind: e.target.value as MatchKind })}
+          >
+            <option value="whole_word">Whole word</option>
+            <option value="literal">Anywhere</option>
+            <option value="regex">Regex</option>
+          </select>
+          <label className="settings-checkbox-label">
+            <input
+              type="checkbox"
+              className="settings-checkbox"
+              checked={newRule.case_sensitive}
+              onChange={(e) => setNewRule({ ...newRule, case_sensitive: e.target.checked })}
+            />
+            <span>Match case</span>
+          </label>
+        </div>
+        <button className="add-prompt-btn" onClick={handleAddRule} disabled={!newRule.find}>
+          + Add Replacement
+        </button>
+        <div className="fallback-row">
+          <input
+            type="text"
+            className="settings-input"
+            placeholder="Try on sample text"
+            value={ruleSample}
+            onChange={(e) => setRuleSample(e.target.value)}
+          />
+          <button className="settings-btn settings-btn-secondary" onClick={handleTestRules}>
+            Test
+          </button>
+        </div>
+        {ruleSampleResult !== null && <p className="settings-hint">{ruleSampleResult}</p>}
+        {ruleError && <p className="settings-hint settings-error">{ruleError}</p>}
+        <p className="settings-hint">
+          Fixes recurring mishearings in every transcription, after any rewrite. Rules apply in order.
+        </p>
+      </div>
+
+      <div className="settings-section">
+        <label className="settings-checkbox-label">
+          <input
+            type="checkbox"
+            className="settings-checkbox"
+            checked={options.live_transcription}
+            onChange={(e) =>
+              setOptions({ ...options, live_transcription: e.target.checked })
+            }
+          />
+          <span>Live Transcription</span>
+        </label>
+        <p className="settings-hint">
+          Transcribe at pauses while recording and show the text as you speak
+        </p>
+      </div>
+
       <div className="settings-section">
         <label className="settings-checkbox-label">
           <input
@@ -258,7 +1177,168 @@ export default function Settings() {
           <span>Rewrite Transcribed Text</span>
         </label>
         <p className="settings-hint">
-          Use GPT-5 to fix dictation artifacts, phonetic spelling, and formatting commands
+          Use a language model to fix dictation artifacts, phonetic spelling, and formatting commands
+        </p>
+      </div>
+
+      <div className="settings-section">
+        <label className="settings-label">
+          Rewrite Provider
+          <select
+            className="settings-select"
+            value={options.rewrite_settings.provider}
+            onChange={(e) => updateRewriteSettings({ provider: e.target.value })}
+          >
+            <option value="openai_responses">OpenAI Responses</option>
+            <option value="chat_completions">Chat Completions (OpenAI, Ollama, ...)</option>
+            <option value="messages">Messages API</option>
+          </select>
+        </label>
+        <label className="settings-label">
+          Rewrite Model
+          <input
+            type="text"
+            className="settings-input"
+            value={options.rewrite_settings.model}
+            onChange={(e) => updateRewriteSettings({ model: e.target.value })}
+            placeholder="gpt-5.4"
+          />
+        </label>
+        <label className="settings-label">
+          Rewrite Base URL
+          <input
+            type="text"
+            className="settings-input"
+            value={options.rewrite_settings.base_url}
+            onChange={(e) => updateRewriteSettings({ base_url: e.target.value })}
+            placeholder={
+              options.rewrite_settings.provider === "messages"
+                ? "https://api.anthropic.com/v1"
+                : "https://api.openai.com/v1 (Ollama: http://localhost:11434/v1)"
+            }
+          />
+        </label>
+        <label className="settings-label">
+          Rewrite API Key
+          <input
+            type="password"
+            className="settings-input"
+            value={options.rewrite_settings.api_key}
+            onChange={(e) => updateRewriteSettings({ api_key: e.target.value })}
+            placeholder="Empty to use the OpenAI key, or none for local servers"
+          />
+        </label>
+        <label className="settings-label">
+          Temperature
+          <input
+            type="number"
+            className="settings-input"
+            min={0}
+            max={2}
+            step={0.1}
+            value={options.rewrite_settings.temperature ?? ""}
+            onChange={(e) =>
+              updateRewriteSettings({
+                temperature: e.target.value === "" ? null : Math.min(2, Math.max(0, Number(e.target.value))),
+              })
+            }
+            placeholder="Server default"
+          />
+        </label>
+        <label className="settings-label">
+          Reasoning Effort
+          <select
+            className="settings-select"
+            value={options.rewrite_settings.reasoning_effort}
+            onChange={(e) => updateRewriteSettings({ reasoning_effort: e.target.value })}
+          >
+            <option value="">Server default</option>
+            <option value="minimal">Minimal</option>
+            <option value="low">Low</option>
+            <option value="medium">Medium</option>
+            <option value="high">High</option>
+          </select>
+        </label>
+        {options.rewrite_settings.provider !== "messages" && (
+          <label className="settings-label">
+            Service Tier
+            <input
+              type="text"
+              className="settings-input"
+              value={options.rewrite_settings.service_tier}
+              onChange={(e) => updateRewriteSettings({ service_tier: e.target.value })}
+              placeholder="Default tier"
+            />
+          </label>
+        )}
+        <p className="settings-hint">
+          Used for rewriting and translation. For Ollama choose Chat Completions, clear the service tier
+          and reasoning effort, and leave the API key empty.
+        </p>
+      </div>
+
+      <div className="settings-section">
+        <label className="settings-checkbox-label">
+          <input
+            type="checkbox"
+            className="settings-checkbox"
+            checked={options.stream_rewrite}
+            onChange={(e) =>
+              setOptions({ ...options, stream_rewrite: e.target.checked })
+            }
+          />
+          <span>Stream Rewrite Output</span>
+        </label>
+        <p className="settings-hint">
+          Show the rewrite as it is generated and, in type mode, type it as it arrives
+        </p>
+      </div>
+
+      <div className="settings-section">
+        <label className="settings-label">
+          When Rewriting Fails
+          <select
+            className="settings-select"
+            value={options.rewrite_failure_policy}
+            onChange={(e) => setOptions({ ...options, rewrite_failure_policy: e.target.value })}
+          >
+            <option value="fallback_warn">Use the transcript and warn</option>
+            <option value="fallback_silent">Use the transcript silently</option>
+            <option value="fail">Fail and keep the recording for retry</option>
+          </select>
+        </label>
+        <p className="settings-hint">
+          Also applies when the rewrite model returns no text
+        </p>
+      </div>
+
+      <div className="settings-section">
+        <label className="settings-checkbox-label">
+          <input
+            type="checkbox"
+            className="settings-checkbox"
+            checked={options.translate_to_english}
+            onChange={(e) =>
+              setOptions({ ...options, translate_to_english: e.target.checked })
+            }
+          />
+          <span>Translate Speech to English</span>
+        </label>
+        <p className="settings-hint">
+          Uses the translations endpoint instead of transcriptions (OpenAI: whisper-1 only)
+        </p>
+        <label className="settings-label">
+          Rewrite Into Language
+          <input
+            type="text"
+            className="settings-input"
+            value={options.rewrite_target_language}
+            onChange={(e) => setOptions({ ...options, rewrite_target_language: e.target.value })}
+            placeholder="e.g. English (empty to keep the spoken language)"
+          />
+        </label>
+        <p className="settings-hint">
+          Runs the rewrite step to translate the text, together with the active prompt if rewriting is on
         </p>
       </div>
 
@@ -323,9 +1403,15 @@ export default function Settings() {
           <button className="add-prompt-btn" onClick={handleAddPrompt}>
             + Add New Prompt
           </button>
+          <p className="settings-hint">
+            Variables: {"{{transcript}}"} (required), {"{{date}}"}, {"{{previous_transcript}}"},{" "}
+            {"{{clipboard}}"}, {"{{language}}"}. Single braces are kept as written.
+          </p>
         </div>
       )}
 
+      {saveError && <p className="settings-hint settings-error">{saveError}</p>}
+
       <div className="settings-actions">
         <button className="settings-btn settings-btn-link" onClick={handleOpenSettingsFolder}>
           Open Settings Folder